@@ -0,0 +1,94 @@
+//! dark and light appearance
+//! the canvas is drawn with a palette matching the iced theme of the widgets, picked from the os appearance setting unless overridden
+
+use std::process::Command;
+
+use circe::config::ThemePreference;
+use iced::{Color, Theme};
+
+/// colors the canvas is drawn with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    /// true for the dark palette, selects the iced theme of the widgets
+    pub dark: bool,
+    /// fill of the canvas
+    pub background: Color,
+    /// grid, origin marker, gizmo, scale bar and operating point annotations
+    pub foreground: Color,
+}
+
+impl Palette {
+    pub const DARK: Palette = Palette {
+        dark: true,
+        background: Color::from_rgb(0.2, 0.2, 0.2),
+        foreground: Color::WHITE,
+    };
+    pub const LIGHT: Palette = Palette {
+        dark: false,
+        background: Color::from_rgb(0.95, 0.95, 0.95),
+        foreground: Color::from_rgb(0.1, 0.1, 0.1),
+    };
+
+    /// returns the palette for preference. Auto follows the os appearance, dark if it cannot be determined.
+    pub fn from_preference(preference: ThemePreference) -> Palette {
+        let dark = match preference {
+            ThemePreference::Auto => os_prefers_dark().unwrap_or(true),
+            ThemePreference::Dark => true,
+            ThemePreference::Light => false,
+        };
+        if dark {Palette::DARK} else {Palette::LIGHT}
+    }
+    /// returns the iced theme of the widgets matching the palette
+    pub fn theme(&self) -> Theme {
+        if self.dark {Theme::Dark} else {Theme::Light}
+    }
+    /// returns the foreground color with opacity alpha
+    pub fn foreground_alpha(&self, alpha: f32) -> Color {
+        Color { a: alpha, ..self.foreground }
+    }
+}
+
+/// query the os appearance setting, none if it cannot be determined
+fn os_prefers_dark() -> Option<bool> {
+    #[cfg(target_family = "windows")]
+    {
+        let out = Command::new("reg")
+            .args(["query", r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize", "/v", "AppsUseLightTheme"])
+            .output().ok()?;
+        if !out.status.success() {
+            return None;
+        }
+        // e.g. "    AppsUseLightTheme    REG_DWORD    0x0"
+        Some(String::from_utf8_lossy(&out.stdout).split_whitespace().last() == Some("0x0"))
+    }
+    #[cfg(target_os = "macos")]
+    {
+        // the key only exists while dark appearance is set
+        let out = Command::new("defaults").args(["read", "-g", "AppleInterfaceStyle"]).output().ok()?;
+        Some(out.status.success() && String::from_utf8_lossy(&out.stdout).trim() == "Dark")
+    }
+    #[cfg(target_os = "linux")]
+    {
+        // e.g. 'prefer-dark', 'prefer-light' or 'default'
+        let out = Command::new("gsettings").args(["get", "org.gnome.desktop.interface", "color-scheme"]).output().ok()?;
+        if !out.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&out.stdout).contains("dark"))
+    }
+    #[cfg(not(any(target_family = "windows", target_os = "macos", target_os = "linux")))]
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn override_ignores_os_appearance() {
+        assert_eq!(Palette::from_preference(ThemePreference::Dark), Palette::DARK);
+        assert_eq!(Palette::from_preference(ThemePreference::Light), Palette::LIGHT);
+        assert_eq!(Palette::LIGHT.theme(), Theme::Light);
+        assert_eq!(Palette::DARK.foreground_alpha(0.5), Color::from_rgba(1.0, 1.0, 1.0, 0.5));
+    }
+}
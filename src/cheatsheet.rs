@@ -0,0 +1,73 @@
+//! hotkey cheat sheet
+//! semi-transparent overlay on the canvas listing the keys currently bound to every action, toggled with ?
+
+use circe::{transforms::{CSBox, CSVec, Point}, keybindings::KeyBindings};
+use iced::{widget::canvas::{self, Frame, Path, Stroke, stroke, Fill, Text}, Color, Size};
+
+use crate::appearance::Palette;
+
+/// text size of the cheat sheet in pixels
+const TEXT_SIZE: f32 = 14.0;
+/// height of a line of the cheat sheet in pixels
+const LINE_HEIGHT: f32 = TEXT_SIZE * 1.3;
+/// distance between the cheat sheet and the canvas edges, and padding around its text, in pixels
+const MARGIN: f32 = 20.0;
+
+/// returns the number of hotkeys listed per column of lines hotkeys, so that the cheat sheet fits a canvas height pixels tall
+fn rows_per_column(height: f32, lines: usize) -> usize {
+    (((height - 4.0 * MARGIN) / LINE_HEIGHT).floor().max(1.0) as usize).min(lines.max(1))
+}
+
+/// draw the cheat sheet of key_bindings centered on the canvas of bounds csb.
+/// Hotkeys which do not fit the canvas height continue in another column.
+pub fn draw(frame: &mut Frame, csb: CSBox, key_bindings: &KeyBindings, palette: &Palette) {
+    let hotkeys = key_bindings.cheat_sheet();
+    let rows = rows_per_column(csb.height(), hotkeys.len());
+    let key_chars = hotkeys.iter().map(|(k, _)| k.chars().count()).max().unwrap_or(0);
+    let action_chars = hotkeys.iter().map(|(_, a)| a.chars().count()).max().unwrap_or(0);
+    // monospace estimate, as for the device tooltip
+    let key_width = (key_chars + 2) as f32 * TEXT_SIZE * 0.6;
+    let column_width = key_width + (action_chars + 2) as f32 * TEXT_SIZE * 0.6;
+    let columns = hotkeys.len().div_ceil(rows).max(1);
+    let size = Size::new(columns as f32 * column_width + MARGIN, rows as f32 * LINE_HEIGHT + 2.0 * MARGIN);
+    let origin = csb.center() - CSVec::new(size.width, size.height) / 2.0;
+
+    let background = Fill { style: canvas::Style::Solid(Color { a: 0.85, ..palette.background }), ..Fill::default() };
+    frame.fill_rectangle(Point::from(origin).into(), size, background);
+    let border = Stroke { width: 1.0, style: stroke::Style::Solid(palette.foreground_alpha(0.5)), ..Stroke::default() };
+    frame.stroke(&Path::rectangle(Point::from(origin).into(), size), border);
+
+    for (i, (keys, action)) in hotkeys.into_iter().enumerate() {
+        let x = origin.x + MARGIN + (i / rows) as f32 * column_width;
+        let y = origin.y + MARGIN + (i % rows) as f32 * LINE_HEIGHT;
+        frame.fill_text(Text {
+            content: keys,
+            position: iced::Point::new(x, y),
+            color: Color::from_rgb(1.0, 0.8, 0.0),
+            size: TEXT_SIZE,
+            ..Default::default()
+        });
+        frame.fill_text(Text {
+            content: action.to_string(),
+            position: iced::Point::new(x + key_width, y),
+            color: palette.foreground,
+            size: TEXT_SIZE,
+            ..Default::default()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cheat_sheet_fits_the_canvas_height() {
+        let lines = KeyBindings::default().cheat_sheet().len();
+        assert_eq!(rows_per_column(10000.0, lines), lines);
+        let rows = rows_per_column(300.0, lines);
+        assert!(rows < lines);
+        assert!(rows as f32 * LINE_HEIGHT + 2.0 * MARGIN <= 300.0);
+        assert_eq!(rows_per_column(0.0, lines), 1);
+    }
+}
@@ -0,0 +1,65 @@
+//! command line arguments for running circe headless, without opening the gui
+
+pub const USAGE: &str = "usage: circe --netlist <schematic.circe> [--op] [--out <netlist.cir>]";
+
+/// headless run requested from the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Args {
+    /// path of the saved schematic to load
+    pub schematic: String,
+    /// path the generated netlist is written to
+    pub out: String,
+    /// ngspice analysis commands to run, in order, e.g. op
+    pub analyses: Vec<String>,
+}
+
+/// parse command line arguments, excluding the program name.
+/// Returns none if there are no arguments, in which case the gui should be opened.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut args = args.into_iter().peekable();
+    if args.peek().is_none() {
+        return Ok(None);
+    }
+    let mut schematic = None;
+    let mut out = String::from("netlist.cir");
+    let mut analyses = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--netlist" => schematic = Some(args.next().ok_or("--netlist requires a path")?),
+            "--out" => out = args.next().ok_or("--out requires a path")?,
+            "--op" => analyses.push(String::from("op")),
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+    let schematic = schematic.ok_or("--netlist is required")?;
+    Ok(Some(Args { schematic, out, analyses }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn no_arguments_opens_gui() {
+        assert_eq!(parse(args("")), Ok(None));
+    }
+
+    #[test]
+    fn netlist_and_op() {
+        assert_eq!(
+            parse(args("--netlist schem.circe --op")),
+            Ok(Some(Args { schematic: String::from("schem.circe"), out: String::from("netlist.cir"), analyses: vec![String::from("op")] }))
+        );
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        assert!(parse(args("--op")).is_err());
+        assert!(parse(args("--netlist")).is_err());
+        assert!(parse(args("--netlist a.circe --tran")).is_err());
+    }
+}
@@ -0,0 +1,294 @@
+//! user configuration, persisted as json
+
+use std::{fs, io, collections::BTreeMap};
+
+use crate::keybindings::KeyBindings;
+
+/// default location of the configuration file
+pub const CONFIG_PATH: &str = "circe.json";
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// default parameters of newly placed devices, keyed by device class name
+    pub device_defaults: BTreeMap<String, Vec<(String, String)>>,
+    /// recently applied device parameters, most recent first, keyed by device class name
+    pub recent_params: BTreeMap<String, Vec<Vec<(String, String)>>>,
+    /// if true, antialiasing is disabled and less detail is drawn, for weak gpus. Antialiasing takes effect on restart.
+    pub low_quality: bool,
+    /// how numbers are displayed in the infobar and in annotations
+    pub number_format: NumberFormat,
+    /// minimum time between redraws of the schematic during rapid edits, in milliseconds. 
+    /// 0 redraws every frame after an edit, edits within the same frame are always drawn together.
+    pub passive_debounce_ms: u64,
+    /// if true, fitting the view snaps the zoom down to a power of two pixels per grid unit
+    pub snap_fit_zoom: bool,
+    /// dark or light appearance, following the os setting by default
+    pub theme: ThemePreference,
+    /// if true, placing a device first asks for its value
+    pub prompt_on_place: bool,
+    /// if true, clicking again at the same point selects the next of the overlapping elements there
+    pub click_cycles: bool,
+    /// if true, writing the schematic or a netlist over an existing file other than the one opened, saved or exported last does not ask first
+    pub overwrite_without_asking: bool,
+    /// if true, the minimap is not shown in the lower right corner of the canvas
+    pub hide_minimap: bool,
+    /// if true, selected elements are outlined with moving dashes
+    pub animate_selection: bool,
+    /// if true, the ports of devices with more than 2 ports are labeled with their names
+    pub show_pin_names: bool,
+    /// how the schematic cursor is drawn
+    pub cursor: CursorStyle,
+    /// whether the canvas grid is drawn as dots or lines
+    pub grid_style: GridStyle,
+    /// drawing style of the net classes nets may be assigned to, keyed by class name
+    pub net_classes: NetClasses,
+    /// panning of the view while dragging near the canvas edges
+    pub auto_pan: AutoPan,
+    /// keys bound to the keyboard actions of the canvas
+    pub key_bindings: KeyBindings,
+}
+
+/// panning of the view while moving, wiring or selecting near the canvas edges
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct AutoPan {
+    /// distance from the canvas edges within which the view pans, in pixels. 0 disables auto-pan.
+    pub margin: f32,
+    /// distance the view pans by per cursor movement, in pixels
+    pub speed: f32,
+}
+
+impl Default for AutoPan {
+    fn default() -> Self {
+        AutoPan { margin: 20.0, speed: 8.0 }
+    }
+}
+
+/// drawing style of the wires of a net class
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct NetStyle {
+    /// red, green and blue, each from 0 to 1
+    pub color: [f32; 3],
+    /// wire width relative to unclassed wires
+    pub width: f32,
+}
+
+impl Default for NetStyle {
+    fn default() -> Self {
+        NetStyle { color: [0.0, 0.8, 1.0], width: 1.0 }
+    }
+}
+
+/// net classes keyed by name, power, ground and signal unless configured otherwise
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(transparent)]
+pub struct NetClasses(pub BTreeMap<String, NetStyle>);
+
+impl Default for NetClasses {
+    fn default() -> Self {
+        NetClasses(BTreeMap::from([
+            (String::from("power"), NetStyle { color: [1.0, 0.3, 0.3], width: 2.0 }),
+            (String::from("ground"), NetStyle { color: [0.6, 0.6, 0.6], width: 2.0 }),
+            (String::from("signal"), NetStyle::default()),
+        ]))
+    }
+}
+
+/// shape of the schematic cursor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum CursorShape {
+    /// square outline
+    #[default]
+    Box,
+    /// filled circle
+    Dot,
+    /// small cross
+    Crosshair,
+    /// horizontal and vertical lines across the whole canvas
+    FullCrosshair,
+}
+
+impl CursorShape {
+    pub const ALL: [CursorShape; 4] = [CursorShape::Box, CursorShape::Dot, CursorShape::Crosshair, CursorShape::FullCrosshair];
+}
+
+impl std::fmt::Display for CursorShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CursorShape::Box => write!(f, "box"),
+            CursorShape::Dot => write!(f, "dot"),
+            CursorShape::Crosshair => write!(f, "crosshair"),
+            CursorShape::FullCrosshair => write!(f, "full crosshair"),
+        }
+    }
+}
+
+/// appearance of the schematic cursor
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CursorStyle {
+    pub shape: CursorShape,
+    /// red, green and blue, each from 0 to 1
+    pub color: [f32; 3],
+    /// width of the box, dot or cross in pixels
+    pub size: f32,
+}
+
+impl Default for CursorStyle {
+    fn default() -> Self {
+        CursorStyle { shape: CursorShape::Box, color: [1.0, 0.9, 0.0], size: 5.0 }
+    }
+}
+
+/// appearance of the canvas grid
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum GridStyle {
+    /// a dot at every grid point
+    #[default]
+    Dots,
+    /// horizontal and vertical lines across the whole canvas
+    Lines,
+}
+
+impl GridStyle {
+    pub const ALL: [GridStyle; 2] = [GridStyle::Dots, GridStyle::Lines];
+}
+
+impl std::fmt::Display for GridStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GridStyle::Dots => write!(f, "dots"),
+            GridStyle::Lines => write!(f, "lines"),
+        }
+    }
+}
+
+/// choice of dark or light appearance
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ThemePreference {
+    /// follow the os appearance setting at startup
+    #[default]
+    Auto,
+    Dark,
+    Light,
+}
+
+impl ThemePreference {
+    pub const ALL: [ThemePreference; 3] = [ThemePreference::Auto, ThemePreference::Dark, ThemePreference::Light];
+}
+
+impl std::fmt::Display for ThemePreference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemePreference::Auto => write!(f, "follow os"),
+            ThemePreference::Dark => write!(f, "dark"),
+            ThemePreference::Light => write!(f, "light"),
+        }
+    }
+}
+
+/// display conventions for numbers
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct NumberFormat {
+    /// inserted between groups of three integer digits, e.g. "," or " ". Empty for none.
+    pub thousands_separator: String,
+    /// separates the integer and fractional digits, e.g. "." or ","
+    pub decimal_separator: String,
+    /// number of decimal places of the zoom scale in the infobar
+    pub zoom_precision: usize,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat { thousands_separator: String::new(), decimal_separator: String::from("."), zoom_precision: 1 }
+    }
+}
+
+impl NumberFormat {
+    /// rewrite a number formatted by rust, e.g. "-12345.6" or "1.5e-9", with the configured separators
+    pub fn localize(&self, s: &str) -> String {
+        let (sign, s) = s.strip_prefix('-').map_or(("", s), |s| ("-", s));
+        let (mantissa, exponent) = s.find(['e', 'E']).map_or((s, ""), |i| s.split_at(i));
+        let (int, frac) = mantissa.split_once('.').map_or((mantissa, None), |(i, f)| (i, Some(f)));
+        if !int.chars().all(|c| c.is_ascii_digit()) {
+            return format!("{}{}", sign, s);  // inf, NaN
+        }
+        let mut grouped = String::new();
+        for (i, c) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                grouped.push_str(&self.thousands_separator);
+            }
+            grouped.push(c);
+        }
+        match frac {
+            Some(frac) => format!("{}{}{}{}{}", sign, grouped, self.decimal_separator, frac, exponent),
+            None => format!("{}{}{}", sign, grouped, exponent),
+        }
+    }
+    /// format an integer, e.g. a coordinate
+    pub fn int(&self, v: i64) -> String {
+        self.localize(&v.to_string())
+    }
+    /// format the zoom scale with the configured number of decimal places
+    pub fn zoom(&self, v: f32) -> String {
+        self.localize(&format!("{:04.*}", self.zoom_precision, v))
+    }
+}
+
+impl Config {
+    /// load configuration from path. Returns the default configuration if the file is missing or invalid.
+    pub fn load(path: &str) -> Self {
+        fs::read(path).ok()
+        .and_then(|b| serde_json::from_slice(&b).ok())
+        .unwrap_or_default()
+    }
+    /// write configuration to path
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn localized_numbers() {
+        let nf = NumberFormat { thousands_separator: String::from(" "), decimal_separator: String::from(","), zoom_precision: 2 };
+        assert_eq!(nf.localize("-12345.678"), "-12 345,678");
+        assert_eq!(nf.localize("123"), "123");
+        assert_eq!(nf.localize("1.50e-9"), "1,50e-9");
+        assert_eq!(nf.localize("inf"), "inf");
+        assert_eq!(nf.int(-1000000), "-1 000 000");
+        assert_eq!(nf.zoom(5.0), "5,00");
+        assert_eq!(NumberFormat::default().zoom(5.0), "05.0");
+        assert_eq!(NumberFormat::default().localize("-12345.678"), "-12345.678");
+    }
+
+    #[test]
+    fn partial_cursor_style_keeps_defaults() {
+        let config: Config = serde_json::from_str(r#"{"cursor": {"shape": "FullCrosshair", "size": 9.0}}"#).unwrap();
+        assert_eq!(config.cursor, CursorStyle { shape: CursorShape::FullCrosshair, size: 9.0, ..CursorStyle::default() });
+        assert_eq!(Config::default().cursor.shape, CursorShape::Box);
+    }
+
+    #[test]
+    fn quality_defaults_to_high() {
+        assert!(!Config::default().low_quality);
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert!(!config.low_quality);
+        let config: Config = serde_json::from_str(r#"{"low_quality": true}"#).unwrap();
+        let saved: Config = serde_json::from_str(&serde_json::to_string(&config).unwrap()).unwrap();
+        assert!(saved.low_quality);
+    }
+
+    #[test]
+    fn grid_style_defaults_to_dots() {
+        let config: Config = serde_json::from_str(r#"{"grid_style": "Lines"}"#).unwrap();
+        assert_eq!(config.grid_style, GridStyle::Lines);
+        assert_eq!(Config::default().grid_style, GridStyle::Dots);
+    }
+}
@@ -0,0 +1,431 @@
+//! key bindings
+//! every keyboard action of the canvas and the keys bound to it, remappable in the configuration file. 
+//! Keys are written as listed in the cheat sheet, e.g. `Ctrl+Shift+Z`, `Del` or `?`.
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use iced::keyboard::{self, KeyCode};
+
+/// names of the keys which can be bound, as written in the configuration file and the cheat sheet
+const KEY_NAMES: &[(KeyCode, &str)] = &[
+    (KeyCode::Key0, "0"), (KeyCode::Key1, "1"), (KeyCode::Key2, "2"), (KeyCode::Key3, "3"), (KeyCode::Key4, "4"),
+    (KeyCode::Key5, "5"), (KeyCode::Key6, "6"), (KeyCode::Key7, "7"), (KeyCode::Key8, "8"), (KeyCode::Key9, "9"),
+    (KeyCode::A, "A"), (KeyCode::B, "B"), (KeyCode::C, "C"), (KeyCode::D, "D"), (KeyCode::E, "E"), (KeyCode::F, "F"),
+    (KeyCode::G, "G"), (KeyCode::H, "H"), (KeyCode::I, "I"), (KeyCode::J, "J"), (KeyCode::K, "K"), (KeyCode::L, "L"),
+    (KeyCode::M, "M"), (KeyCode::N, "N"), (KeyCode::O, "O"), (KeyCode::P, "P"), (KeyCode::Q, "Q"), (KeyCode::R, "R"),
+    (KeyCode::S, "S"), (KeyCode::T, "T"), (KeyCode::U, "U"), (KeyCode::V, "V"), (KeyCode::W, "W"), (KeyCode::X, "X"),
+    (KeyCode::Y, "Y"), (KeyCode::Z, "Z"), (KeyCode::F1, "F1"), (KeyCode::F2, "F2"), (KeyCode::F3, "F3"), (KeyCode::F4, "F4"),
+    (KeyCode::F5, "F5"), (KeyCode::F6, "F6"), (KeyCode::F7, "F7"), (KeyCode::F8, "F8"), (KeyCode::F9, "F9"),
+    (KeyCode::F10, "F10"), (KeyCode::F11, "F11"), (KeyCode::F12, "F12"), (KeyCode::Escape, "Esc"), (KeyCode::Delete, "Del"),
+    (KeyCode::Space, "Space"), (KeyCode::Tab, "Tab"), (KeyCode::Enter, "Enter"), (KeyCode::Backspace, "Backspace"),
+    (KeyCode::Insert, "Insert"), (KeyCode::Home, "Home"), (KeyCode::End, "End"), (KeyCode::PageUp, "PageUp"),
+    (KeyCode::PageDown, "PageDown"), (KeyCode::Left, "Left"), (KeyCode::Right, "Right"), (KeyCode::Up, "Up"),
+    (KeyCode::Down, "Down"),
+];
+
+/// an action triggered from the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    ToggleCheatSheet,
+    Cancel,
+    Wire,
+    Bend,
+    Annotate,
+    PlaceResistor,
+    PlaceGround,
+    PlaceVoltageSource,
+    PlacePotentiometer,
+    RotatePlacement,
+    RotatePlacementBack,
+    MirrorX,
+    MirrorY,
+    RotateSelected,
+    RotateDiagonal,
+    MoveGridOrigin,
+    ToggleProbe,
+    Move,
+    SwapPorts,
+    Disable,
+    Lock,
+    Cycle,
+    Delete,
+    DeleteWithWires,
+    Copy,
+    Paste,
+    Duplicate,
+    Undo,
+    Redo,
+    Save,
+    Open,
+    FitView,
+    FitSelection,
+    CleanUpWires,
+    Renumber,
+    WriteBom,
+    WriteKicadNetlist,
+    WriteNetlist,
+    RunDcOp,
+    MeasureImpedance,
+    OpenDatasheet,
+}
+
+impl Action {
+    /// every action, in the order they are listed in the cheat sheet
+    pub const ALL: [Action; 41] = [
+        Action::ToggleCheatSheet,
+        Action::Cancel,
+        Action::Wire,
+        Action::Bend,
+        Action::Annotate,
+        Action::PlaceResistor,
+        Action::PlaceGround,
+        Action::PlaceVoltageSource,
+        Action::PlacePotentiometer,
+        Action::RotatePlacement,
+        Action::RotatePlacementBack,
+        Action::MirrorX,
+        Action::MirrorY,
+        Action::RotateSelected,
+        Action::RotateDiagonal,
+        Action::MoveGridOrigin,
+        Action::ToggleProbe,
+        Action::Move,
+        Action::SwapPorts,
+        Action::Disable,
+        Action::Lock,
+        Action::Cycle,
+        Action::Delete,
+        Action::DeleteWithWires,
+        Action::Copy,
+        Action::Paste,
+        Action::Duplicate,
+        Action::Undo,
+        Action::Redo,
+        Action::Save,
+        Action::Open,
+        Action::FitView,
+        Action::FitSelection,
+        Action::CleanUpWires,
+        Action::Renumber,
+        Action::WriteBom,
+        Action::WriteKicadNetlist,
+        Action::WriteNetlist,
+        Action::RunDcOp,
+        Action::MeasureImpedance,
+        Action::OpenDatasheet,
+    ];
+
+    /// returns the name of the action in the configuration file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::ToggleCheatSheet => "toggle_cheat_sheet",
+            Action::Cancel => "cancel",
+            Action::Wire => "wire",
+            Action::Bend => "bend",
+            Action::Annotate => "annotate",
+            Action::PlaceResistor => "place_resistor",
+            Action::PlaceGround => "place_ground",
+            Action::PlaceVoltageSource => "place_voltage_source",
+            Action::PlacePotentiometer => "place_potentiometer",
+            Action::RotatePlacement => "rotate_placement",
+            Action::RotatePlacementBack => "rotate_placement_back",
+            Action::MirrorX => "mirror_x",
+            Action::MirrorY => "mirror_y",
+            Action::RotateSelected => "rotate_selected",
+            Action::RotateDiagonal => "rotate_diagonal",
+            Action::MoveGridOrigin => "move_grid_origin",
+            Action::ToggleProbe => "toggle_probe",
+            Action::Move => "move",
+            Action::SwapPorts => "swap_ports",
+            Action::Disable => "disable",
+            Action::Lock => "lock",
+            Action::Cycle => "cycle",
+            Action::Delete => "delete",
+            Action::DeleteWithWires => "delete_with_wires",
+            Action::Copy => "copy",
+            Action::Paste => "paste",
+            Action::Duplicate => "duplicate",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Save => "save",
+            Action::Open => "open",
+            Action::FitView => "fit_view",
+            Action::FitSelection => "fit_selection",
+            Action::CleanUpWires => "clean_up_wires",
+            Action::Renumber => "renumber",
+            Action::WriteBom => "write_bom",
+            Action::WriteKicadNetlist => "write_kicad_netlist",
+            Action::WriteNetlist => "write_netlist",
+            Action::RunDcOp => "run_dc_op",
+            Action::MeasureImpedance => "measure_impedance",
+            Action::OpenDatasheet => "open_datasheet",
+        }
+    }
+    /// returns what the action does, as listed in the cheat sheet
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::ToggleCheatSheet => "show / hide this cheat sheet",
+            Action::Cancel => "cancel, clear found net",
+            Action::Wire => "draw wire",
+            Action::Bend => "bend wires",
+            Action::Annotate => "place arrow annotations",
+            Action::PlaceResistor => "resistor",
+            Action::PlaceGround => "ground",
+            Action::PlaceVoltageSource => "voltage source",
+            Action::PlacePotentiometer => "potentiometer",
+            Action::RotatePlacement => "rotate clockwise during move / placement",
+            Action::RotatePlacementBack => "rotate counter clockwise during move / placement",
+            Action::MirrorX => "mirror x during move / placement",
+            Action::MirrorY => "mirror y during move / placement",
+            Action::RotateSelected => "rotate selected counter clockwise",
+            Action::RotateDiagonal => "rotate selected 45 degrees",
+            Action::MoveGridOrigin => "move the grid origin to the cursor",
+            Action::ToggleProbe => "place / remove a voltage probe",
+            Action::Move => "move selected",
+            Action::SwapPorts => "swap ports of selected",
+            Action::Disable => "disable / enable selected",
+            Action::Lock => "lock / unlock selected",
+            Action::Cycle => "cycle tentative selection",
+            Action::Delete => "delete selected",
+            Action::DeleteWithWires => "delete selected and dangling wires",
+            Action::Copy => "copy selected",
+            Action::Paste => "paste at cursor",
+            Action::Duplicate => "duplicate selected next to the selection",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Save => "save schematic",
+            Action::Open => "open schematic",
+            Action::FitView => "fit view to geometry",
+            Action::FitSelection => "fit view to selection",
+            Action::CleanUpWires => "clean up wires",
+            Action::Renumber => "renumber device identifiers",
+            Action::WriteBom => "export bill of materials",
+            Action::WriteKicadNetlist => "export KiCad netlist",
+            Action::WriteNetlist => "write netlist",
+            Action::RunDcOp => "run dc op simulation",
+            Action::MeasureImpedance => "measure impedance between selected nets",
+            Action::OpenDatasheet => "open datasheet of selected",
+        }
+    }
+    /// returns the keys bound to the action unless remapped
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::ToggleCheatSheet => &["?"],
+            Action::Cancel => &["Esc"],
+            Action::Wire => &["W"],
+            Action::Bend => &["H"],
+            Action::Annotate => &["A"],
+            Action::PlaceResistor => &["R"],
+            Action::PlaceGround => &["G"],
+            Action::PlaceVoltageSource => &["V"],
+            Action::PlacePotentiometer => &["P"],
+            Action::RotatePlacement => &["R"],
+            Action::RotatePlacementBack => &["Shift+R"],
+            Action::MirrorX => &["X"],
+            Action::MirrorY => &["Y"],
+            Action::RotateSelected => &["Shift+R"],
+            Action::RotateDiagonal => &["Ctrl+R"],
+            Action::MoveGridOrigin => &["Shift+G"],
+            Action::ToggleProbe => &["Shift+P"],
+            Action::Move => &["M"],
+            Action::SwapPorts => &["S"],
+            Action::Disable => &["D"],
+            Action::Lock => &["L"],
+            Action::Cycle => &["C"],
+            Action::Delete => &["Del"],
+            Action::DeleteWithWires => &["Shift+Del"],
+            Action::Copy => &["Ctrl+C"],
+            Action::Paste => &["Ctrl+V"],
+            Action::Duplicate => &["Ctrl+D"],
+            Action::Undo => &["Ctrl+Z"],
+            Action::Redo => &["Ctrl+Y", "Ctrl+Shift+Z"],
+            Action::Save => &["Ctrl+S"],
+            Action::Open => &["Ctrl+O"],
+            Action::FitView => &["F"],
+            Action::FitSelection => &["Shift+F"],
+            Action::CleanUpWires => &["K"],
+            Action::Renumber => &["N"],
+            Action::WriteBom => &["B"],
+            Action::WriteKicadNetlist => &["E"],
+            Action::WriteNetlist => &["T"],
+            Action::RunDcOp => &["Space"],
+            Action::MeasureImpedance => &["I"],
+            Action::OpenDatasheet => &["U"],
+        }
+    }
+    /// returns true for actions which transform the elements being moved or placed. 
+    /// While moving, they take precedence over other actions bound to the same keys.
+    fn transforms_placement(&self) -> bool {
+        matches!(self, Action::RotatePlacement | Action::RotatePlacementBack | Action::MirrorX | Action::MirrorY)
+    }
+}
+
+/// a key with the modifiers held with it, or a typed character
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCombo {
+    /// a key pressed with exactly these modifiers held
+    Key { key_code: KeyCode, ctrl: bool, shift: bool, alt: bool },
+    /// a character typed with whichever keys the keyboard layout takes for it, e.g. `?`
+    Char(char),
+}
+
+impl KeyCombo {
+    /// returns true if event presses the key combination
+    pub fn matches(&self, event: &keyboard::Event) -> bool {
+        match (self, event) {
+            (KeyCombo::Key { key_code, ctrl, shift, alt }, keyboard::Event::KeyPressed { key_code: k, modifiers }) => {
+                key_code == k && *ctrl == modifiers.control() && *shift == modifiers.shift() && *alt == modifiers.alt()
+            },
+            (KeyCombo::Char(c), keyboard::Event::CharacterReceived(r)) => c == r,
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for KeyCombo {
+    type Err = String;
+
+    /// parses a key combination such as `Ctrl+Shift+Z`, `Del` or `?`. Names are not case sensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            if !c.is_ascii_alphanumeric() {
+                return Ok(KeyCombo::Char(c));
+            }
+        }
+        let mut tokens: Vec<&str> = s.split('+').map(str::trim).collect();
+        let key = tokens.pop().unwrap_or_default();
+        let key_code = KEY_NAMES.iter()
+            .chain(&[(KeyCode::Escape, "Escape"), (KeyCode::Delete, "Delete")])
+            .find(|(_, name)| name.eq_ignore_ascii_case(key))
+            .map(|(key_code, _)| *key_code)
+            .ok_or(format!("unknown key {}", key))?;
+        let (mut ctrl, mut shift, mut alt) = (false, false, false);
+        for t in tokens {
+            match t.to_ascii_lowercase().as_str() {
+                "ctrl" => ctrl = true,
+                "shift" => shift = true,
+                "alt" => alt = true,
+                _ => return Err(format!("unknown modifier {}", t)),
+            }
+        }
+        Ok(KeyCombo::Key { key_code, ctrl, shift, alt })
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyCombo::Key { key_code, ctrl, shift, alt } => {
+                let name = KEY_NAMES.iter().find(|(k, _)| k == key_code).map_or("?", |(_, name)| name);
+                for (held, modifier) in [(ctrl, "Ctrl+"), (shift, "Shift+"), (alt, "Alt+")] {
+                    if *held {
+                        write!(f, "{}", modifier)?;
+                    }
+                }
+                write!(f, "{}", name)
+            },
+            KeyCombo::Char(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+/// the keys bound to every action. Saved as a map of action names to key combinations, 
+/// actions missing from it keep their default keys, unknown actions and keys are ignored.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(from = "BTreeMap<String, Vec<String>>", into = "BTreeMap<String, Vec<String>>")]
+pub struct KeyBindings(BTreeMap<Action, Vec<KeyCombo>>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings(Action::ALL.iter().map(|a| {
+            (*a, a.default_keys().iter().filter_map(|k| k.parse().ok()).collect())
+        }).collect())
+    }
+}
+
+impl From<BTreeMap<String, Vec<String>>> for KeyBindings {
+    fn from(map: BTreeMap<String, Vec<String>>) -> Self {
+        let mut bindings = KeyBindings::default();
+        for (name, keys) in map {
+            if let Some(a) = Action::ALL.iter().find(|a| a.name() == name) {
+                bindings.0.insert(*a, keys.iter().filter_map(|k| k.parse().ok()).collect());
+            }
+        }
+        bindings
+    }
+}
+
+impl From<KeyBindings> for BTreeMap<String, Vec<String>> {
+    fn from(bindings: KeyBindings) -> Self {
+        bindings.0.into_iter().map(|(a, keys)| (a.name().to_string(), keys.iter().map(|k| k.to_string()).collect())).collect()
+    }
+}
+
+impl KeyBindings {
+    /// returns the keys bound to action
+    pub fn keys(&self, action: Action) -> &[KeyCombo] {
+        self.0.get(&action).map_or(&[], |keys| keys.as_slice())
+    }
+    /// returns the action event triggers, if any. If several actions are bound to the same keys, 
+    /// actions transforming the elements being moved are picked while moving, and other actions otherwise.
+    pub fn action(&self, event: &keyboard::Event, moving: bool) -> Option<Action> {
+        let bound: Vec<Action> = self.0.iter()
+            .filter(|(_, keys)| keys.iter().any(|k| k.matches(event)))
+            .map(|(a, _)| *a)
+            .collect();
+        bound.iter().find(|a| a.transforms_placement() == moving).or(bound.first()).copied()
+    }
+    /// returns the keys and description of every bound action, in the order they are listed in the cheat sheet
+    pub fn cheat_sheet(&self) -> Vec<(String, &'static str)> {
+        Action::ALL.iter()
+            .filter(|a| !self.keys(**a).is_empty())
+            .map(|a| (self.keys(*a).iter().map(|k| k.to_string()).collect::<Vec<_>>().join(" / "), a.description()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(key_code: KeyCode, modifiers: keyboard::Modifiers) -> keyboard::Event {
+        keyboard::Event::KeyPressed { key_code, modifiers }
+    }
+
+    #[test]
+    fn key_combos_are_parsed_and_written_alike() {
+        for s in ["Ctrl+Shift+Z", "Del", "?", "Shift+F5", "7"] {
+            assert_eq!(s.parse::<KeyCombo>().unwrap().to_string(), s);
+        }
+        assert_eq!("ctrl + escape".parse::<KeyCombo>().unwrap().to_string(), "Ctrl+Esc");
+        assert!("Hyper+A".parse::<KeyCombo>().is_err());
+        assert!("Ctrl+Nope".parse::<KeyCombo>().is_err());
+    }
+
+    #[test]
+    fn events_trigger_their_bound_action() {
+        let bindings = KeyBindings::default();
+        let r = press(KeyCode::R, keyboard::Modifiers::empty());
+        assert_eq!(bindings.action(&r, false), Some(Action::PlaceResistor));
+        assert_eq!(bindings.action(&r, true), Some(Action::RotatePlacement));
+        assert_eq!(bindings.action(&press(KeyCode::Escape, keyboard::Modifiers::empty()), true), Some(Action::Cancel));
+        assert_eq!(bindings.action(&press(KeyCode::Z, keyboard::Modifiers::CTRL | keyboard::Modifiers::SHIFT), false), Some(Action::Redo));
+        assert_eq!(bindings.action(&press(KeyCode::R, keyboard::Modifiers::ALT), false), None);
+        assert_eq!(bindings.action(&keyboard::Event::CharacterReceived('?'), false), Some(Action::ToggleCheatSheet));
+    }
+
+    #[test]
+    fn remapped_keys_keep_other_defaults() {
+        let bindings: KeyBindings = serde_json::from_str(r#"{"wire": ["Q", "Ctrl+W"], "no_such_action": ["X"], "undo": ["nope"]}"#).unwrap();
+        assert_eq!(bindings.action(&press(KeyCode::Q, keyboard::Modifiers::empty()), false), Some(Action::Wire));
+        assert_eq!(bindings.action(&press(KeyCode::W, keyboard::Modifiers::empty()), false), None);
+        assert!(bindings.keys(Action::Undo).is_empty());
+        assert_eq!(bindings.keys(Action::Move), KeyBindings::default().keys(Action::Move));
+        assert!(bindings.cheat_sheet().contains(&(String::from("Q / Ctrl+W"), "draw wire")));
+
+        let saved = serde_json::to_string(&bindings).unwrap();
+        assert_eq!(serde_json::from_str::<KeyBindings>(&saved).unwrap(), bindings);
+    }
+}
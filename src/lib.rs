@@ -0,0 +1,9 @@
+//! Circe schematic engine
+//! schematic, nets, devices and coordinate transforms, usable without the gui. 
+//! The circe binary is a frontend to this library, as is the headless command line.
+
+pub mod transforms;
+pub mod schematic;
+pub mod config;
+pub mod keybindings;
+pub mod cli;
@@ -2,23 +2,34 @@
 //! Schematic Capture for EDA with ngspice integration
 
 use std::fmt::Debug;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
 
-mod transforms;
-use transforms::{Point, CSPoint, CSBox, SSPoint};
+use circe::{transforms, schematic, config, cli, keybindings::Action};
+use transforms::{Point, CSPoint, CSBox, SSPoint, VSBox, VSPoint};
 
 mod viewport;
 use viewport::ViewportState;
 
-mod schematic;
+mod appearance;
+use appearance::Palette;
+
+mod minimap;
+use minimap::Minimap;
+
+mod cheatsheet;
+
 use schematic::{Schematic, SchematicState, RcRDevice};
 
+use config::Config;
+
 
 
 use iced::{
-    Application, Color, Command, Element, Length, Rectangle, Settings,
+    Application, Color, Command, Element, Length, Rectangle, Settings, Subscription,
     Theme, executor, Size, mouse, widget::{
-        canvas, column, row, canvas::{
+        canvas, column, row, text, text_input, horizontal_rule, checkbox, button, scrollable, pick_list, Column, Row, canvas::{
             Cache, Cursor, Geometry, event::{self, Event}
         }
     }
@@ -34,14 +45,31 @@ use colored::Colorize;
 
 use std::process::{self, Command as Cmd, Stdio};
 
+/// id of the text input of the placement value prompt, focused when it opens
+const PLACEMENT_VALUE_ID: &str = "placement_value";
+
+/// net class picker entry removing the selected nets from their class
+const NO_NET_CLASS: &str = "none";
+
 /// Spice Manager to facillitate interaction with NgSpice
 struct SpManager{
-    tmp: Option<PkVecvaluesall>,
+    /// values of the last simulation point since last taken
+    tmp: Mutex<Option<PkVecvaluesall>>,
+    /// messages ngspice printed to stderr since last taken
+    errors: Mutex<Vec<String>>,
 }
 
 impl SpManager {
     fn new() -> Self {
-        SpManager { tmp: None }
+        SpManager { tmp: Mutex::new(None), errors: Mutex::new(vec![]) }
+    }
+    /// returns and clears the values of the last simulation point, none if no point was sent since last taken
+    fn take_data(&self) -> Option<PkVecvaluesall> {
+        self.tmp.lock().ok().and_then(|mut d| d.take())
+    }
+    /// returns and clears the messages ngspice printed to stderr
+    fn take_errors(&self) -> Vec<String> {
+        self.errors.lock().map(|mut e| std::mem::take(&mut *e)).unwrap_or_default()
     }
 }
 
@@ -55,7 +83,12 @@ impl paprika::PkSpiceManager for SpManager{
         };
         let msgc = match token {
             "stdout" => msgs.green(),
-            "stderr" => msgs.red(),
+            "stderr" => {
+                if let Ok(errors) = self.errors.get_mut() {
+                    errors.push(msgs.to_string());
+                }
+                msgs.red()
+            },
             _ => msg.magenta().strikethrough(),
         };
         println!("{}", msgc);
@@ -68,27 +101,149 @@ impl paprika::PkSpiceManager for SpManager{
     fn cb_send_init(&mut self, pkvecinfoall: PkVecinfoall, id: i32) {
     }
     fn cb_send_data(&mut self, pkvecvaluesall: PkVecvaluesall, count: i32, id: i32) {
-        self.tmp = Some(pkvecvaluesall);
+        if let Ok(tmp) = self.tmp.get_mut() {
+            *tmp = Some(pkvecvaluesall);
+        }
     }
     fn cb_bgt_state(&mut self, is_fin: bool, id: i32) {
     }
 }
 
 pub fn main() -> iced::Result {
+    match cli::parse(std::env::args().skip(1)) {
+        Ok(Some(args)) => process::exit(headless(args)),
+        Ok(None) => {},
+        Err(e) => {
+            eprintln!("{}\n{}", e, cli::USAGE);
+            process::exit(2);
+        },
+    }
+    let low_quality = Config::load(config::CONFIG_PATH).low_quality;
     Circe::run(Settings {
         window: iced::window::Settings {
              size: (600, 500), 
              ..iced::window::Settings::default()
             },
-        antialiasing: true,
+        antialiasing: !low_quality,
         ..Settings::default()
     })
 }
 
+/// load the ngspice shared library
+fn load_ngspice() -> PkSpice<SpManager> {
+    let lib;
+    #[cfg(target_family="windows")]
+    {
+        lib = PkSpice::<SpManager>::new(std::ffi::OsStr::new("paprika/ngspice.dll")).unwrap();
+    }
+    #[cfg(target_os = "macos")]
+    {
+
+        // retrieve libngspice.dylib from the following possible directories
+        let ret = Cmd::new("find")
+            .args(&["/usr/lib", "/usr/local/lib"])
+            .arg("-name")
+            .arg("*libngspice.dylib")
+            .stdout(Stdio::piped())
+            .output()
+            .unwrap_or_else(|_| {
+                eprintln!("Error: Could not find libngspice.dylib. Make sure it is installed.");
+                process::exit(1);
+            });
+        let path = String::from_utf8(ret.stdout).unwrap();
+        lib = PkSpice::<SpManager>::new(&std::ffi::OsString::from(path.trim())).unwrap();
+    }
+    #[cfg(target_os = "linux")]
+    {
+
+        // dynamically retrieves libngspice from system
+        let ret = Cmd::new("sh")
+            .arg("-c")
+            .arg("ldconfig -p | grep ngspice | awk '/.*libngspice.so$/{print $4}'")
+            .stdout(Stdio::piped()).output().unwrap_or_else(|_| {
+                eprintln!("Error: Could not find libngspice. Make sure it is installed.");
+                process::exit(1);
+            });
+
+        let path = String::from_utf8(ret.stdout).unwrap();
+        lib = PkSpice::<SpManager>::new(&std::ffi::OsString::from(path.trim())).unwrap();
+    }
+    lib
+}
+
+/// run headless: load the schematic, write its netlist and run the requested analyses, printing results. 
+/// Returns the process exit code.
+fn headless(args: cli::Args) -> i32 {
+    let mut schematic = Schematic::default();
+    if let Err(e) = schematic.open(&args.schematic) {
+        eprintln!("Error: Could not open {}: {}", args.schematic, e);
+        return 1;
+    }
+    if let Err(e) = schematic.netlist_to(&args.out) {
+        eprintln!("Error: Could not write {}: {}", args.out, e);
+        return 1;
+    }
+    if args.analyses.is_empty() {
+        return 0;
+    }
+    if let Err(e) = schematic.validate() {
+        eprintln!("Error: {}", e);
+        return 1;
+    }
+    let manager = Arc::new(SpManager::new());
+    let mut lib = load_ngspice();
+    lib.init(Some(manager.clone()));
+    lib.command(&format!("source \"{}\"", args.out));
+    for analysis in &args.analyses {
+        // an analysis without results must not print those of the one before
+        manager.take_data();
+        lib.command(analysis);
+        let Some(pkvecvaluesall) = manager.take_data() else {
+            eprintln!("Error: {} produced no results", analysis);
+            return 1;
+        };
+        println!("{}:", analysis);
+        for v in &pkvecvaluesall.vecsa {
+            println!("{}\t{}", v.name, v.creal);
+        }
+    }
+    0
+}
+
+/// redraws of the passive cache, coalesced during rapid edits. Edits only mark it out of date, 
+/// the cache is cleared when drawing at most once per debounce interval, and always once after the last edit.
+struct PassiveRedraw {
+    /// true if the passive cache is out of date
+    dirty: Cell<bool>,
+    /// time the passive cache was last cleared for redrawing
+    cleared_at: Cell<Instant>,
+}
+
+impl PassiveRedraw {
+    fn new(now: Instant) -> Self {
+        PassiveRedraw { dirty: Cell::new(false), cleared_at: Cell::new(now) }
+    }
+    /// mark the passive cache out of date
+    fn invalidate(&self) {
+        self.dirty.set(true);
+    }
+    /// returns true if the passive cache is out of date
+    fn is_dirty(&self) -> bool {
+        self.dirty.get()
+    }
+    /// returns true and marks the cache up to date if it is out of date and debounce passed since it was last cleared
+    fn take_due(&self, now: Instant, debounce: Duration) -> bool {
+        if !self.dirty.get() || now.duration_since(self.cleared_at.get()) < debounce {
+            return false;
+        }
+        self.dirty.set(false);
+        self.cleared_at.set(now);
+        true
+    }
+}
+
 /// main program
 struct Circe {
-    /// zoom scale of the viewport, used only for display in the infobar
-    zoom_scale: f32,
     /// cursor coordinate in schematic space, used only for display in the infobar
     curpos_ssp: SSPoint,
     /// tentative net name, used only for display in the infobar
@@ -98,12 +253,24 @@ struct Circe {
     active_cache: Cache,
     /// iced canvas graphical cache, cleared following some schematic actions
     passive_cache: Cache,
+    /// redraws of the passive cache, debounced by `Config::passive_debounce_ms`
+    passive_redraw: PassiveRedraw,
     /// iced canvas graphical cache, almost never cleared
     background_cache: Cache,
+    /// time selection animations are timed from
+    animation_start: Instant,
 
-    /// parameter editor text
-    text: String,
+    /// parameter editor title
+    param_title: String,
+    /// parameter editor fields (name, value)
+    params: Vec<(String, String)>,
+    /// parameter editor attributes text
+    attr_text: String,
+    /// recently applied parameters of the active device class, most recent first
+    recent_params: Vec<Vec<(String, String)>>,
 
+    /// user configuration
+    config: Config,
     /// schematic
     schematic: Schematic,
     /// active device - some if only 1 device selected, otherwise is none
@@ -115,18 +282,459 @@ struct Circe {
 
     /// active tab index
     active_tab: usize,
+    /// status line of the infobar: the last measurement, or an error of the frontend, e.g. of ngspice or the config
+    status: Option<String>,
+    /// operating point current limit text
+    current_limit_text: String,
+    /// operating point annotation precision text
+    op_precision_text: String,
+    /// if true, wire bends are drawn with rounded corners
+    rounded_corners: bool,
+    /// if true, nets are drawn with a badge counting their connected pins
+    inspect_connections: bool,
+    /// if true, the hotkey cheat sheet is drawn over the canvas
+    show_cheat_sheet: bool,
+    /// if true, floating nets prevent the simulation from running
+    block_floating_nets: bool,
+    /// if true, 0 ohm resistors are allowed as jumpers
+    zero_ohm_jumpers: bool,
+    /// name given to the next saved operating point baseline
+    baseline_name_text: String,
+    /// simulation temperature text
+    temperature_text: String,
+    /// net name search text
+    net_search_text: String,
+    /// device class, parameter, current and new value of the batch value replacement
+    replace_class: Option<&'static str>,
+    replace_param: Option<String>,
+    replace_from_text: String,
+    replace_to_text: String,
+    /// label of newly placed arrow annotations
+    arrow_label_text: String,
+    /// initial voltage text input for the selected wires
+    initial_voltage_text: String,
+    /// number of decimal places of the zoom scale text
+    zoom_precision_text: String,
+    /// canvas colors, matching the widget theme
+    palette: Palette,
+    /// value typed for the device waiting to be placed
+    placement_value_text: String,
+    /// path typed to save the schematic as
+    save_as_text: String,
+    /// path of the ngspice rawfile to load results from
+    raw_path_text: String,
+    /// temperature sweep text, temperatures separated by spaces or commas
+    temperature_sweep_text: String,
+
+    /// if true, the viewport is fitted to newly pasted elements
+    auto_fit: bool,
+    /// bounds the viewport should be fitted to on the next canvas event
+    fit_request: Cell<Option<VSBox>>,
+    /// saved view the viewport should be restored to on the next canvas event
+    view_request: Cell<Option<schematic::ViewRecord>>,
+    /// view of the canvas as of the last canvas event, saved with the schematic
+    view: Cell<Option<schematic::ViewRecord>>,
+    /// false while a widget outside the canvas, e.g. a text input, has keyboard focus. 
+    /// Set by the last mouse button press: inside the canvas focuses the canvas, anywhere else takes focus away from it.
+    canvas_focused: Cell<bool>,
+    /// device class picked from the palette, placed once the cursor enters the canvas
+    palette_pick: Cell<Option<&'static str>>,
+    /// zoom scale of the viewport for display in the infobar. 
+    /// The viewport is the canvas state, only the canvas can read it: set from it after every canvas event.
+    viewport_scale: Cell<f32>,
 }
 
 #[derive(Debug, Clone)]
 pub enum Msg {
-    NewZoom(f32),
-    TextInputChanged(String),
+    /// the viewport zoom changed, the infobar is redrawn with the scale in `viewport_scale`
+    NewZoom,
+    /// redraw the passive cache if it is out of date, sent every frame after edits while redraws are debounced
+    FlushPassive,
+    ParamInputChanged(usize, String),
+    AttrInputChanged(String),
+    CurrentLimitChanged(String),
+    OpPrecisionChanged(String),
+    RoundedCornersToggled(bool),
+    InspectConnectionsToggled(bool),
+    BlockFloatingNetsToggled(bool),
+    ZeroOhmJumpersToggled(bool),
+    LowQualityToggled(bool),
+    SnapFitZoomToggled(bool),
+    ThemeSelected(config::ThemePreference),
+    PromptOnPlaceToggled(bool),
+    ClickCyclesToggled(bool),
+    AnimateSelectionToggled(bool),
+    ShowPinNamesToggled(bool),
+    ShowMinimapToggled(bool),
+    AnimationFrame,
+    CursorShapeSelected(config::CursorShape),
+    GridStyleSelected(config::GridStyle),
+    NetClassSelected(String),
+    PlacementValueChanged(String),
+    PlacementValueSubmit,
+    PlacementCancel,
+    SaveAsChanged(String),
+    SaveAsSubmit,
+    OverwriteConfirm,
+    OverwriteCancel,
+    OverwriteWithoutAskingToggled(bool),
+    RawPathChanged(String),
+    LoadRaw,
+    BaselineNameChanged(String),
+    DirectiveChanged(usize, String),
+    TemperatureChanged(String),
+    TemperatureSweepChanged(String),
+    RunTemperatureSweep,
+    SaveBaseline,
     TextInputSubmit,
-    CanvasEvent(Event, SSPoint),
+    SetDefaultParams,
+    RecentParamsSelected(usize),
+    NetSearchChanged(String),
+    ThousandsSeparatorChanged(String),
+    DecimalSeparatorChanged(String),
+    ZoomPrecisionChanged(String),
+    NetSearchSubmit,
+    ReplaceClassSelected(&'static str),
+    ReplaceParamSelected(String),
+    ReplaceFromChanged(String),
+    ReplaceToChanged(String),
+    ReplaceValuesSubmit,
+    ArrowLabelChanged(String),
+    ArrowLabelSubmit,
+    InitialVoltageChanged(String),
+    InitialVoltageSubmit,
+    PalettePicked(&'static str),
+    PlaceDevice(&'static str, SSPoint),
+    CanvasEvent(Event, SSPoint, VSPoint),
     
     TabSel(usize),
 }
 
+impl Circe {
+    /// frequency at which impedance is measured
+    const MEASURE_FREQ: &str = "1k";
+
+    /// one text field per control statement, followed by a blank field to add a new statement
+    fn directives_view(&self) -> Element<Msg> {
+        let directives = self.schematic.directives();
+        let mut col = Column::new().spacing(5).push(
+            row![
+                text("temperature (°C)").size(16),
+                text_input("27", &self.temperature_text).width(60).on_input(Msg::TemperatureChanged),
+                text("sweep").size(16),
+                text_input("-40 27 85", &self.temperature_sweep_text).width(120).on_input(Msg::TemperatureSweepChanged),
+                button("run dc op sweep").on_press(Msg::RunTemperatureSweep),
+            ].spacing(5).align_items(iced::Alignment::Center)
+        ).push(
+            row![
+                text("ngspice rawfile").size(16),
+                text_input("out.raw", &self.raw_path_text).width(240).on_input(Msg::RawPathChanged).on_submit(Msg::LoadRaw),
+                button("load results").on_press(Msg::LoadRaw),
+                text("annotates the last point of the last analysis").size(12),
+            ].spacing(5).align_items(iced::Alignment::Center)
+        ).push(
+            text("appended to the netlist, space runs these analyses instead of dc op if any are set").size(16)
+        );
+        let blank_last = directives.last().is_some_and(|l| l.is_empty());
+        let n = if blank_last {directives.len()} else {directives.len() + 1};
+        for i in 0..n {
+            let line = directives.get(i).map(|l| l.as_str()).unwrap_or("");
+            col = col.push(text_input(".tran 1u 1m", line).on_input(move |s| Msg::DirectiveChanged(i, s)));
+        }
+        scrollable(col.padding(10)).into()
+    }
+    /// file the schematic is saved to and opened from
+    fn file_view(&self) -> Element<Msg> {
+        column![
+            text(format!("file: {}", self.schematic.file_path())).size(16),
+            text_input("save as", &self.save_as_text).width(155).on_input(Msg::SaveAsChanged).on_submit(Msg::SaveAsSubmit),
+            text("ctrl+s / ctrl+o use this file").size(12),
+            checkbox("overwrite without asking", self.config.overwrite_without_asking, Msg::OverwriteWithoutAskingToggled).size(16),
+        ].spacing(5).into()
+    }
+    /// finding nets and editing values, labels and nets across the schematic
+    fn edit_view(&self) -> Element<Msg> {
+        column![
+            text_input("find net", &self.net_search_text).width(155).on_input(Msg::NetSearchChanged).on_submit(Msg::NetSearchSubmit),
+            text("highlighted until esc").size(12),
+            text("replace values").size(16),
+            pick_list(schematic::class_names(), self.replace_class, Msg::ReplaceClassSelected).placeholder("device type").width(155),
+            pick_list(
+                self.replace_class.map_or(vec![], schematic::param_names), 
+                self.replace_param.clone(), 
+                Msg::ReplaceParamSelected,
+            ).placeholder("parameter").width(155),
+            text_input("from", &self.replace_from_text).width(155).on_input(Msg::ReplaceFromChanged),
+            text_input("to", &self.replace_to_text).width(155).on_input(Msg::ReplaceToChanged).on_submit(Msg::ReplaceValuesSubmit),
+            text("enter replaces every match").size(12),
+            text("arrow label").size(16),
+            text_input("none", &self.arrow_label_text).width(155).on_input(Msg::ArrowLabelChanged).on_submit(Msg::ArrowLabelSubmit),
+            text("enter relabels selected arrows").size(12),
+            text("net class of selected wires").size(16),
+            pick_list(
+                std::iter::once(String::from(NO_NET_CLASS)).chain(self.config.net_classes.0.keys().cloned()).collect::<Vec<_>>(),
+                self.schematic.selected_net_class(),
+                Msg::NetClassSelected,
+            ).placeholder(NO_NET_CLASS).width(155),
+            text("initial voltage (V)").size(16),
+            text_input(
+                &self.schematic.selected_initial_condition().map_or(String::from("none"), |v| v.to_string()), 
+                &self.initial_voltage_text,
+            ).width(155).on_input(Msg::InitialVoltageChanged).on_submit(Msg::InitialVoltageSubmit),
+            text("enter sets .ic of selected wires").size(12),
+        ].spacing(5).into()
+    }
+    /// options of the simulation and its annotations
+    fn simulation_view(&self) -> Element<Msg> {
+        column![
+            text("current limit (A)").size(16),
+            text_input("none", &self.current_limit_text).width(155).on_input(Msg::CurrentLimitChanged),
+            text("devices above limit in red").size(12),
+            text("op significant figures").size(16),
+            text_input("3", &self.op_precision_text).width(155).on_input(Msg::OpPrecisionChanged),
+            checkbox("floating nets block sim", self.block_floating_nets, Msg::BlockFloatingNetsToggled).size(16),
+            checkbox("0 Ω resistors are jumpers", self.zero_ohm_jumpers, Msg::ZeroOhmJumpersToggled).size(16),
+        ].spacing(5).into()
+    }
+    /// appearance and behavior of the editor, persisted to the config
+    fn settings_view(&self) -> Element<Msg> {
+        column![
+            text("thousands / decimal separator").size(16),
+            row![
+                text_input("none", &self.config.number_format.thousands_separator).width(75).on_input(Msg::ThousandsSeparatorChanged),
+                text_input(".", &self.config.number_format.decimal_separator).width(75).on_input(Msg::DecimalSeparatorChanged),
+            ].spacing(5),
+            text("zoom decimal places").size(16),
+            text_input("1", &self.zoom_precision_text).width(155).on_input(Msg::ZoomPrecisionChanged),
+            checkbox("rounded corners", self.rounded_corners, Msg::RoundedCornersToggled).size(16),
+            checkbox("inspect connections", self.inspect_connections, Msg::InspectConnectionsToggled).size(16),
+            checkbox("ask value on placement", self.config.prompt_on_place, Msg::PromptOnPlaceToggled).size(16),
+            checkbox("click again to cycle", self.config.click_cycles, Msg::ClickCyclesToggled).size(16),
+            checkbox("animate selection", self.config.animate_selection, Msg::AnimateSelectionToggled).size(16),
+            checkbox("pin names", self.config.show_pin_names, Msg::ShowPinNamesToggled).size(16),
+            checkbox("minimap", !self.config.hide_minimap, Msg::ShowMinimapToggled).size(16),
+            checkbox("snap zoom when fitting", self.config.snap_fit_zoom, Msg::SnapFitZoomToggled).size(16),
+            checkbox("low quality", self.config.low_quality, Msg::LowQualityToggled).size(16),
+            text("cursor").size(16),
+            pick_list(&config::CursorShape::ALL[..], Some(self.config.cursor.shape), Msg::CursorShapeSelected).width(155),
+            text("grid").size(16),
+            pick_list(&config::GridStyle::ALL[..], Some(self.config.grid_style), Msg::GridStyleSelected).width(155),
+            text("theme").size(16),
+            pick_list(&config::ThemePreference::ALL[..], Some(self.config.theme), Msg::ThemeSelected).width(155),
+            text("antialiasing applies on restart").size(12),
+        ].spacing(5).into()
+    }
+    /// one entry per placeable device class. The picked device follows the cursor onto the canvas, click to drop it.
+    fn palette_view(&self) -> Element<Msg> {
+        let mut col = Column::new().spacing(2);
+        for &name in schematic::class_names() {
+            col = col.push(
+                button(row![
+                    canvas(SymbolIcon(name)).width(24).height(24),
+                    text(name).size(14),
+                ].spacing(5).align_items(iced::Alignment::Center))
+                .on_press(Msg::PalettePicked(name))
+                .width(155)
+            );
+        }
+        col.into()
+    }
+    /// persist the config, reporting a failure to save it
+    fn save_config(&mut self) {
+        if let Err(e) = self.config.save(config::CONFIG_PATH) {
+            self.status = Some(format!("Error: Could not save config: {}", e));
+        }
+    }
+    /// apply number_format to displayed numbers and persist it to the config
+    fn set_number_format(&mut self, number_format: config::NumberFormat) {
+        self.schematic.set_number_format(number_format.clone());
+        self.config.number_format = number_format;
+        self.invalidate_passive();
+        self.save_config();
+    }
+    /// table of net voltages of the last operating point against the saved baseline
+    fn op_comparison_view(&self) -> Element<Msg> {
+        let precision = self.op_precision_text.trim().parse::<usize>().unwrap_or(3).max(1);
+        let fmt = |v: Option<f32>| v.map_or(String::from("-"), |v| self.config.number_format.localize(&schematic::format_significant(v, precision)));
+        let cell = |s: String| text(s).size(16).width(120);
+        let mut table = Column::new().spacing(5);
+        match self.schematic.op_comparison() {
+            Some((name, deltas)) => {
+                table = table.push(row![
+                    cell(String::from("net")), cell(name), cell(String::from("current")), cell(String::from("delta")),
+                ]);
+                for d in deltas {
+                    table = table.push(row![
+                        cell(d.net.clone()), cell(fmt(d.old)), cell(fmt(d.new)), cell(fmt(d.delta())),
+                    ]);
+                }
+            },
+            None => table = table.push(text("no baseline saved").size(16)),
+        }
+        let sweep = self.schematic.sweep_results();
+        if !sweep.is_empty() {
+            table = table.push(horizontal_rule(10));
+            let mut header = Row::new().push(cell(String::from("net")));
+            for (label, _) in sweep {
+                header = header.push(cell(label.clone()));
+            }
+            table = table.push(header);
+            let nets: std::collections::BTreeSet<&String> = sweep.iter().flat_map(|(_, v)| v.keys()).collect();
+            for net in nets {
+                let mut r = Row::new().push(cell(net.clone()));
+                for (_, voltages) in sweep {
+                    r = r.push(cell(fmt(voltages.get(net).copied())));
+                }
+                table = table.push(r);
+            }
+        }
+        column![
+            row![
+                text_input("baseline", &self.baseline_name_text).width(155).on_input(Msg::BaselineNameChanged),
+                button("save baseline").on_press(Msg::SaveBaseline),
+            ].spacing(5),
+            horizontal_rule(10),
+            scrollable(table),
+        ].padding(10).into()
+    }
+    /// refresh the simulation setup text fields from the schematic, e.g. after opening a file
+    fn sync_sim_texts(&mut self) {
+        self.temperature_text = self.schematic.temperature().map_or(String::new(), |t| t.to_string());
+        self.temperature_sweep_text = self.schematic.temperature_sweep().iter().map(|t| t.to_string()).collect::<Vec<_>>().join(" ");
+    }
+    /// returns the minimap on the canvas of bounds csb, None if hidden or nothing to show
+    fn minimap(&self, csb: CSBox) -> Option<Minimap> {
+        if self.config.hide_minimap {
+            return None;
+        }
+        Minimap::new(csb, self.schematic.bounding_box())
+    }
+    /// mark the passive cache out of date, it is redrawn with the next frame
+    fn invalidate_passive(&self) {
+        self.passive_redraw.invalidate();
+    }
+    /// run the dc operating point at every temperature of the sweep, collecting the results labeled by temperature
+    fn run_temperature_sweep(&mut self) {
+        let temperatures = self.schematic.temperature_sweep().to_vec();
+        if temperatures.is_empty() {
+            self.status = Some(String::from("set the temperatures to sweep over, e.g. -40 27 85"));
+            return;
+        }
+        if let Err(e) = self.schematic.validate() {
+            self.status = Some(format!("Error: {}", e));
+            return;
+        }
+        self.schematic.clear_sweep_results();
+        self.spmanager.take_errors();
+        let path = match Schematic::session_file("netlist.cir") {
+            Ok(path) => path,
+            Err(e) => {
+                self.status = Some(format!("Error: Could not write netlist: {}", e));
+                return;
+            },
+        };
+        let mut failed = vec![];
+        for t in temperatures {
+            if let Err(e) = self.schematic.netlist_at_temperature_to(&path, t) {
+                self.status = Some(format!("Error: Could not write netlist: {}", e));
+                return;
+            }
+            // a failed op must not label the results of the temperature before with t
+            self.spmanager.take_data();
+            self.lib.command(&format!("source \"{}\"", path));
+            self.lib.command("op");
+            match self.spmanager.take_data() {
+                Some(pkvecvaluesall) => self.schematic.op_sweep_point(t, &pkvecvaluesall),
+                None => failed.push(t.to_string()),
+            }
+        }
+        self.invalidate_passive();
+        self.report_ngspice_errors();
+        if !failed.is_empty() && self.status.is_none() {
+            self.status = Some(format!("Error: dc op produced no results at {} °C", failed.join(", ")));
+        }
+    }
+    /// show the last message ngspice printed to stderr since messages were last taken. 
+    /// If a message refers to a netlist line, the device which produced the line is selected and fit to.
+    fn report_ngspice_errors(&mut self) {
+        let errors = self.spmanager.take_errors();
+        if let Some(vsb) = self.schematic.locate_ngspice_error(&errors) {
+            self.fit_request.set(Some(vsb));
+            self.invalidate_passive();
+            self.active_cache.clear();
+        }
+        self.status = errors.last().map(|e| format!("Error: ngspice: {}", e));
+    }
+    /// refresh the recently applied parameters of the active device class and persist them to the config
+    fn update_recent_params(&mut self) {
+        if let Some(ad) = &self.active_device {
+            self.recent_params = self.schematic.recent_params().get(ad.0.borrow().class().name()).cloned().unwrap_or_default();
+        }
+        self.config.recent_params = self.schematic.recent_params().clone();
+        self.save_config();
+    }
+
+    /// measure the impedance between the two nets of the selected wires. 
+    /// A 1A ac test current is injected between the nets in a temporary netlist, the resulting voltage is the impedance.
+    fn measure_impedance(&mut self) {
+        let Some((a, b)) = self.schematic.selected_net_pair() else {
+            self.status = Some(String::from("select wires of exactly 2 nets to measure"));
+            return;
+        };
+        let res = Schematic::session_file("measure.cir").and_then(|path| {
+            self.schematic.measure_netlist_to(&path, &a, &b).map(|_| path)
+        });
+        let path = match res {
+            Ok(path) => path,
+            Err(e) => {
+                self.status = Some(format!("Error: Could not write measurement netlist: {}", e));
+                return;
+            },
+        };
+        // values of an earlier analysis must not pass for the measurement
+        self.spmanager.take_data();
+        self.lib.command(&format!("source \"{}\"", path));
+        self.lib.command(&format!("ac lin 1 {} {}", Circe::MEASURE_FREQ, Circe::MEASURE_FREQ));
+        let v = |pkvecvaluesall: &PkVecvaluesall, n: &str| {
+            pkvecvaluesall.vecsa.iter()
+            .find(|v| v.name.eq_ignore_ascii_case(n))
+            .map(|v| (v.creal, v.cimag))
+        };
+        // only the ac analysis has a frequency vector
+        let ac = self.spmanager.take_data().filter(|vals| v(vals, "frequency").is_some());
+        self.status = match ac.map(|vals| (v(&vals, &a), v(&vals, &b))) {
+            Some((Some(va), Some(vb))) => {
+                let (re, im) = (va.0 - vb.0, va.1 - vb.1);
+                Some(format!("|Z({}, {})| = {:.4e} ohm @ {}Hz", a, b, (re * re + im * im).sqrt(), Circe::MEASURE_FREQ))
+            },
+            _ => Some(String::from("measurement failed")),
+        };
+    }
+}
+
+/// returns true if event is typed into a widget outside the canvas. Modifier changes still reach the canvas for snapping
+fn typed_elsewhere(event: &Event, canvas_focused: bool) -> bool {
+    !canvas_focused && matches!(
+        event, 
+        Event::Keyboard(iced::keyboard::Event::KeyPressed{..} | iced::keyboard::Event::KeyReleased{..} | iced::keyboard::Event::CharacterReceived(_))
+    )
+}
+
+/// returns true if url is a web url, the only kind opened from device attributes
+fn is_web_url(url: &str) -> bool {
+    url.split_once("://").is_some_and(|(scheme, rest)| {
+        !rest.is_empty() && (scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https"))
+    })
+}
+
+/// open url in the default web browser. Urls other than http(s) are refused.
+fn open_url(url: &str) -> Result<(), String> {
+    if !is_web_url(url) {
+        return Err(String::from("only http and https urls are opened"));
+    }
+    opener::open(url).map_err(|e| e.to_string())
+}
+
 impl Application for Circe {
     type Executor = executor::Default;
     type Message = Msg;
@@ -135,63 +743,79 @@ impl Application for Circe {
 
     fn new(_flags: ()) -> (Self, Command<Msg>) {
         let manager = Arc::new(SpManager::new());
-        let mut lib;
-        #[cfg(target_family="windows")]
-        {
-            lib = PkSpice::<SpManager>::new(std::ffi::OsStr::new("paprika/ngspice.dll")).unwrap();
-        }
-        #[cfg(target_os = "macos")]
-        {
-
-            // retrieve libngspice.dylib from the following possible directories
-            let ret = Cmd::new("find")
-                .args(&["/usr/lib", "/usr/local/lib"])
-                .arg("-name")
-                .arg("*libngspice.dylib")
-                .stdout(Stdio::piped())
-                .output()
-                .unwrap_or_else(|_| {
-                    eprintln!("Error: Could not find libngspice.dylib. Make sure it is installed.");
-                    process::exit(1);
-                });
-            let path = String::from_utf8(ret.stdout).unwrap();
-            lib = PkSpice::<SpManager>::new(&std::ffi::OsString::from(path.trim())).unwrap();
-        }
-        #[cfg(target_os = "linux")]
-        {
-
-            // dynamically retrieves libngspice from system
-            let ret = Cmd::new("sh")
-                .arg("-c")
-                .arg("ldconfig -p | grep ngspice | awk '/.*libngspice.so$/{print $4}'")
-                .stdout(Stdio::piped()).output().unwrap_or_else(|_| {
-                    eprintln!("Error: Could not find libngspice. Make sure it is installed.");
-                    process::exit(1);
-                });
-
-            let path = String::from_utf8(ret.stdout).unwrap();
-            lib = PkSpice::<SpManager>::new(&std::ffi::OsString::from(path.trim())).unwrap();
-        }
-
+        let mut lib = load_ngspice();
         lib.init(Some(manager.clone()));
+        let config = Config::load(config::CONFIG_PATH);
+        let mut schematic = Schematic::default();
+        for (class_name, params) in &config.device_defaults {
+            schematic.set_device_defaults(class_name, params.clone());
+        }
+        schematic.set_recent_params(config.recent_params.clone());
+        schematic.set_low_quality(config.low_quality);
+        schematic.set_number_format(config.number_format.clone());
+        schematic.set_prompt_on_place(config.prompt_on_place);
+        schematic.set_click_cycles(config.click_cycles);
+        schematic.set_key_bindings(config.key_bindings.clone());
+        schematic.set_confirm_overwrite(!config.overwrite_without_asking);
+        schematic.set_show_pin_names(config.show_pin_names);
+        schematic.set_net_styles(config.net_classes.0.clone());
+        let zoom_precision_text = config.number_format.zoom_precision.to_string();
+        let palette = Palette::from_preference(config.theme);
+        schematic.set_annotation_color(palette.foreground);
         (
             Circe {
-                zoom_scale: 10.0,  // would be better to get this from the viewport on startup
                 curpos_ssp: SSPoint::origin(),
                 net_name: None,
 
                 active_cache: Default::default(),
                 passive_cache: Default::default(),
+                passive_redraw: PassiveRedraw::new(Instant::now()),
+                animation_start: Instant::now(),
                 background_cache: Default::default(),
 
-                text: String::from(""),
-                schematic: Schematic::default(),
+                param_title: String::from(""),
+                params: vec![],
+                attr_text: String::from(""),
+                recent_params: vec![],
+                config,
+                schematic,
                 active_device: None,
 
                 lib,
                 spmanager: manager,
 
                 active_tab: 0,
+                status: None,
+                current_limit_text: String::from(""),
+                op_precision_text: String::from("3"),
+                rounded_corners: false,
+                inspect_connections: false,
+                show_cheat_sheet: false,
+                block_floating_nets: false,
+                zero_ohm_jumpers: false,
+                baseline_name_text: String::from("baseline"),
+                temperature_text: String::from(""),
+                temperature_sweep_text: String::from(""),
+                net_search_text: String::from(""),
+                replace_class: None,
+                replace_param: None,
+                replace_from_text: String::from(""),
+                replace_to_text: String::from(""),
+                arrow_label_text: String::from(""),
+                initial_voltage_text: String::from(""),
+                zoom_precision_text,
+                palette,
+                placement_value_text: String::from(""),
+                save_as_text: String::from(""),
+                raw_path_text: String::from(""),
+
+                auto_fit: true,
+                fit_request: Cell::new(None),
+                view_request: Cell::new(None),
+                view: Cell::new(None),
+                canvas_focused: Cell::new(true),
+                palette_pick: Cell::new(None),
+                viewport_scale: Cell::new(Viewport::default().vc_scale()),
             },
             Command::none(),
         )
@@ -201,55 +825,504 @@ impl Application for Circe {
         String::from("Schematic Prototyping")
     }
 
+    fn theme(&self) -> Theme {
+        self.palette.theme()
+    }
+
+    fn subscription(&self) -> Subscription<Msg> {
+        // redraw every frame only while there is a selection to animate, 
+        // or while the last edit within the debounce interval is yet to be drawn
+        if self.config.animate_selection && self.schematic.has_selection() {
+            iced::window::frames().map(|_| Msg::AnimationFrame)
+        } else if self.passive_redraw.is_dirty() && self.config.passive_debounce_ms > 0 {
+            iced::window::frames().map(|_| Msg::FlushPassive)
+        } else {
+            Subscription::none()
+        }
+    }
+
     fn update(&mut self, message: Msg) -> Command<Msg> {
+        let prompting = self.schematic.placement_prompt().is_some();
         match message {
-            Msg::NewZoom(value) => {
-                self.zoom_scale = value
+            Msg::NewZoom => {},
+            Msg::ParamInputChanged(i, s) => {
+                if let Some(p) = self.params.get_mut(i) {
+                    p.1 = s;
+                }
+            },
+            Msg::AttrInputChanged(s) => {
+                self.attr_text = s;
+            },
+            Msg::CurrentLimitChanged(s) => {
+                self.schematic.set_current_limit(schematic::parse_spice_value(&s));
+                self.current_limit_text = s;
+                self.invalidate_passive();
+            },
+            Msg::OpPrecisionChanged(s) => {
+                if let Ok(precision) = s.trim().parse::<usize>() {
+                    self.schematic.set_op_precision(precision);
+                    self.invalidate_passive();
+                    self.status = None;
+                } else {
+                    self.status = Some(format!("Error: {} is not a number of significant figures, showing {}", s.trim(), self.schematic.op_precision()));
+                }
+                self.op_precision_text = s;
+            },
+            Msg::RoundedCornersToggled(rounded) => {
+                self.rounded_corners = rounded;
+                self.schematic.set_rounded_corners(rounded);
+                self.invalidate_passive();
+            },
+            Msg::InspectConnectionsToggled(inspect) => {
+                self.inspect_connections = inspect;
+                self.schematic.set_inspect_connections(inspect);
+                self.invalidate_passive();
+            },
+            Msg::BlockFloatingNetsToggled(block) => {
+                self.block_floating_nets = block;
+                self.schematic.set_block_floating_nets(block);
+            },
+            Msg::ZeroOhmJumpersToggled(allow) => {
+                self.zero_ohm_jumpers = allow;
+                self.schematic.set_zero_ohm_jumpers(allow);
+            },
+            Msg::DirectiveChanged(i, s) => {
+                let mut directives = self.schematic.directives().to_vec();
+                if i < directives.len() {
+                    directives[i] = s;
+                } else {
+                    directives.push(s);
+                }
+                // keep a single trailing blank line to type new statements into
+                while directives.len() > 1 && directives[directives.len() - 2..].iter().all(|l| l.is_empty()) {
+                    directives.pop();
+                }
+                self.schematic.set_directives(directives);
+            },
+            Msg::TemperatureChanged(s) => {
+                let t = s.trim().parse::<f32>();
+                if s.trim().is_empty() || t.is_ok() {
+                    self.status = None;
+                } else {
+                    // the default is simulated rather than the last valid temperature, which is no longer shown
+                    self.status = Some(format!("Error: {} is not a temperature, simulating at the default", s.trim()));
+                }
+                self.schematic.set_temperature(t.ok());
+                self.temperature_text = s;
+            },
+            Msg::TemperatureSweepChanged(s) => {
+                let temperatures: Result<Vec<f32>, _> = s.split([' ', ',']).filter(|t| !t.is_empty()).map(|t| t.parse::<f32>()).collect();
+                match temperatures {
+                    Ok(temperatures) => {
+                        self.schematic.set_temperature_sweep(temperatures);
+                        self.status = None;
+                    },
+                    Err(_) => {
+                        // no sweep is run rather than one over the last valid temperatures, which are no longer shown
+                        self.schematic.set_temperature_sweep(vec![]);
+                        self.status = Some(format!("Error: {} are not temperatures", s.trim()));
+                    },
+                }
+                self.temperature_sweep_text = s;
             },
-            Msg::TextInputChanged(s) => {
-                self.text = s;
+            Msg::RunTemperatureSweep => {
+                self.run_temperature_sweep();
+            },
+            Msg::LowQualityToggled(low_quality) => {
+                self.config.low_quality = low_quality;
+                self.schematic.set_low_quality(low_quality);
+                self.invalidate_passive();
+                self.active_cache.clear();
+                self.save_config();
+            },
+            Msg::SnapFitZoomToggled(snap) => {
+                self.config.snap_fit_zoom = snap;
+                self.save_config();
+            },
+            Msg::ThemeSelected(theme) => {
+                self.config.theme = theme;
+                self.palette = Palette::from_preference(theme);
+                self.schematic.set_annotation_color(self.palette.foreground);
+                self.background_cache.clear();
+                self.invalidate_passive();
+                self.save_config();
+            },
+            Msg::PromptOnPlaceToggled(prompt) => {
+                self.config.prompt_on_place = prompt;
+                self.schematic.set_prompt_on_place(prompt);
+                self.save_config();
+            },
+            Msg::ClickCyclesToggled(cycles) => {
+                self.config.click_cycles = cycles;
+                self.schematic.set_click_cycles(cycles);
+                self.save_config();
+            },
+            Msg::AnimateSelectionToggled(animate) => {
+                self.config.animate_selection = animate;
+                self.active_cache.clear();
+                self.save_config();
+            },
+            Msg::ShowMinimapToggled(show) => {
+                self.config.hide_minimap = !show;
+                self.invalidate_passive();
+                self.save_config();
+            },
+            Msg::ShowPinNamesToggled(show) => {
+                self.config.show_pin_names = show;
+                self.schematic.set_show_pin_names(show);
+                self.invalidate_passive();
+                self.save_config();
+            },
+            Msg::AnimationFrame => {
+                // the selection is also drawn to the passive cache, only the animated outline is redrawn
+                self.active_cache.clear();
+            },
+            Msg::CursorShapeSelected(shape) => {
+                self.config.cursor.shape = shape;
+                self.active_cache.clear();
+                self.save_config();
+            },
+            Msg::GridStyleSelected(style) => {
+                self.config.grid_style = style;
+                self.invalidate_passive();
+                self.save_config();
+            },
+            Msg::NetClassSelected(class) => {
+                let class = Some(class).filter(|c| c != NO_NET_CLASS);
+                if self.schematic.assign_net_class_selected(class) {
+                    self.invalidate_passive();
+                } else {
+                    self.status = Some(String::from("select wires to assign their nets to a class"));
+                }
+            },
+            Msg::PlacementValueChanged(s) => {
+                self.placement_value_text = s;
+            },
+            Msg::PlacementValueSubmit => {
+                if self.schematic.submit_placement_value(&self.placement_value_text, self.curpos_ssp) {
+                    self.placement_value_text.clear();
+                    self.canvas_focused.set(true);
+                    self.active_cache.clear();
+                }
+            },
+            Msg::PlacementCancel => {
+                self.schematic.cancel_placement_prompt();
+                self.placement_value_text.clear();
+                self.canvas_focused.set(true);
+            },
+            Msg::SaveAsChanged(s) => {
+                self.save_as_text = s;
+            },
+            Msg::SaveAsSubmit => {
+                let path = self.save_as_text.trim().to_string();
+                if let Some(view) = self.view.get() {
+                    self.schematic.set_view(view);
+                }
+                self.status = match self.schematic.save_as(&path) {
+                    Ok(schematic::SaveStatus::Saved { overwrote: true }) => Some(format!("overwrote {}", path)),
+                    Ok(schematic::SaveStatus::Saved { overwrote: false }) => Some(format!("saved {}", path)),
+                    Ok(schematic::SaveStatus::NeedsConfirmation) => None,
+                    Err(e) => Some(format!("Error: Could not save schematic: {}", e)),
+                };
+            },
+            Msg::OverwriteConfirm => {
+                let path = self.schematic.overwrite_prompt().unwrap_or_default().to_string();
+                self.status = self.schematic.confirm_overwrite().err().map(|e| format!("Error: Could not write {}: {}", path, e));
+                self.canvas_focused.set(true);
+            },
+            Msg::OverwriteCancel => {
+                self.schematic.cancel_overwrite();
+                self.canvas_focused.set(true);
+            },
+            Msg::OverwriteWithoutAskingToggled(overwrite) => {
+                self.config.overwrite_without_asking = overwrite;
+                self.schematic.set_confirm_overwrite(!overwrite);
+                self.save_config();
+            },
+            Msg::RawPathChanged(s) => {
+                self.raw_path_text = s;
+            },
+            Msg::LoadRaw => {
+                self.status = match schematic::raw::parse(self.raw_path_text.trim()) {
+                    Ok(results) => match results.last_point() {
+                        Some(point) => {
+                            self.schematic.op(&point);
+                            self.invalidate_passive();
+                            None
+                        },
+                        None => Some(format!("{} has no points", self.raw_path_text.trim())),
+                    },
+                    Err(e) => Some(format!("Error: {}", e)),
+                };
+            },
+            Msg::BaselineNameChanged(s) => {
+                self.baseline_name_text = s;
+            },
+            Msg::SaveBaseline => {
+                if !self.schematic.save_op_baseline(&self.baseline_name_text) {
+                    self.status = Some(String::from("run a dc op simulation before saving a baseline"));
+                }
             },
             Msg::TextInputSubmit => {
+                if let Some(ad) = self.active_device.clone() {
+                    self.schematic.set_device_params(&ad, &self.params, &self.attr_text);
+                    self.invalidate_passive();
+                    self.update_recent_params();
+                }
+            },
+            Msg::RecentParamsSelected(i) => {
+                if let Some(params) = self.recent_params.get(i) {
+                    self.params = params.clone();
+                    return self.update(Msg::TextInputSubmit);
+                }
+            },
+            Msg::SetDefaultParams => {
                 if let Some(ad) = &self.active_device {
-                    ad.0.borrow_mut().class_mut().set(self.text.clone());
-                    self.passive_cache.clear();
+                    let class_name = ad.0.borrow().class().name();
+                    self.schematic.set_device_defaults(class_name, self.params.clone());
+                    self.config.device_defaults = self.schematic.device_defaults().clone();
+                    self.save_config();
                 }
             },
-            Msg::CanvasEvent(event, ssp) => {
-                let (opt_s, clear_passive) = self.schematic.events_handler(event, ssp);
-                if clear_passive {self.passive_cache.clear()}
+            Msg::CanvasEvent(event, ..) if self.schematic.action(&event) == Some(Action::ToggleCheatSheet) => {
+                self.show_cheat_sheet = !self.show_cheat_sheet;
+                self.active_cache.clear();
+            },
+            Msg::CanvasEvent(event, ssp, vsp) => {
+                if let Some(view) = self.view.get() {
+                    self.schematic.set_view(view);
+                }
+                self.schematic.set_curpos_placement(vsp);
+                let action = self.schematic.action(&event);
+                let (opt_s, clear_passive, clear_active) = self.schematic.events_handler(event, ssp);
+                // a measurement refers to the selection it was taken of
+                if self.schematic.take_selection_changed() || action == Some(Action::Cancel) {
+                    self.status = None;
+                }
+                if let Some(view) = self.schematic.take_opened_view() {
+                    self.view_request.set(Some(view));
+                }
+                if clear_passive {self.invalidate_passive()}
+                if clear_active || clear_passive {self.active_cache.clear()}
+                if let Some(vsb) = self.schematic.take_added_bounds() {
+                    if self.auto_fit {
+                        self.fit_request.set(Some(vsb));
+                    }
+                }
                 self.net_name = opt_s;
                 self.curpos_ssp = ssp;
                 self.active_device = self.schematic.active_device();
                 if let Some(rcrd) = &self.active_device {
-                    self.text = rcrd.0.borrow().class().param_summary();
+                    let d = rcrd.0.borrow();
+                    self.param_title = format!("{} ({})", d.ng_id(), d.class().name());
+                    self.params = d.class().params();
+                    self.attr_text = d.attributes_summary();
+                    self.recent_params = self.schematic.recent_params().get(d.class().name()).cloned().unwrap_or_default();
                 } else {
-                    self.text = String::from("");
+                    self.param_title = String::from("");
+                    self.params = vec![];
+                    self.attr_text = String::from("");
+                    self.recent_params = vec![];
                 }
-                if let Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::Space, modifiers: _}) = event {
-                    self.lib.command("source netlist.cir");  // results pointer array starts at same address
-                    self.lib.command("op");  // ngspice recommends sending in control statements separately, not as part of netlist
-                    if let Some(pkvecvaluesall) = self.spmanager.tmp.as_ref() {
-                        self.schematic.op(pkvecvaluesall);
+                if let (Some(Action::RunDcOp), None) = (action, self.schematic.error()) {
+                    match Schematic::session_file("netlist.cir") {
+                        Err(e) => self.status = Some(format!("Error: Could not write netlist: {}", e)),
+                        Ok(path) => {
+                            self.spmanager.take_errors();
+                            self.spmanager.take_data();
+                            // results pointer array starts at same address
+                            self.lib.command(&format!("source \"{}\"", path));
+                            if self.schematic.has_directives() {
+                                self.lib.command("run");  // run the analyses of the control statements
+                            } else {
+                                self.lib.command("op");  // ngspice recommends sending in control statements separately, not as part of netlist
+                                if let Some(pkvecvaluesall) = self.spmanager.take_data() {
+                                    self.schematic.op(&pkvecvaluesall);
+                                }
+                            }
+                            self.report_ngspice_errors();
+                        },
                     }
-                    
+                }
+                if self.schematic.take_opened() {
+                    self.sync_sim_texts();
+                }
+                if action == Some(Action::MeasureImpedance) {
+                    self.measure_impedance();
+                }
+                if action == Some(Action::OpenDatasheet) {
+                    self.status = match self.schematic.active_datasheet_url() {
+                        Some(url) => open_url(&url).err().map(|e| format!("Error: Could not open {}: {}", url, e)),
+                        None => Some(String::from("select a single device with a datasheet_url attribute")),
+                    };
+                }
+            },
+            Msg::ThousandsSeparatorChanged(s) => {
+                let mut nf = self.config.number_format.clone();
+                nf.thousands_separator = s;
+                self.set_number_format(nf);
+            },
+            Msg::DecimalSeparatorChanged(s) => {
+                let mut nf = self.config.number_format.clone();
+                nf.decimal_separator = s;
+                self.set_number_format(nf);
+            },
+            Msg::ZoomPrecisionChanged(s) => {
+                if let Ok(precision) = s.trim().parse::<usize>() {
+                    let mut nf = self.config.number_format.clone();
+                    nf.zoom_precision = precision.min(6);
+                    self.set_number_format(nf);
+                }
+                self.zoom_precision_text = s;
+            },
+            Msg::NetSearchChanged(s) => {
+                self.net_search_text = s;
+            },
+            Msg::NetSearchSubmit => {
+                match self.schematic.find_net(&self.net_search_text) {
+                    Ok(vsb) => {
+                        self.fit_request.set(Some(vsb));
+                        self.status = None;
+                    },
+                    Err(e) => self.status = Some(e),
+                }
+                self.active_cache.clear();
+            },
+            Msg::ReplaceClassSelected(name) => {
+                self.replace_class = Some(name);
+                self.replace_param = schematic::param_names(name).into_iter().next();
+            },
+            Msg::ReplaceParamSelected(name) => {
+                self.replace_param = Some(name);
+            },
+            Msg::ReplaceFromChanged(s) => {
+                self.replace_from_text = s;
+            },
+            Msg::ReplaceToChanged(s) => {
+                self.replace_to_text = s;
+            },
+            Msg::ReplaceValuesSubmit => {
+                let Some(class_name) = self.replace_class else {
+                    self.status = Some(String::from("pick a device type to replace values of"));
+                    return Command::none();
+                };
+                let Some(param) = &self.replace_param else {
+                    self.status = Some(format!("{} has no parameters to replace", class_name));
+                    return Command::none();
+                };
+                match self.schematic.replace_values(class_name, param, &self.replace_from_text, &self.replace_to_text) {
+                    Ok(n) => {
+                        self.status = Some(format!("replaced {} {} {} values", n, class_name, param));
+                        if n > 0 {
+                            self.invalidate_passive();
+                            self.update_recent_params();
+                        }
+                    },
+                    Err(e) => self.status = Some(format!("Error: {}", e)),
+                }
+            },
+            Msg::ArrowLabelChanged(s) => {
+                self.schematic.set_arrow_label(&s);
+                self.arrow_label_text = s;
+            },
+            Msg::ArrowLabelSubmit => {
+                if self.schematic.relabel_selected_arrows() {
+                    self.invalidate_passive();
+                }
+            },
+            Msg::InitialVoltageChanged(s) => {
+                self.initial_voltage_text = s;
+            },
+            Msg::InitialVoltageSubmit => {
+                let text = self.initial_voltage_text.trim();
+                let volts = schematic::parse_spice_value(text);
+                if volts.is_none() && !text.is_empty() {
+                    self.status = Some(format!("Error: {} is not a voltage", text));
+                } else if !self.schematic.set_initial_condition_selected(volts) {
+                    self.status = Some(String::from("select wires to set their initial voltage"));
+                } else {
+                    self.status = None;
+                }
+            },
+            Msg::PalettePicked(name) => {
+                // the palette is not a text input, hotkeys e.g. rotate keep working while placing
+                self.canvas_focused.set(true);
+                self.palette_pick.set(Some(name));
+            },
+            Msg::PlaceDevice(name, ssp) => {
+                if self.schematic.place_device(name, ssp) {
+                    self.active_cache.clear();
                 }
             },
             Msg::TabSel(i) => {
                 self.active_tab = i;
             },
+            Msg::FlushPassive => {
+                // the canvas is redrawn after every message, clearing the passive cache once the debounce interval passed
+            },
         }
-        Command::none()
+        let mut commands = vec![];
+        // a device key was just pressed with value prompts on, hotkeys are ignored while the value is typed
+        if !prompting && self.schematic.placement_prompt().is_some() {
+            self.canvas_focused.set(false);
+            commands.push(text_input::focus(text_input::Id::new(PLACEMENT_VALUE_ID)));
+        }
+        Command::batch(commands)
     }
 
     fn view(&self) -> Element<Msg> {
         let canvas = canvas(self as &Self)
             .width(Length::Fill)
             .height(Length::Fill);
-        let infobar = infobar(self.curpos_ssp, self.zoom_scale, self.net_name.clone());
-        let pe = param_editor(self.text.clone(), Msg::TextInputChanged, || {Msg::TextInputSubmit});
+        let infobar = infobar(
+            self.curpos_ssp, 
+            self.viewport_scale.get(), 
+            self.net_name.clone(), 
+            self.schematic.wire_lengths(), 
+            self.schematic.error().or(self.status.clone()).or(self.schematic.warning()),
+            self.config.number_format.clone(),
+        );
+        let pe = param_editor(
+            self.param_title.clone(), 
+            self.params.clone(), 
+            self.attr_text.clone(), 
+            Msg::ParamInputChanged, 
+            Msg::AttrInputChanged, 
+            || {Msg::TextInputSubmit},
+            || {Msg::SetDefaultParams},
+        ).with_recent(self.recent_params.clone(), Msg::RecentParamsSelected);
+        let prompt: Element<Msg> = match self.schematic.placement_prompt() {
+            Some((class, name, value)) => column![
+                text(format!("{} {}", class, name)).size(16),
+                text_input(&value, &self.placement_value_text)
+                    .id(text_input::Id::new(PLACEMENT_VALUE_ID))
+                    .width(155)
+                    .on_input(Msg::PlacementValueChanged)
+                    .on_submit(Msg::PlacementValueSubmit),
+                button("cancel").on_press(Msg::PlacementCancel),
+                text("enter to place, blank keeps the default").size(12),
+            ].spacing(5).into(),
+            None => Column::new().into(),
+        };
+        let overwrite: Element<Msg> = match self.schematic.overwrite_prompt() {
+            Some(path) => column![
+                text(format!("{} exists", path)).size(16),
+                row![
+                    button("overwrite").on_press(Msg::OverwriteConfirm),
+                    button("cancel").on_press(Msg::OverwriteCancel),
+                ].spacing(5),
+            ].spacing(5).into(),
+            None => Column::new().into(),
+        };
         let schematic = row![
-            pe, 
+            column![
+                self.palette_view(), prompt, overwrite, horizontal_rule(10), 
+                pe, horizontal_rule(10), 
+                self.file_view(), horizontal_rule(10), 
+                self.edit_view(), horizontal_rule(10), 
+                self.simulation_view(), horizontal_rule(10), 
+                self.settings_view(),
+            ].width(Length::Shrink), 
             column![
                 canvas, 
                 infobar
@@ -258,6 +1331,8 @@ impl Application for Circe {
 
         let tabs = Tabs::with_tabs(self.active_tab, vec![
             (TabLabel::Text("Schematic".to_string()), schematic.into()),
+            (TabLabel::Text("Op Comparison".to_string()), self.op_comparison_view()),
+            (TabLabel::Text("Control Statements".to_string()), self.directives_view()),
             (TabLabel::Text("Device Creator".to_string()), iced::widget::text("placeholder").into())
         ], Msg::TabSel);
 
@@ -267,6 +1342,25 @@ impl Application for Circe {
 
 use viewport::Viewport;
 
+/// device symbol drawn as an icon, e.g. in the palette
+struct SymbolIcon(&'static str);
+
+impl canvas::Program<Msg> for SymbolIcon {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = canvas::Frame::new(bounds.size());
+        schematic::draw_symbol_icon(self.0, &mut frame);
+        vec![frame.into_geometry()]
+    }
+}
+
 impl canvas::Program<Msg> for Circe {
     type State = Viewport;
 
@@ -279,32 +1373,100 @@ impl canvas::Program<Msg> for Circe {
     ) -> (event::Status, Option<Msg>) {
         
         let curpos = cursor.position_in(&bounds);
-        let vstate = viewport.state.clone();
+        viewport.snap_fit_zoom = self.config.snap_fit_zoom;
         let mut msg = None;
+
+        if let Event::Mouse(iced::mouse::Event::ButtonPressed(_)) = event {
+            self.canvas_focused.set(curpos.is_some());
+        }
+        // the canvas may be resized while a text input has focus
+        if viewport.resize(CSBox::from_points([CSPoint::origin(), CSPoint::new(bounds.width, bounds.height)])) {
+            self.passive_cache.clear();
+            self.active_cache.clear();
+        }
+        if typed_elsewhere(&event, self.canvas_focused.get()) {
+            return (event::Status::Ignored, None);
+        }
         
         if let Some(curpos_csp) = curpos.map(|x| Point::from(x).into()) {
-            if let Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code, modifiers}) = event {
-                if let (_, iced::keyboard::KeyCode::F, 0, _) = (vstate, key_code, modifiers.bits(), curpos) {
-                    let vsb = self.schematic.bounding_box().inflate(5., 5.);
-                    viewport.display_bounds(
-                        CSBox::from_points([CSPoint::origin(), CSPoint::new(bounds.width, bounds.height)]), 
-                        vsb,
-                    );
+            let csb = CSBox::from_points([CSPoint::origin(), CSPoint::new(bounds.width, bounds.height)]);
+            if let Some(vsb) = self.fit_request.take() {
+                viewport.display_bounds(csb, vsb.inflate(5., 5.));
+                self.passive_cache.clear();
+                self.active_cache.clear();
+            }
+            // a view saved with an opened schematic is restored, or the schematic is fitted if the saved view is invalid
+            if let Some(view) = self.view_request.take() {
+                if !viewport.restore_view(view, csb) {
+                    viewport.display_bounds(csb, self.schematic.bounding_box().inflate(5., 5.));
+                }
+                self.passive_cache.clear();
+                self.active_cache.clear();
+            }
+            self.view.set(Some(viewport.view(csb)));
+            // clicking or dragging in the minimap pans the view, the schematic underneath does not see the click.
+            // While placing, wiring or moving, clicks go to the schematic.
+            if let Some(minimap) = self.minimap(csb) {
+                match (&viewport.state, &self.schematic.state, event) {
+                    (ViewportState::None, SchematicState::Idle, Event::Mouse(iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left))) 
+                    if minimap.contains(curpos_csp) => {
+                        viewport.state = ViewportState::Minimap;
+                    },
+                    (ViewportState::Minimap, _, Event::Mouse(iced::mouse::Event::CursorMoved { .. })) => {},
+                    (ViewportState::Minimap, _, Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left))) => {
+                        viewport.state = ViewportState::None;
+                        return (event::Status::Captured, None);
+                    },
+                    _ => {},
+                }
+                if let ViewportState::Minimap = viewport.state {
+                    viewport.center_on(minimap.vsp_at(curpos_csp), csb);
                     self.passive_cache.clear();
+                    self.active_cache.clear();
+                    return (event::Status::Captured, None);
                 }
             }
+            match self.schematic.action(&event) {
+                Some(Action::FitView) => {
+                    let vsb = self.schematic.bounding_box().inflate(5., 5.);
+                    viewport.display_bounds(csb, vsb);
+                    self.passive_cache.clear();
+                    self.active_cache.clear();
+                },
+                Some(Action::FitSelection) => {
+                    if let Some(vsb) = self.schematic.selected_bounding_box() {
+                        viewport.display_bounds(csb, vsb.inflate(5., 5.));
+                        self.passive_cache.clear();
+                        self.active_cache.clear();
+                    }
+                },
+                _ => {},
+            }
 
+            if let (Event::Mouse(iced::mouse::Event::CursorMoved { .. }), true) = (event, self.schematic.dragging()) {
+                if viewport.auto_pan(curpos_csp, csb, &self.config.auto_pan) {
+                    self.passive_cache.clear();
+                }
+            }
             let (msg0, clear_passive0, processed) = viewport.events_handler(event, curpos_csp, bounds);
             if !processed {
-                msg = Some(Msg::CanvasEvent(event, viewport.curpos_ssp()));
+                // the schematic decides whether the active cache is cleared, see Msg::CanvasEvent
+                msg = Some(Msg::CanvasEvent(event, viewport.curpos_ssp(), viewport.curpos_placement(self.schematic.snap_required())));
             } else {
                 if clear_passive0 { self.passive_cache.clear() }
                 msg = msg0;
+                self.active_cache.clear();
+            }
+            if let Event::Mouse(iced::mouse::Event::CursorMoved { .. }) | Event::Keyboard(iced::keyboard::Event::ModifiersChanged(_)) = event {
+                self.active_cache.clear();  // the cursor, and whether it is snapped, is drawn on the active cache
+            }
+            if let (Event::Mouse(iced::mouse::Event::CursorMoved { .. }), Some(name)) = (event, self.palette_pick.get()) {
+                self.palette_pick.set(None);
+                msg = Some(Msg::PlaceDevice(name, viewport.curpos_ssp()));
             }
-            
-            self.active_cache.clear();
         }
 
+        self.viewport_scale.set(viewport.vc_scale());
         if msg.is_some() {
             (event::Status::Captured, msg)
         } else {
@@ -321,7 +1483,14 @@ impl canvas::Program<Msg> for Circe {
     ) -> Vec<Geometry> {
         let active = self.active_cache.draw(bounds.size(), |frame| {
             self.schematic.draw_active(viewport.vc_transform(), viewport.vc_scale(), frame);
-            viewport.draw_cursor(frame);
+            if self.config.animate_selection {
+                self.schematic.draw_selection_ants(viewport.vc_transform(), viewport.vc_scale(), frame, self.animation_start.elapsed().as_secs_f32());
+            }
+            viewport.draw_cursor(frame, self.schematic.snap_required(), self.config.low_quality, &self.config.cursor);
+            viewport.draw_range_warning(frame);
+            if self.show_cheat_sheet {
+                cheatsheet::draw(frame, CSBox::new(CSPoint::origin(), CSPoint::from([bounds.width, bounds.height])), &self.config.key_bindings, &self.palette);
+            }
 
             if let ViewportState::NewView(vsp0, vsp1) = viewport.state {
                 let csp0 = viewport.vc_transform().transform_point(vsp0);
@@ -335,14 +1504,26 @@ impl canvas::Program<Msg> for Circe {
             }
         });
 
+        if self.passive_redraw.take_due(Instant::now(), Duration::from_millis(self.config.passive_debounce_ms)) {
+            self.passive_cache.clear();
+        }
         let passive = self.passive_cache.draw(bounds.size(), |frame| {
-            viewport.draw_grid(frame, CSBox::new(CSPoint::origin(), CSPoint::from([bounds.width, bounds.height])));
+            viewport.draw_grid(frame, CSBox::new(CSPoint::origin(), CSPoint::from([bounds.width, bounds.height])), !self.config.low_quality, self.config.grid_style, self.schematic.grid_origin(), &self.palette);
             self.schematic.draw_passive(viewport.vc_transform(), viewport.vc_scale(), frame);
+            let csb = CSBox::new(CSPoint::origin(), CSPoint::from([bounds.width, bounds.height]));
+            viewport.draw_gizmo(frame, csb, &self.palette);
+            // the scale bar moves left of the minimap
+            if let Some(minimap) = self.minimap(csb) {
+                viewport.draw_scale_bar(frame, CSBox::new(csb.min, CSPoint::new(minimap.bounds().min.x, csb.max.y)), &self.palette);
+                minimap.draw(frame, &self.schematic, viewport.visible_vsb(csb), &self.palette);
+            } else {
+                viewport.draw_scale_bar(frame, csb, &self.palette);
+            }
         });
 
         let background = self.background_cache.draw(bounds.size(), |frame| {
             let f = canvas::Fill {
-                style: canvas::Style::Solid(Color::from_rgb(0.2, 0.2, 0.2)),
+                style: canvas::Style::Solid(self.palette.background),
                 ..canvas::Fill::default()
             };
             frame.fill_rectangle(iced::Point::ORIGIN, bounds.size(), f);
@@ -359,10 +1540,12 @@ impl canvas::Program<Msg> for Circe {
     ) -> mouse::Interaction {
         if cursor.is_over(&bounds) {
             match (&viewport.state, &self.schematic.state) {
-                (ViewportState::Panning(_), _) => mouse::Interaction::Grabbing,
+                (ViewportState::Panning(_) | ViewportState::Minimap, _) => mouse::Interaction::Grabbing,
                 (ViewportState::None, SchematicState::Idle) => mouse::Interaction::default(),
-                (ViewportState::None, SchematicState::Wiring(_)) => mouse::Interaction::Crosshair,
+                (ViewportState::None, SchematicState::Wiring(..)) => mouse::Interaction::Crosshair,
                 (ViewportState::None, SchematicState::Moving(_)) => mouse::Interaction::ResizingVertically,
+                (ViewportState::None, SchematicState::Bending(None)) => mouse::Interaction::Grab,
+                (ViewportState::None, SchematicState::Bending(Some(_))) => mouse::Interaction::Grabbing,
                 _ => mouse::Interaction::default(),
             }
         } else {
@@ -376,14 +1559,20 @@ mod infobar {
     use iced::alignment::{self};
     use iced::widget::{row, text};
     use iced_lazy::{component, Component};
-    use iced::{Element, Renderer};
+    use iced::{Element, Renderer, Color};
 
-    use crate::transforms::SSPoint;
+    use crate::transforms::{SSPoint, SSCoord};
+    use crate::config::NumberFormat;
 
     pub struct InfoBar {
         curpos_ssp: SSPoint,
         zoom_scale: f32,
         net_name: Option<String>,
+        /// length of the wire segment being drawn and total length of the wire, if wiring
+        wire_lengths: Option<(SSCoord, SSCoord)>,
+        message: Option<String>,
+        /// separators and precision of displayed numbers
+        number_format: NumberFormat,
     }
     
     impl InfoBar {
@@ -391,11 +1580,17 @@ mod infobar {
             curpos_ssp: SSPoint,
             zoom_scale: f32,
             net_name: Option<String>,
+            wire_lengths: Option<(SSCoord, SSCoord)>,
+            message: Option<String>,
+            number_format: NumberFormat,
         ) -> Self {
             Self {
                 curpos_ssp,
                 zoom_scale,
                 net_name,
+                wire_lengths,
+                message,
+                number_format,
             }
         }
     }
@@ -404,8 +1599,11 @@ mod infobar {
         curpos_ssp: SSPoint,
         zoom_scale: f32,
         net_name: Option<String>,
+        wire_lengths: Option<(SSCoord, SSCoord)>,
+        message: Option<String>,
+        number_format: NumberFormat,
     ) -> InfoBar {
-        InfoBar::new(curpos_ssp, zoom_scale, net_name)
+        InfoBar::new(curpos_ssp, zoom_scale, net_name, wire_lengths, message, number_format)
     }
 
     impl<Message> Component<Message, Renderer> for InfoBar {
@@ -420,12 +1618,17 @@ mod infobar {
             None
         }
         fn view(&self, _state: &Self::State) -> Element<(), Renderer> {
-            let str_ssp = format!("x: {}; y: {}", self.curpos_ssp.x, self.curpos_ssp.y);
+            let nf = &self.number_format;
+            let str_ssp = format!("x: {}; y: {}", nf.int(self.curpos_ssp.x.into()), nf.int(self.curpos_ssp.y.into()));
             let s = self.net_name.as_deref().unwrap_or_default();
+            let m = self.message.as_deref().unwrap_or_default();
+            let l = self.wire_lengths.map(|(segment, total)| format!("segment: {}; total: {}", nf.int(segment.into()), nf.int(total.into()))).unwrap_or_default();
             row![
                 text(str_ssp).size(16).height(16).vertical_alignment(alignment::Vertical::Center),
-                text(&format!("{:04.1}", self.zoom_scale)).size(16).height(16).vertical_alignment(alignment::Vertical::Center),
+                text(nf.zoom(self.zoom_scale)).size(16).height(16).vertical_alignment(alignment::Vertical::Center),
                 text(s).size(16).height(16).vertical_alignment(alignment::Vertical::Center),
+                text(l).size(16).height(16).vertical_alignment(alignment::Vertical::Center),
+                text(m).size(16).height(16).vertical_alignment(alignment::Vertical::Center).style(Color::from_rgb(1.0, 0.3, 0.3)),
             ]
             .spacing(10)
             .into()
@@ -443,42 +1646,78 @@ mod infobar {
 }
 
 mod param_editor {
-    use iced::widget::{column, text_input, button};
+    use iced::widget::{Column, row, text, text_input, button, horizontal_rule, pick_list};
     use iced_lazy::{component, Component};
     use iced::{Length, Element, Renderer};
 
     #[derive(Debug, Clone)]
     pub enum Evt {
-        InputChanged(String),
+        InputChanged(usize, String),
+        AttrChanged(String),
         InputSubmit,
+        SetDefault,
+        RecentSelected(String),
+    }
+
+    /// returns the label of a set of recently applied parameters, e.g. "1k"
+    fn recent_label(params: &[(String, String)]) -> String {
+        params.iter().map(|(_, v)| v.as_str()).collect::<Vec<_>>().join(" ")
     }
 
+    /// form with one labeled field per device parameter, followed by the device attributes
     pub struct ParamEditor<Message> {
-        value: String,
-        on_change: Box<dyn Fn(String) -> Message>,
+        title: String,
+        params: Vec<(String, String)>,
+        attributes: String,
+        on_change: Box<dyn Fn(usize, String) -> Message>,
+        on_attr_change: Box<dyn Fn(String) -> Message>,
         on_submit: Box<dyn Fn() -> Message>,
+        on_set_default: Box<dyn Fn() -> Message>,
+        /// recently applied parameters, most recent first
+        recent: Vec<Vec<(String, String)>>,
+        on_recent: Option<Box<dyn Fn(usize) -> Message>>,
     }
     
     impl<Message> ParamEditor<Message> {
         pub fn new(
-            value: String,
-            on_change: impl Fn(String) -> Message + 'static,
+            title: String,
+            params: Vec<(String, String)>,
+            attributes: String,
+            on_change: impl Fn(usize, String) -> Message + 'static,
+            on_attr_change: impl Fn(String) -> Message + 'static,
             on_submit: impl Fn() -> Message + 'static,
+            on_set_default: impl Fn() -> Message + 'static,
         ) -> Self {
             Self {
-                value,
+                title,
+                params,
+                attributes,
                 on_change: Box::new(on_change),
+                on_attr_change: Box::new(on_attr_change),
                 on_submit: Box::new(on_submit),
+                on_set_default: Box::new(on_set_default),
+                recent: vec![],
+                on_recent: None,
             }
         }
+        /// offer recently applied parameters as suggestions, on_recent is called with the index of the selected one
+        pub fn with_recent(mut self, recent: Vec<Vec<(String, String)>>, on_recent: impl Fn(usize) -> Message + 'static) -> Self {
+            self.recent = recent;
+            self.on_recent = Some(Box::new(on_recent));
+            self
+        }
     }
 
     pub fn param_editor<Message>(
-        value: String,
-        on_change: impl Fn(String) -> Message + 'static,
+        title: String,
+        params: Vec<(String, String)>,
+        attributes: String,
+        on_change: impl Fn(usize, String) -> Message + 'static,
+        on_attr_change: impl Fn(String) -> Message + 'static,
         on_submit: impl Fn() -> Message + 'static,
+        on_set_default: impl Fn() -> Message + 'static,
     ) -> ParamEditor<Message> {
-        ParamEditor::new(value, on_change, on_submit)
+        ParamEditor::new(title, params, attributes, on_change, on_attr_change, on_submit, on_set_default)
     }
 
     impl<Message> Component<Message, Renderer> for ParamEditor<Message> {
@@ -491,22 +1730,62 @@ mod param_editor {
             event: Evt,
         ) -> Option<Message> {
             match event {
-                Evt::InputChanged(s) => {
-                    Some((self.on_change)(s))
+                Evt::InputChanged(i, s) => {
+                    Some((self.on_change)(i, s))
+                },
+                Evt::AttrChanged(s) => {
+                    Some((self.on_attr_change)(s))
                 },
                 Evt::InputSubmit => {
                     Some((self.on_submit)())
                 },
+                Evt::SetDefault => {
+                    Some((self.on_set_default)())
+                },
+                Evt::RecentSelected(label) => {
+                    let i = self.recent.iter().position(|p| recent_label(p) == label)?;
+                    self.on_recent.as_ref().map(|f| f(i))
+                },
             }
         }
         fn view(&self, _state: &Self::State) -> Element<Evt, Renderer> {
-            column![
-                text_input("", &self.value)
-                .width(50)
-                .on_input(Evt::InputChanged)
-                .on_submit(Evt::InputSubmit),
-                button("enter"),
-            ]
+            let mut col = Column::new().push(text(&self.title).size(16));
+            for (i, (name, value)) in self.params.iter().enumerate() {
+                col = col.push(
+                    row![
+                        text(name).size(16).width(50),
+                        text_input("", value)
+                        .width(100)
+                        .on_input(move |s| Evt::InputChanged(i, s))
+                        .on_submit(Evt::InputSubmit),
+                    ].spacing(5)
+                );
+            }
+            if !self.recent.is_empty() {
+                let labels: Vec<String> = self.recent.iter().map(|p| recent_label(p)).collect();
+                col = col.push(
+                    pick_list(labels, None, Evt::RecentSelected)
+                    .placeholder("recent values")
+                    .text_size(16)
+                    .width(155)
+                );
+            }
+            col
+            .push(horizontal_rule(10))
+            .push(text("attributes").size(16))
+            .push(
+                text_input("footprint=0805", &self.attributes)
+                .width(155)
+                .on_input(Evt::AttrChanged)
+                .on_submit(Evt::InputSubmit)
+            )
+            .push(
+                row![
+                    button("enter").on_press(Evt::InputSubmit),
+                    button("set default").on_press(Evt::SetDefault),
+                ].spacing(5)
+            )
+            .spacing(5)
             .width(Length::Shrink)
             .into()
         }
@@ -521,3 +1800,51 @@ mod param_editor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passive_redraws_are_debounced_until_after_the_last_edit() {
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+        let redraw = PassiveRedraw::new(t0);
+        assert!(!redraw.take_due(t0 + ms(50), ms(20)), "nothing to redraw");
+        redraw.invalidate();
+        assert!(redraw.take_due(t0 + ms(50), ms(20)));
+        // edits within the interval are coalesced, the last one is still drawn once it passed
+        redraw.invalidate();
+        assert!(!redraw.take_due(t0 + ms(60), ms(20)));
+        redraw.invalidate();
+        assert!(!redraw.take_due(t0 + ms(65), ms(20)));
+        assert!(redraw.is_dirty());
+        assert!(redraw.take_due(t0 + ms(70), ms(20)));
+        assert!(!redraw.is_dirty());
+        // without debounce every edit is drawn with the next frame
+        redraw.invalidate();
+        assert!(redraw.take_due(t0 + ms(70), Duration::ZERO));
+    }
+
+    #[test]
+    fn keys_typed_outside_the_canvas_are_withheld() {
+        let r = Event::Keyboard(iced::keyboard::Event::KeyPressed { key_code: iced::keyboard::KeyCode::R, modifiers: iced::keyboard::Modifiers::empty() });
+        assert!(typed_elsewhere(&r, false));
+        assert!(typed_elsewhere(&Event::Keyboard(iced::keyboard::Event::CharacterReceived('r')), false));
+        assert!(!typed_elsewhere(&r, true));
+        let alt = Event::Keyboard(iced::keyboard::Event::ModifiersChanged(iced::keyboard::Modifiers::ALT));
+        assert!(!typed_elsewhere(&alt, false));
+        let click = Event::Mouse(iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left));
+        assert!(!typed_elsewhere(&click, false));
+    }
+
+    #[test]
+    fn only_web_urls_are_opened() {
+        assert!(is_web_url("https://example.com/ds.pdf"));
+        assert!(is_web_url("HTTP://example.com"));
+        assert!(!is_web_url("file:///etc/passwd"));
+        assert!(!is_web_url("calc.exe"));
+        assert!(!is_web_url("https://"));
+        assert!(open_url("javascript:alert(1)").is_err());
+    }
+}
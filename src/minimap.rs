@@ -0,0 +1,87 @@
+//! minimap
+//! overview of the whole schematic in the lower right corner of the canvas, with the visible area outlined.
+//! Clicking or dragging in the minimap pans the view there.
+
+use circe::{transforms::{CSBox, CSPoint, VCTransform, VSBox, VSPoint, Point}, schematic::Schematic};
+use iced::{widget::canvas::{self, Frame, Path, Stroke, stroke, Fill}, Color, Size};
+
+use crate::appearance::Palette;
+
+/// placement and transform of the minimap on the canvas
+pub struct Minimap {
+    /// area of the minimap in canvas space
+    bounds: CSBox,
+    /// transform from viewport space to the minimap on the canvas
+    vct: VCTransform,
+}
+
+impl Minimap {
+    /// size of the minimap in pixels
+    const SIZE: Size = Size::new(160.0, 120.0);
+    /// distance between the minimap and the canvas edges in pixels
+    const MARGIN: f32 = 10.0;
+
+    /// returns the minimap showing vsb, the bounds of the schematic, on the canvas of bounds csb.
+    /// None if the schematic is empty or the canvas too small to hold the minimap.
+    pub fn new(csb: CSBox, vsb: VSBox) -> Option<Self> {
+        if vsb.width() <= 0.0 && vsb.height() <= 0.0 {
+            return None;
+        }
+        let max = CSPoint::new(csb.max.x - Minimap::MARGIN, csb.max.y - Minimap::MARGIN);
+        let bounds = CSBox::new(CSPoint::new(max.x - Minimap::SIZE.width, max.y - Minimap::SIZE.height), max);
+        if bounds.min.x < csb.min.x + Minimap::MARGIN || bounds.min.y < csb.min.y + Minimap::MARGIN {
+            return None;
+        }
+        let vsb = vsb.inflate(5.0, 5.0);
+        let s = (Minimap::SIZE.width / vsb.width()).min(Minimap::SIZE.height / vsb.height());
+        let vct = VCTransform::scale(s, -s);
+        let vct = vct.then_translate(bounds.center() - vct.transform_point(vsb.center()));
+        Some(Minimap { bounds, vct })
+    }
+    /// returns the area of the minimap in canvas space
+    pub fn bounds(&self) -> CSBox {
+        self.bounds
+    }
+    /// returns true if csp lies on the minimap
+    pub fn contains(&self, csp: CSPoint) -> bool {
+        self.bounds.contains(csp)
+    }
+    /// returns the point of the schematic shown at csp on the minimap
+    pub fn vsp_at(&self, csp: CSPoint) -> VSPoint {
+        self.vct.inverse().unwrap().transform_point(csp)
+    }
+    /// draw the minimap: the schematic overview and the outline of visible, the area shown by the main view
+    pub fn draw(&self, frame: &mut Frame, schematic: &Schematic, visible: VSBox, palette: &Palette) {
+        let size = Size::new(self.bounds.width(), self.bounds.height());
+        let background = Fill { style: canvas::Style::Solid(Color { a: 0.9, ..palette.background }), ..Fill::default() };
+        frame.fill_rectangle(Point::from(self.bounds.min).into(), size, background);
+        let border = Stroke { width: 1.0, style: stroke::Style::Solid(palette.foreground_alpha(0.5)), ..Stroke::default() };
+        frame.stroke(&Path::rectangle(Point::from(self.bounds.min).into(), size), border);
+
+        schematic.draw_overview(self.vct, frame, palette.foreground_alpha(0.7));
+
+        if let Some(view) = self.vct.outer_transformed_box(&visible).intersection(&self.bounds) {
+            let outline = Stroke { width: 1.5, style: stroke::Style::Solid(Color::from_rgb(1.0, 0.8, 0.0)), ..Stroke::default() };
+            frame.stroke(&Path::rectangle(Point::from(view.min).into(), Size::new(view.width(), view.height())), outline);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimap_maps_back_to_the_schematic() {
+        let csb = CSBox::new(CSPoint::origin(), CSPoint::new(800.0, 600.0));
+        let vsb = VSBox::new(VSPoint::new(-10.0, -5.0), VSPoint::new(30.0, 15.0));
+        let minimap = Minimap::new(csb, vsb).unwrap();
+        assert!(minimap.contains(CSPoint::new(700.0, 550.0)));
+        assert!(!minimap.contains(CSPoint::new(100.0, 100.0)));
+        let center = minimap.vsp_at(CSPoint::new(710.0, 530.0));
+        assert!((center - vsb.center()).length() < 1e-3);
+
+        assert!(Minimap::new(csb, VSBox::zero()).is_none());
+        assert!(Minimap::new(CSBox::new(CSPoint::origin(), CSPoint::new(100.0, 100.0)), vsb).is_none());
+    }
+}
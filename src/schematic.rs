@@ -4,21 +4,37 @@
 mod nets;
 mod devices;
 mod interactable;
+mod bom;
+mod history;
+mod file;
+mod kicad;
+mod opcompare;
+mod probe;
+mod arrow;
+pub mod raw;
 
-use std::{collections::HashSet, fs};
-use nets::{Nets, NetEdge, NetVertex};
+use std::{collections::{HashSet, BTreeMap, BTreeSet}, fs, io};
+use nets::{Nets, NetEdge, NetVertex, NetAssignments};
+use crate::config::{NumberFormat, NetStyle};
+use crate::keybindings::{Action, KeyBindings};
 use crate::transforms::{
-    self, SSPoint, VCTransform, VSBox, Point, SSBox, CSPoint, SSTransform, ViewportSpace, SSVec
+    self, SSPoint, VCTransform, VSBox, Point, SSBox, CSPoint, SSTransform, ViewportSpace, SSVec, SSCoord, VSPoint, CSVec
 };
 use iced::{
     widget::canvas::{
-        Frame, self, event::Event, path::Builder, Stroke, LineCap
+        Frame, self, event::Event, path::Builder, Stroke, LineCap, Path, Text
     }, 
-    Size, Color
+    Size, Color, alignment::{Horizontal, Vertical}
 };
-use self::{devices::Devices, interactable::Interactive};
+use self::{devices::Devices, interactable::{Interactive, VisualState}, history::{History, Snapshot}};
 
 pub use self::devices::RcRDevice;
+pub use self::bom::BomLine;
+pub use self::devices::{parse_spice_value, format_significant, class_names, param_names, draw_symbol_icon};
+pub use self::opcompare::{OpDelta, OpVoltages};
+pub use self::probe::Probe;
+pub use self::arrow::ArrowAnnotation;
+pub use self::file::ViewRecord;
 
 /// trait for element which can be drawn on canvas
 pub trait Drawable {
@@ -36,13 +52,15 @@ pub trait SchematicSet {
 pub enum BaseElement {
     NetEdge(NetEdge),
     Device(RcRDevice),
+    Arrow(ArrowAnnotation),
 }
 
 impl PartialEq for BaseElement {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::NetEdge(l0), Self::NetEdge(r0)) => *l0 == *r0,
-            (Self::Device(l0), Self::Device(r0)) => by_address::ByAddress(l0) == by_address::ByAddress(r0),
+            (Self::Device(l0), Self::Device(r0)) => l0 == r0,
+            (Self::Arrow(l0), Self::Arrow(r0)) => l0 == r0,
             _ => false,
         }
     }
@@ -50,22 +68,59 @@ impl PartialEq for BaseElement {
 
 impl Eq for BaseElement {}
 
+impl BaseElement {
+    /// returns true if the element is electrically connected to others through its position - such elements must always snap to the grid
+    pub fn is_electrical(&self) -> bool {
+        match self {
+            BaseElement::NetEdge(_) => true,
+            BaseElement::Device(_) => true,
+            BaseElement::Arrow(_) => false,
+        }
+    }
+}
+
 impl std::hash::Hash for BaseElement {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match self {
             BaseElement::NetEdge(e) => {e.hash(state)},
-            BaseElement::Device(d) => {by_address::ByAddress(d).hash(state)},
+            BaseElement::Device(d) => {d.hash(state)},
+            BaseElement::Arrow(a) => {a.hash(state)},
         }
     }
 }
 
 #[derive(Clone)]
 pub enum SchematicState {
-    Wiring(Option<(Box<Nets>, SSPoint)>),
+    Wiring(Option<(Box<Nets>, SSPoint, SSCoord)>, (SSPoint, bool)),
+    // wire being drawn, its starting point and the length of segments already committed, cursor point and whether clicking there would connect to existing geometry
     Idle,
-    Selecting(SSBox),
+    Selecting(SSBox, bool),
+    // selection box, whether the cursor was dragged since the button was pressed
     Moving(Option<(SSPoint, SSPoint, SSTransform)>),
     // first click, second click, transform for rotation/flip ONLY
+    Bending(Option<(NetEdge, SSPoint)>),
+    // wire segment being bent and the point its bend is dragged to
+    Annotating(Option<VSPoint>, VSPoint),
+    // tail of the arrow being placed, if clicked, and the cursor point its head follows
+}
+
+/// length of the wire routed from src to dst. Wires are routed horizontally and vertically only.
+fn wire_length(src: SSPoint, dst: SSPoint) -> SSCoord {
+    (dst.x - src.x).abs() + (dst.y - src.y).abs()
+}
+
+/// dash offset of marching ants at animation time t in seconds, advancing one dash every quarter second
+fn ants_offset(t: f32, dash: f32) -> usize {
+    ((t * 2.0).fract() * 2.0 * dash) as usize
+}
+
+/// returns the netlist line number an ngspice error message refers to, e.g. 3 for `Error on line 3 :`
+fn error_line(msg: &str) -> Option<usize> {
+    let lower = msg.to_ascii_lowercase();
+    lower.match_indices("line ").find_map(|(i, m)| {
+        let digits: String = lower[i + m.len()..].chars().take_while(char::is_ascii_digit).collect();
+        digits.parse().ok()
+    })
 }
 
 impl Default for SchematicState {
@@ -83,6 +138,74 @@ impl SchematicState {
     }
 }
 
+/// elements copied to be pasted later
+#[derive(Debug, Clone, Default)]
+struct Clipboard {
+    /// copied devices, not part of the schematic
+    devices: Vec<RcRDevice>,
+    /// copied net segments
+    edges: Vec<NetEdge>,
+    /// copied arrow annotations
+    arrows: Vec<ArrowAnnotation>,
+    /// cursor position at the time of copying - pasted elements are placed relative to the cursor
+    anchor: SSPoint,
+}
+
+/// outcome of saving the schematic to a path, see `Schematic::save_as`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveStatus {
+    /// the schematic was written. overwrote is true if it replaced a file not opened or saved before
+    Saved { overwrote: bool },
+    /// nothing was written, the file exists and overwriting it waits for `confirm_overwrite`
+    NeedsConfirmation,
+}
+
+/// kind of file written from the schematic, see `Schematic::write_file`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// the schematic itself, which becomes the file ctrl+s and ctrl+o use
+    Schematic,
+    /// spice netlist
+    Netlist,
+    /// bill of materials as csv
+    Bom,
+    /// KiCad netlist
+    KicadNetlist,
+}
+
+/// returns the private directory of this session in the temp dir, created under a fresh name readable by the user only
+fn session_dir() -> io::Result<&'static std::path::Path> {
+    static DIR: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
+    if let Some(dir) = DIR.get() {
+        return Ok(dir.as_path());
+    }
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_nanos());
+    let mut attempt = 0;
+    loop {
+        let dir = std::env::temp_dir().join(format!("circe_{}_{}_{}", std::process::id(), nanos, attempt));
+        match builder.create(&dir) {
+            Ok(()) => return Ok(DIR.get_or_init(|| dir).as_path()),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// simulation setup of a schematic
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SimConfig {
+    /// ngspice control statements appended to the netlist after the device lines, one per line, e.g. .tran 1u 1m
+    pub directives: Vec<String>,
+    /// simulation temperature in degrees celsius, ngspice defaults to 27 if none
+    pub temperature: Option<f32>,
+    /// temperatures in degrees celsius the operating point is swept over
+    pub temperature_sweep: Vec<f32>,
+}
+
 /// schematic
 #[derive(Default)]
 pub struct Schematic {
@@ -91,10 +214,108 @@ pub struct Schematic {
     pub state: SchematicState,
 
     selskip: usize,
+    /// point of the last plain click and the element it selected, cleared once the cursor moves to another point
+    last_click: Option<(SSPoint, BaseElement)>,
+    /// if true, clicking again at the point of the last click selects the next of the elements overlapping there, see `select_at`
+    click_cycles: bool,
+    /// keys bound to the keyboard actions, see `action`
+    key_bindings: KeyBindings,
     selected: HashSet<BaseElement>,
+
+    history: History,
+    clipboard: Clipboard,
+    /// bounding box of elements added by the last paste, to be taken by the viewport for fitting
+    added_bounds: Option<VSBox>,
+    /// true if an event changed the selection, to be taken by the frontend
+    selection_changed: bool,
+    /// error message of the last failed file operation, cleared on success
+    error: Option<String>,
+    /// if true, wire bends are drawn with rounded corners
+    rounded_corners: bool,
+    /// if true, every net is drawn with a badge counting its connected pins, see `connection_counts`
+    inspect_connections: bool,
+    /// if true, floating nets found before simulation are reported as errors, which prevents the simulation from running
+    block_floating_nets: bool,
+    /// if true, 0 ohm resistors are intentional jumpers, otherwise they block the simulation
+    zero_ohm_jumpers: bool,
+    /// if true, placing a device with parameters first asks for its value, see `placement_prompt`
+    prompt_on_place: bool,
+    /// new device waiting for its value before it follows the cursor
+    pending_placement: Option<RcRDevice>,
+    /// warning from the last electrical rule check or file operation, if any
+    warning: Option<String>,
+    /// file opened or saved last, ctrl+s saves to it without asking
+    file_path: Option<String>,
+    /// if true, saving over a file other than `file_path` or `exported` waits for confirmation
+    confirm_overwrite: bool,
+    /// file waiting for confirmation before it is overwritten and what is written to it, see `overwrite_prompt`
+    pending_overwrite: Option<(String, FileKind)>,
+    /// files netlists and bills of materials were written to in this session, written over again without asking
+    exported: HashSet<String>,
+    /// view of the canvas as last reported by the frontend, saved with the schematic
+    view: Option<ViewRecord>,
+    /// view saved with the schematic opened last, until taken by the frontend
+    opened_view: Option<ViewRecord>,
+    /// true if a schematic was opened, until taken by the frontend
+    opened: bool,
+    /// net voltages of the last operating point
+    op_voltages: opcompare::OpVoltages,
+    /// operating point results later runs are compared against
+    op_baseline: Option<opcompare::OpBaseline>,
+    /// net voltages of the operating point at each temperature of the last temperature sweep, labeled by temperature
+    sweep_results: Vec<(String, OpVoltages)>,
+    /// net found by name search, highlighted until cleared with esc
+    found_net: Option<String>,
+    /// analysis setup, saved with the schematic
+    sim_config: SimConfig,
+    /// voltage probes, saved with the schematic
+    probes: Vec<Probe>,
+    /// nets assigned to net classes, saved with the schematic
+    net_classes: NetAssignments<String>,
+    /// initial voltages of nets for transient analysis, saved with the schematic
+    initial_conditions: NetAssignments<f32>,
+    /// drawing style of each net class, keyed by class name
+    net_styles: BTreeMap<String, NetStyle>,
+    /// arrow annotations, saved with the schematic
+    arrows: Vec<ArrowAnnotation>,
+    /// label of newly placed arrow annotations
+    arrow_label: String,
+    /// identity of the arrow annotation added last, see `add_arrow`
+    arrow_wm: usize,
+    /// point the drawn grid is anchored at, saved with the schematic
+    grid_origin: SSPoint,
+    /// lines of each device in the last generated netlist, counting from 1 for the title line
+    netlist_lines: Vec<(std::ops::Range<usize>, RcRDevice)>,
+    /// cursor position of the last cursor event, where the hover tooltip is drawn
+    hover_ssp: SSPoint,
+    /// unsnapped cursor position for the next event, where non-electrical elements are placed
+    placement_vsp: Option<VSPoint>,
+    /// device port near the cursor, snapped to while wiring
+    snap_port: Option<SSPoint>,
+    /// if true, optional indicators are left out of the active cache to reduce per-frame drawing
+    low_quality: bool,
 }
 
 impl Schematic {
+    /// identifier of the test current source used for impedance measurement
+    const MEASURE_SOURCE_ID: &str = "Icirce_measure";
+    /// file the schematic is saved to and opened from, until saved as another file
+    const SCHEMATIC_PATH: &str = "schematic.circe";
+    /// distance along each axis within which the cursor snaps to device ports while wiring
+    const PORT_SNAP_DISTANCE: SSCoord = 1;
+
+    /// returns the device under the cursor, if it is the only tentative element
+    fn hovered_device(&self) -> Option<RcRDevice> {
+        if self.nets.tentatives().next().is_some() {
+            return None;
+        }
+        let mut v: Vec<_> = self.devices.tentatives().collect();
+        if v.len() == 1 {
+            v.pop()
+        } else {
+            None
+        }
+    }
     /// returns `Some<RcRDevice>` if there is exactly 1 device in selected, otherwise returns none
     pub fn active_device(&self) -> Option<RcRDevice> {
         let mut v: Vec<_> = self.selected.iter().filter_map(|x| {
@@ -109,6 +330,136 @@ impl Schematic {
             None
         }
     }
+    /// returns the length of the wire segment being drawn and the total length of the wire including committed segments, if wiring
+    pub fn wire_lengths(&self) -> Option<(SSCoord, SSCoord)> {
+        match &self.state {
+            SchematicState::Wiring(Some((_, prev_ssp, total)), (ssp, _)) => {
+                let segment = wire_length(*prev_ssp, *ssp);
+                Some((segment, total + segment))
+            },
+            _ => None,
+        }
+    }
+    /// returns the datasheet url of the active device, if any
+    pub fn active_datasheet_url(&self) -> Option<String> {
+        self.active_device().and_then(|d| d.0.borrow().datasheet_url().map(String::from))
+    }
+    /// returns true while elements are dragged along with the cursor: moving, wiring or selecting by box
+    pub fn dragging(&self) -> bool {
+        matches!(self.state, SchematicState::Moving(Some(_)) | SchematicState::Wiring(Some(_), _) | SchematicState::Selecting(..))
+    }
+    /// returns true if the element(s) being placed must snap to the grid, regardless of the snap-off modifier
+    pub fn snap_required(&self) -> bool {
+        match &self.state {
+            SchematicState::Wiring(..) => true,
+            SchematicState::Moving(_) => self.selected.iter().any(|be| be.is_electrical()),
+            _ => false,
+        }
+    }
+    /// returns a snapshot of the current schematic, including selection
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            nets: self.nets.clone(),
+            devices: self.devices.snapshot(),
+            arrows: self.arrows.clone(),
+            net_classes: self.net_classes.clone(),
+            initial_conditions: self.initial_conditions.clone(),
+            grid_origin: self.grid_origin,
+            selected: self.selected.iter().cloned().collect(),
+        }
+    }
+    /// restore the schematic to snapshot. Selected elements which do not exist in the restored schematic are dropped.
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.nets = snapshot.nets;
+        self.devices.restore(&snapshot.devices);
+        self.arrows = snapshot.arrows;
+        self.net_classes = snapshot.net_classes;
+        self.initial_conditions = snapshot.initial_conditions;
+        self.grid_origin = snapshot.grid_origin;
+        self.clear_tentatives();
+        self.selected = snapshot.selected.into_iter().filter(|be| {
+            match be {
+                BaseElement::NetEdge(e) => self.nets.graph.contains_edge(NetVertex(e.src), NetVertex(e.dst)),
+                BaseElement::Device(d) => self.devices.contains(d),
+                BaseElement::Arrow(a) => self.arrows.contains(a),
+            }
+        }).collect();
+    }
+    /// record the current schematic in history. To be called before every edit.
+    pub fn checkpoint(&mut self) {
+        let snapshot = self.snapshot();
+        self.history.push(snapshot);
+    }
+    /// revert the last edit. Returns true if there was an edit to revert.
+    pub fn undo(&mut self) -> bool {
+        if let Some(snapshot) = self.history.undo(self.snapshot()) {
+            self.restore(snapshot);
+            true
+        } else {
+            false
+        }
+    }
+    /// reapply the last reverted edit. Returns true if there was an edit to reapply.
+    pub fn redo(&mut self) -> bool {
+        if let Some(snapshot) = self.history.redo(self.snapshot()) {
+            self.restore(snapshot);
+            true
+        } else {
+            false
+        }
+    }
+    /// set the parameters and attributes of device d. The change is recorded in history.
+    pub fn set_device_params(&mut self, d: &RcRDevice, params: &[(String, String)], attributes: &str) {
+        self.checkpoint();
+        let mut d = d.0.borrow_mut();
+        d.class_mut().set_params(params);
+        d.set_attributes(attributes);
+        let params = d.class().params();
+        if !params.is_empty() {
+            self.devices.push_recent(d.class().name(), &params);
+        }
+    }
+    /// set the parameter named param of every device of the class named class_name whose param is from to to, 
+    /// e.g. the value of every 10k resistor to 12k. Numbers match however they are written, see `same_value`. 
+    /// Returns the number of devices changed, the change is recorded in history as one step. 
+    /// Nothing is changed if to is not a valid value for any of the matching devices, the error names the device.
+    pub fn replace_values(&mut self, class_name: &str, param: &str, from: &str, to: &str) -> Result<usize, String> {
+        let to = to.trim();
+        if to.is_empty() {
+            return Ok(0);
+        }
+        let new_value = [(param.to_string(), to.to_string())];
+        let mut changes: Vec<RcRDevice> = vec![];
+        for d in self.devices.sorted() {
+            let device = d.0.borrow();
+            if device.class().name() != class_name {
+                continue;
+            }
+            let params = device.class().params();
+            if !params.iter().any(|(name, value)| name == param && devices::same_value(value, from)) {
+                continue;
+            }
+            let mut class = device.class().clone();
+            class.set_params(&new_value);
+            class.validate_params().map_err(|e| format!("{}: {}", device.ng_id(), e))?;
+            if class.params() != params {
+                drop(device);
+                changes.push(d);
+            }
+        }
+        if changes.is_empty() {
+            return Ok(0);
+        }
+        self.checkpoint();
+        for d in &changes {
+            d.0.borrow_mut().class_mut().set_params(&new_value);
+        }
+        let params = changes[0].0.borrow().class().params();
+        if !params.is_empty() {
+            self.devices.push_recent(class_name, &params);
+        }
+        Ok(changes.len())
+    }
     /// clear selection
     fn clear_selected(&mut self) {
         self.selected.clear();
@@ -117,13 +468,19 @@ impl Schematic {
     fn clear_tentatives(&mut self) {
         self.devices.clear_tentatives();
         self.nets.clear_tentatives();
+        for a in &mut self.arrows {
+            a.tentative = false;
+        }
     }
-    /// set tentative flags by intersection with ssb
+    /// set tentative flags by intersection with ssb. ssb corners lie on grid points, elements touching its edges are included.
     pub fn tentatives_by_ssbox(&mut self, ssb: &SSBox) {
         self.clear_tentatives();
-        let ssb_p = SSBox::from_points([ssb.min, ssb.max]).inflate(1, 1);
+        let ssb_p = SSBox::from_points([ssb.min, ssb.max]);
         self.devices.tentatives_by_ssbox(&ssb_p);
         self.nets.tentatives_by_ssbox(&ssb_p);
+        for a in &mut self.arrows {
+            a.tentative = a.intersects_ssb(&ssb_p);
+        }
     }
     /// set 1 tentative flag by ssp, skipping skip elements which contains ssp. Returns netname if tentative is a net segment
     pub fn tentative_by_sspoint(&mut self, ssp: SSPoint, skip: &mut usize) -> Option<String> {
@@ -141,9 +498,47 @@ impl Schematic {
                     d.0.borrow_mut().interactable.tentative = true;
                     None
                 },
+                BaseElement::Arrow(a) => {
+                    if let Some(a) = self.arrows.iter_mut().find(|x| **x == a) {
+                        a.tentative = true;
+                    }
+                    None
+                },
             }
         } else {None}
     }
+    /// set tentative flags on the whole net connected to the device port at ssp. 
+    /// Returns the net name, or none if there is no device port with a connected wire at ssp.
+    fn tentative_net_by_port(&mut self, ssp: SSPoint) -> Option<String> {
+        if !self.devices.occupies_ssp(ssp) {
+            return None;
+        }
+        self.clear_tentatives();
+        self.nets.tentatives_by_net(ssp)
+    }
+    /// highlight every wire of the net named name, e.g. when the net is referred to from outside the canvas.
+    /// Returns false if there is no such net.
+    pub fn highlight_net(&mut self, name: &str) -> bool {
+        self.clear_tentatives();
+        self.nets.tentatives_by_label(name)
+    }
+    /// find the net named name and keep it highlighted, with its connected pins, until cleared with esc. 
+    /// Returns the bounding box of the net to fit the view to, or a message if there is no such net.
+    pub fn find_net(&mut self, name: &str) -> Result<VSBox, String> {
+        let name = name.trim();
+        let pts: Vec<VSPoint> = self.nets.graph.all_edges()
+        .filter(|e| e.2.label.as_deref().map(|l| l.as_str()) == Some(name))
+        .flat_map(|e| [e.0.0, e.1.0])
+        .map(|ssp| ssp.cast().cast_unit())
+        .collect();
+        if pts.is_empty() {
+            self.found_net = None;
+            return Err(format!("no net named {}", name));
+        }
+        self.highlight_net(name);
+        self.found_net = Some(name.to_string());
+        Ok(VSBox::from_points(pts))
+    }
     /// set 1 tentative flag by ssp, sets flag on next qualifying element. Returns netname i tentative is a net segment
     pub fn tentative_next_by_ssp(&mut self, ssp: SSPoint) -> Option<String> {
         let mut skip = self.selskip;
@@ -151,18 +546,60 @@ impl Schematic {
         self.selskip = skip;
         s
     }
+    /// select the single element at ssp. If several elements overlap, the one highlighted by hovering or cycling is selected. 
+    /// With click cycling on, clicking again at the same point deselects the element selected by the last click and selects the next one instead, as with `C`.
+    fn select_at(&mut self, ssp: SSPoint) {
+        match self.last_click.take() {
+            Some((prev_ssp, prev)) if self.click_cycles && prev_ssp == ssp => {
+                self.selected.remove(&prev);
+                self.tentative_next_by_ssp(ssp);
+            },
+            _ => {
+                let mut skip = self.selskip.saturating_sub(1);
+                self.tentative_by_sspoint(ssp, &mut skip);
+                self.selskip = skip;
+            },
+        }
+        self.last_click = self.tentative_elements().pop().map(|be| (ssp, be));
+        self.tentatives_to_selected();
+    }
+    /// set whether clicking again at the same point cycles through overlapping elements, see `select_at`
+    pub fn set_click_cycles(&mut self, cycles: bool) {
+        self.click_cycles = cycles;
+    }
+    /// set the keys bound to the keyboard actions
+    pub fn set_key_bindings(&mut self, key_bindings: KeyBindings) {
+        self.key_bindings = key_bindings;
+    }
+    /// returns the action event triggers through the key bindings, if any. 
+    /// While moving, keys bound to rotate or mirror the moved elements do so.
+    pub fn action(&self, event: &Event) -> Option<Action> {
+        let Event::Keyboard(e) = event else {return None};
+        self.key_bindings.action(e, matches!(self.state, SchematicState::Moving(Some(_))))
+    }
+    /// returns every element with tentative flag set
+    fn tentative_elements(&self) -> Vec<BaseElement> {
+        self.devices.tentatives().map(BaseElement::Device)
+        .chain(self.nets.tentatives().map(BaseElement::NetEdge))
+        .chain(self.arrows.iter().filter(|a| a.tentative).cloned().map(BaseElement::Arrow))
+        .collect()
+    }
     /// put every element with tentative flag set into selected vector
     fn tentatives_to_selected(&mut self) {
-        let _: Vec<_> = self.devices.tentatives().map(
-            |d| {
-                self.selected.insert(BaseElement::Device(d));
-            }
-        ).collect();
-        let _: Vec<_> = self.nets.tentatives().map(
-            |e| {
-                self.selected.insert(BaseElement::NetEdge(e));
-            }
-        ).collect();
+        for be in self.tentative_elements() {
+            self.selected.insert(be);
+        }
+    }
+    /// returns true if a wire ending at ssp would connect to a device port or existing net
+    fn connects_at(&self, ssp: SSPoint) -> bool {
+        self.nets.graph.contains_node(NetVertex(ssp)) || self.occupies_ssp(ssp)
+    }
+    /// returns the device port nearest to ssp within `PORT_SNAP_DISTANCE`, if any
+    fn port_near(&self, ssp: SSPoint) -> Option<SSPoint> {
+        let d = Schematic::PORT_SNAP_DISTANCE;
+        self.devices.ports_ssp().into_iter()
+        .filter(|p| (p.x - ssp.x).abs() <= d && (p.y - ssp.y).abs() <= d)
+        .min_by_key(|p| ((p.x - ssp.x).pow(2) + (p.y - ssp.y).pow(2), p.x, p.y))
     }
     /// returns true if ssp is occupied by an element
     fn occupies_ssp(&self, ssp: SSPoint) -> bool {
@@ -175,16 +612,79 @@ impl Schematic {
         vcscale: f32,
         frame: &mut Frame, 
     ) {  // draw elements which may need to be redrawn at any event
-        self.nets.draw_preview(vct, vcscale, frame);  // this draws tentatives - refactor
-        self.devices.draw_preview(vct, vcscale, frame);
+        // tentatives which are also selected are already highlighted as selected
+        for e in self.nets.tentatives() {
+            if self.visual_state(&BaseElement::NetEdge(e.clone())) == Some(VisualState::Tentative) {
+                e.draw_preview(vct, vcscale, frame);
+            }
+        }
+        for d in self.devices.tentatives() {
+            if self.visual_state(&BaseElement::Device(d.clone())) == Some(VisualState::Tentative) {
+                d.0.borrow().draw_preview(vct, vcscale, frame);
+            }
+        }
+        for a in self.arrows.iter().filter(|a| a.tentative) {
+            if self.visual_state(&BaseElement::Arrow(a.clone())) == Some(VisualState::Tentative) {
+                a.draw_preview(vct, vcscale, frame);
+            }
+        }
+
+        // net found by name search: every segment and a ring around every connected pin
+        if let Some(name) = &self.found_net {
+            let color = VisualState::Tentative.color();
+            for (_, _, e) in self.nets.graph.all_edges().filter(|e| e.2.label.as_deref().map(|l| l.as_str()) == Some(name.as_str())) {
+                e.draw_highlight(vct, vcscale, frame, color);
+            }
+            let stroke = Stroke {
+                width: (0.1 * vcscale).max(0.1 * 2.0),
+                style: canvas::stroke::Style::Solid(color),
+                ..Stroke::default()
+            };
+            let mut path_builder = Builder::new();
+            for (d, i) in self.net_members(name) {
+                let ssp = d.0.borrow().ports_ssp()[i];
+                path_builder.circle(Point::from(vct.transform_point(ssp.cast().cast_unit())).into(), 0.5 * vcscale);
+            }
+            frame.stroke(&path_builder.build(), stroke);
+        }
+
+        // faint ring around the port the cursor snaps to
+        if let (SchematicState::Idle | SchematicState::Wiring(..), Some(ssp), false) = (&self.state, self.snap_port, self.low_quality) {
+            let stroke = Stroke {
+                width: (0.05 * vcscale).max(0.05 * 2.0),
+                style: canvas::stroke::Style::Solid(Color::from_rgba(1.0, 1.0, 1.0, 0.4)),
+                ..Stroke::default()
+            };
+            let mut path_builder = Builder::new();
+            path_builder.circle(Point::from(vct.transform_point(ssp.cast().cast_unit())).into(), Schematic::PORT_SNAP_DISTANCE as f32 * vcscale);
+            frame.stroke(&path_builder.build(), stroke);
+        }
+
+        // tooltip listing the parameters of the hovered device
+        if let SchematicState::Idle = self.state {
+            if let Some(d) = self.hovered_device() {
+                d.0.borrow().draw_tooltip(self.hover_ssp.cast().cast_unit(), vct, frame);
+            }
+        }
 
         match &self.state {
-            SchematicState::Wiring(Some((net, ..))) => {
-                net.as_ref().draw_preview(vct, vcscale, frame);
+            SchematicState::Wiring(opt_ws, (ssp, connects)) => {
+                if let Some((net, ..)) = opt_ws {
+                    net.as_ref().draw_preview(vct, vcscale, frame);
+                }
+                let color = if *connects {Color::from_rgb(0.0, 1.0, 0.0)} else {Color::from_rgb(1.0, 1.0, 0.5)};
+                let stroke = Stroke {
+                    width: (0.1 * vcscale).max(0.1 * 2.0),
+                    style: canvas::stroke::Style::Solid(color),
+                    ..Stroke::default()
+                };
+                let mut path_builder = Builder::new();
+                path_builder.circle(Point::from(vct.transform_point(ssp.cast().cast_unit())).into(), 0.5 * vcscale);
+                frame.stroke(&path_builder.build(), stroke);
             },
             SchematicState::Idle => {
             },
-            SchematicState::Selecting(ssb) => {
+            SchematicState::Selecting(ssb, _) => {
                 let color = if ssb.height() > 0 {Color::from_rgba(1., 1., 0., 0.1)} else {Color::from_rgba(0., 1., 1., 0.1)};
                 let f = canvas::Fill {
                     style: canvas::Style::Solid(color),
@@ -208,20 +708,45 @@ impl Schematic {
                 };
                 frame.stroke(&path_builder.build(), stroke);
             },
+            SchematicState::Bending(Some((e, handle))) => {
+                let mut g = Nets::default();
+                g.route(e.src, *handle);
+                g.route(*handle, e.dst);
+                g.draw_preview(vct, vcscale, frame);
+            },
+            SchematicState::Annotating(Some(from), to) => {
+                ArrowAnnotation::new(*from, *to, self.arrow_label.clone()).draw_preview(vct, vcscale, frame);
+            },
             SchematicState::Moving(Some((ssp0, ssp1, sst))) => {
                 let vvt = transforms::sst_to_xxt::<ViewportSpace>(SchematicState::move_transform(ssp0, ssp1, sst));
 
                 let vct_c = vvt.then(&vct);
-                for be in &self.selected {
+                for be in self.selected.iter().filter(|be| !self.is_locked(be)) {
                     match be {
                         BaseElement::Device(d) => {
                             d.0.borrow().draw_preview(vct_c, vcscale, frame)
                         },
                         BaseElement::NetEdge(e) => {
                             e.draw_preview(vct_c, vcscale, frame)
-                        }
+                        },
+                        BaseElement::Arrow(a) => {
+                            a.draw_preview(vct_c, vcscale, frame)
+                        },
                     }
                 }
+                // crosshair at the rotation anchor: ssp0 moved to ssp1
+                let stroke = Stroke {
+                    width: (0.05 * vcscale).max(0.05 * 2.0),
+                    style: canvas::stroke::Style::Solid(Color::from_rgb(1.0, 0.5, 0.0)),
+                    ..Stroke::default()
+                };
+                let anchor: VSPoint = ssp1.cast().cast_unit();
+                let mut path_builder = Builder::new();
+                for (dx, dy) in [(0.5, 0.0), (0.0, 0.5)] {
+                    path_builder.move_to(Point::from(vct.transform_point(VSPoint::new(anchor.x - dx, anchor.y - dy))).into());
+                    path_builder.line_to(Point::from(vct.transform_point(VSPoint::new(anchor.x + dx, anchor.y + dy))).into());
+                }
+                frame.stroke(&path_builder.build(), stroke);
             },
             _ => {},
         }
@@ -233,24 +758,263 @@ impl Schematic {
         vcscale: f32,
         frame: &mut Frame, 
     ) {  // draw elements which may need to be redrawn at any event
-        self.nets.draw_persistent(vct, vcscale, frame);
+        let styles = self.net_class_styles();
+        if self.rounded_corners {
+            self.nets.draw_rounded(vct, vcscale, frame, &styles);
+        } else {
+            self.nets.draw_styled(vct, vcscale, frame, &styles);
+        }
         self.devices.draw_persistent(vct, vcscale, frame);
-        let _: Vec<_> = self.selected.iter().map(|e|
-            match e {
-                BaseElement::NetEdge(e) => {
+        for p in &self.probes {
+            p.draw(vct, vcscale, frame, self.devices.op_precision(), self.devices.number_format());
+        }
+        for a in &self.arrows {
+            a.draw_persistent(vct, vcscale, frame);
+        }
+        if self.inspect_connections {
+            self.draw_connection_counts(vct, vcscale, frame);
+        }
+        for be in &self.selected {
+            match (be, self.visual_state(be)) {
+                (BaseElement::NetEdge(e), Some(VisualState::Locked)) => {
+                    e.draw_highlight(vct, vcscale, frame, VisualState::Locked.color());
+                },
+                (BaseElement::NetEdge(e), _) => {
                     e.draw_selected(vct, vcscale, frame);
                 },
-                BaseElement::Device(d) => {
+                (BaseElement::Device(d), Some(VisualState::Locked)) => {
+                    d.0.borrow().draw_highlight(vct, vcscale, frame, VisualState::Locked.color());
+                },
+                (BaseElement::Device(d), _) => {
                     d.0.borrow().draw_selected(vct, vcscale, frame);
                 },
+                (BaseElement::Arrow(a), _) => {
+                    a.draw_selected(vct, vcscale, frame);
+                },
             }
-        ).collect();
+        }
+    }
+    /// draw marching ants on the selection: dashes moving along selected wires and arrows, and around the bounds of selected devices. 
+    /// t is the animation time in seconds, see `ants_offset`.
+    pub fn draw_selection_ants(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame, t: f32) {
+        let dash = (0.5 * vcscale).max(4.0);
+        let dashes = [dash, dash];
+        let offset = ants_offset(t, dash);
+        let mut lines = Builder::new();
+        let mut outlines = Builder::new();
+        for be in &self.selected {
+            let (from, to): (VSPoint, VSPoint) = match be {
+                BaseElement::NetEdge(e) => (e.src.cast().cast_unit(), e.dst.cast().cast_unit()),
+                BaseElement::Arrow(a) => (a.from, a.to),
+                BaseElement::Device(d) => {
+                    let csb = vct.outer_transformed_box(&d.0.borrow().interactable.bounds.cast().cast_unit());
+                    outlines.rectangle(Point::from(csb.min).into(), Size::new(csb.width(), csb.height()));
+                    continue;
+                },
+            };
+            lines.move_to(Point::from(vct.transform_point(from)).into());
+            lines.line_to(Point::from(vct.transform_point(to)).into());
+        }
+        // dark dashes over the selection highlight of wires and arrows
+        frame.stroke(&lines.build(), Stroke {
+            width: (0.05 * vcscale).max(1.0),
+            style: canvas::stroke::Style::Solid(Color::from_rgba(0.0, 0.0, 0.0, 0.6)),
+            line_dash: canvas::LineDash { segments: &dashes, offset },
+            ..Stroke::default()
+        });
+        frame.stroke(&outlines.build(), Stroke {
+            width: 1.0,
+            style: canvas::stroke::Style::Solid(VisualState::Selected.color()),
+            line_dash: canvas::LineDash { segments: &dashes, offset },
+            ..Stroke::default()
+        });
+    }
+    /// draw a simplified overview of the schematic with vct, e.g. for a minimap: wires as lines and devices as their bounds, in color
+    pub fn draw_overview(&self, vct: VCTransform, frame: &mut Frame, color: Color) {
+        let mut path_builder = Builder::new();
+        for (_, _, e) in self.nets.graph.all_edges() {
+            path_builder.move_to(Point::from(vct.transform_point(e.src.cast().cast_unit())).into());
+            path_builder.line_to(Point::from(vct.transform_point(e.dst.cast().cast_unit())).into());
+        }
+        for d in self.devices.get_set() {
+            let csb = vct.outer_transformed_box(&d.0.borrow().interactable.bounds.cast().cast_unit());
+            path_builder.rectangle(Point::from(csb.min).into(), Size::new(csb.width(), csb.height()));
+        }
+        let stroke = Stroke {
+            width: 1.0,
+            style: canvas::stroke::Style::Solid(color),
+            ..Stroke::default()
+        };
+        frame.stroke(&path_builder.build(), stroke);
+    }
+    /// returns true if any element is selected
+    pub fn has_selection(&self) -> bool {
+        !self.selected.is_empty()
     }
     /// returns the bouding box of all elements on canvas
     pub fn bounding_box(&self) -> VSBox {
         let bbn = VSBox::from_points(self.nets.graph.nodes().map(|x| x.0.cast().cast_unit()));
         let bbi = self.devices.bounding_box();
-        bbn.union(&bbi)
+        let bba = VSBox::from_points(self.arrows.iter().flat_map(|a| [a.from, a.to]));
+        bbn.union(&bbi).union(&bba)
+    }
+    /// returns the bounding box of all selected elements, if any
+    pub fn selected_bounding_box(&self) -> Option<VSBox> {
+        Self::elements_bounding_box(self.selected.iter())
+    }
+    /// returns the bounding box of elements, if any
+    fn elements_bounding_box<'a>(elements: impl Iterator<Item = &'a BaseElement>) -> Option<VSBox> {
+        let pts: Vec<SSPoint> = elements.flat_map(|be| {
+            match be {
+                BaseElement::NetEdge(e) => [e.src, e.dst],
+                BaseElement::Device(d) => {
+                    let b = d.0.borrow().interactable.bounds;
+                    [b.min, b.max]
+                },
+                BaseElement::Arrow(a) => {
+                    let b = a.bounds();
+                    [b.min, b.max]
+                },
+            }
+        }).collect();
+        if pts.is_empty() {
+            None
+        } else {
+            Some(SSBox::from_points(pts).cast().cast_unit())
+        }
+    }
+    /// returns the bounding box of elements added since last called, if any
+    pub fn take_added_bounds(&mut self) -> Option<VSBox> {
+        self.added_bounds.take()
+    }
+    /// returns true if an event changed the selection since last called
+    pub fn take_selection_changed(&mut self) -> bool {
+        std::mem::take(&mut self.selection_changed)
+    }
+    /// note whether the selection differs from the one before an event
+    fn note_selection_change(&mut self, before: &[BaseElement]) {
+        self.selection_changed |= before.len() != self.selected.len() || before.iter().any(|be| !self.selected.contains(be));
+    }
+    /// copy selected elements into the clipboard. ssp is the reference point for pasting.
+    fn copy_selected(&mut self, ssp: SSPoint) {
+        let mut clipboard = Clipboard { anchor: ssp, ..Default::default() };
+        for be in &self.selected {
+            match be {
+                BaseElement::NetEdge(e) => {
+                    let mut e = e.clone();
+                    e.interactable.tentative = false;
+                    e.label = None;
+                    clipboard.edges.push(e);
+                },
+                BaseElement::Device(d) => {
+                    clipboard.devices.push(d.deep_clone());
+                },
+                BaseElement::Arrow(a) => {
+                    clipboard.arrows.push(a.clone());
+                },
+            }
+        }
+        self.clipboard = clipboard;
+    }
+    /// add copies of elements in the clipboard translated by sst and select them, returns true if anything was added
+    fn paste_transformed(&mut self, devices: &[RcRDevice], edges: &[NetEdge], arrows: &[ArrowAnnotation], sst: SSTransform) -> bool {
+        if devices.is_empty() && edges.is_empty() && arrows.is_empty() {
+            return false;
+        }
+        self.checkpoint();
+        self.selected.clear();
+        for a in arrows {
+            let mut a = a.clone();
+            a.transform(sst);
+            let a = self.add_arrow(a);
+            self.selected.insert(BaseElement::Arrow(a));
+        }
+        for d in devices {
+            let d = d.deep_clone();
+            d.0.borrow_mut().transform(sst);
+            self.devices.insert(d.clone());
+            self.selected.insert(BaseElement::Device(d));
+        }
+        let mut pasted = vec![];
+        for e in edges {
+            let mut e = e.clone();
+            e.transform(sst);
+            self.nets.graph.add_edge(NetVertex(e.src), NetVertex(e.dst), e.clone());
+            pasted.push(e);
+        }
+        self.prune_nets();
+        // pasted segments may have been split or merged - select every segment lying on a pasted segment
+        let new_edges: Vec<NetEdge> = self.nets.graph.all_edges().filter_map(|(_, _, e)| {
+            if pasted.iter().any(|p| p.interactable.contains_ssp(e.src) && p.interactable.contains_ssp(e.dst)) {
+                Some(e.clone())
+            } else {
+                None
+            }
+        }).collect();
+        for e in new_edges {
+            self.selected.insert(BaseElement::NetEdge(e));
+        }
+        self.added_bounds = self.selected_bounding_box();
+        true
+    }
+    /// paste the clipboard such that the copied anchor lands on ssp
+    fn paste(&mut self, ssp: SSPoint) -> bool {
+        let clipboard = self.clipboard.clone();
+        let v = ssp - clipboard.anchor;
+        self.paste_transformed(&clipboard.devices, &clipboard.edges, &clipboard.arrows, SSTransform::translation(v.x, v.y))
+    }
+    /// duplicate the selected elements to the right of the selection, clear of it, and select the duplicates. The clipboard is untouched.
+    fn duplicate_selected(&mut self) -> bool {
+        let mut devices = vec![];
+        let mut edges = vec![];
+        let mut arrows = vec![];
+        for be in &self.selected {
+            match be {
+                BaseElement::NetEdge(e) => {
+                    let mut e = e.clone();
+                    e.interactable.tentative = false;
+                    e.label = None;
+                    edges.push(e);
+                },
+                BaseElement::Device(d) => devices.push(d.clone()),
+                BaseElement::Arrow(a) => arrows.push(a.clone()),
+            }
+        }
+        // offset by the width of the selection and a gap, so no duplicate touches an original
+        let dx = self.selected_bounding_box().map_or(0, |b| b.width().ceil() as SSCoord) + 2;
+        let ret = self.paste_transformed(&devices, &edges, &arrows, SSTransform::translation(dx, 0));
+        // duplicates are right next to the originals, no need to fit the viewport to them
+        self.added_bounds = None;
+        ret
+    }
+    /// rotate the unlocked selected elements by sst about the center of their bounding box, returns true if anything was rotated
+    fn rotate_selected(&mut self, sst: SSTransform) -> bool {
+        let unlocked: Vec<BaseElement> = self.selected.iter().filter(|be| !self.is_locked(be)).cloned().collect();
+        // locked elements stay put, the center is that of the elements which actually rotate
+        let Some(vsb) = Self::elements_bounding_box(unlocked.iter()) else {return false};
+        let center: SSPoint = vsb.center().round().cast().cast_unit();
+        let sst = SchematicState::move_transform(&center, &center, &sst);
+        self.checkpoint();
+        self.move_selected(sst);
+        self.prune_nets();
+        for be in unlocked {
+            match be {
+                BaseElement::NetEdge(mut e) => {
+                    e.transform(sst);
+                    if let Some(ew) = self.nets.graph.edge_weight(NetVertex(e.src), NetVertex(e.dst)) {
+                        self.selected.insert(BaseElement::NetEdge(ew.clone()));
+                    }
+                },
+                BaseElement::Device(d) => {
+                    self.selected.insert(BaseElement::Device(d));
+                },
+                BaseElement::Arrow(mut a) => {
+                    a.transform(sst);
+                    self.selected.insert(BaseElement::Arrow(a));
+                },
+            }
+        }
+        true
     }
     /// set 1 tentative flag based on ssp and skip number. Returns the flagged element, if any.
     fn selectable(&mut self, ssp: SSPoint, skip: &mut usize) -> Option<BaseElement> {
@@ -262,6 +1026,13 @@ impl Schematic {
             if let Some(d) = self.devices.selectable(ssp, skip, &mut count) {
                 return Some(d);
             }
+            for a in self.arrows.iter().filter(|a| a.contains_ssp(ssp)) {
+                count += 1;
+                if count > *skip {
+                    *skip = count;
+                    return Some(BaseElement::Arrow(a.clone()));
+                }
+            }
             if count == 0 {
                 *skip = count;
                 return None;
@@ -269,188 +1040,1391 @@ impl Schematic {
             *skip -= count;
         }
     }
-    /// delete all elements which appear in the selected array
-    pub fn delete_selected(&mut self) {
+    /// returns the visual state of the element, which decides its highlight color
+    fn visual_state(&self, be: &BaseElement) -> Option<VisualState> {
+        let selected = self.selected.contains(be);
+        match be {
+            BaseElement::NetEdge(e) => {
+                let mut interactable = e.interactable;
+                interactable.locked = self.nets.is_locked(e);
+                interactable.visual_state(selected)
+            },
+            BaseElement::Device(d) => d.0.borrow().interactable.visual_state(selected),
+            BaseElement::Arrow(a) => {
+                let tentative = self.arrows.iter().any(|x| x == a && x.tentative);
+                ArrowAnnotation { tentative, ..a.clone() }.visual_state(selected)
+            },
+        }
+    }
+    /// start placing the new device d at ssp, see `start_moving_new`. 
+    /// If values are prompted for on placement, d instead waits for its value and the idle state is returned.
+    fn start_placement(&mut self, d: RcRDevice, ssp: SSPoint) -> SchematicState {
+        if self.prompt_on_place && !d.0.borrow().class().params().is_empty() {
+            self.pending_placement = Some(d);
+            return SchematicState::Idle;
+        }
+        self.start_moving_new(d, ssp)
+    }
+    /// start moving the new device d at ssp: it replaces the selection and follows the cursor until clicked down. Returns the moving state.
+    fn start_moving_new(&mut self, d: RcRDevice, ssp: SSPoint) -> SchematicState {
+        self.selected.clear();
+        d.0.borrow_mut().set_position(ssp);
+        self.selected.insert(BaseElement::Device(d));
+        SchematicState::Moving(Some((ssp, ssp, SSTransform::identity())))
+    }
+    /// start placing a new device of the class named class_name at ssp, see `class_names()`. 
+    /// Returns false if the schematic is busy, e.g. wiring, or there is no such class.
+    pub fn place_device(&mut self, class_name: &str, ssp: SSPoint) -> bool {
+        if !matches!(self.state, SchematicState::Idle) {
+            return false;
+        }
+        let Some(d) = self.devices.new_named(class_name) else {return false};
+        self.state = self.start_placement(d, ssp);
+        true
+    }
+    /// returns true if the element is locked against moving and deleting
+    fn is_locked(&self, be: &BaseElement) -> bool {
+        match be {
+            BaseElement::NetEdge(e) => self.nets.is_locked(e),
+            BaseElement::Device(d) => d.0.borrow().interactable.locked,
+            BaseElement::Arrow(_) => false,
+        }
+    }
+    /// lock every selected element, or unlock them if they are all already locked. Returns true if the selection is not empty.
+    pub fn toggle_lock_selected(&mut self) -> bool {
+        if self.selected.is_empty() {
+            return false;
+        }
+        self.checkpoint();
+        let locked = self.selected.iter().any(|be| !self.is_locked(be));
+        for be in &self.selected {
+            match be {
+                BaseElement::NetEdge(e) => self.nets.set_locked(e, locked),
+                BaseElement::Device(d) => d.0.borrow_mut().interactable.locked = locked,
+                // annotations cannot be locked
+                BaseElement::Arrow(_) => {},
+            }
+        }
+        true
+    }
+    /// disable every selected device, or enable them if they are all already disabled. Returns true if any device is selected.
+    pub fn toggle_disable_selected(&mut self) -> bool {
+        let devices: Vec<RcRDevice> = self.selected.iter().filter_map(|be| match be {
+            BaseElement::Device(d) => Some(d.clone()),
+            BaseElement::NetEdge(_) | BaseElement::Arrow(_) => None,
+        }).collect();
+        if devices.is_empty() {
+            return false;
+        }
+        self.checkpoint();
+        let disabled = devices.iter().any(|d| !d.0.borrow().disabled());
+        for d in devices {
+            d.0.borrow_mut().set_disabled(disabled);
+        }
+        self.prune_nets();
+        true
+    }
+    /// set the drawing style of each net class, keyed by class name
+    pub fn set_net_styles(&mut self, styles: BTreeMap<String, NetStyle>) {
+        self.net_styles = styles;
+    }
+    /// returns the style of every net assigned to a net class with a style, keyed by net name. 
+    /// Later assignments take precedence if nets holding several assignments were joined.
+    fn net_class_styles(&self) -> std::collections::HashMap<String, NetStyle> {
+        self.net_classes.by_net(&self.nets).into_iter().filter_map(|(net, class)| {
+            Some((net, *self.net_styles.get(&class)?))
+        }).collect()
+    }
+    /// returns the net class of the net at ssp, if it is assigned to one
+    fn net_class_at(&self, ssp: SSPoint) -> Option<&str> {
+        self.net_classes.get(&self.nets, ssp).map(String::as_str)
+    }
+    /// returns the net class shared by the nets of all selected wires, none if there is no wire selected or they differ
+    pub fn selected_net_class(&self) -> Option<String> {
+        let mut classes = self.selected.iter().filter_map(|be| match be {
+            BaseElement::NetEdge(e) => Some(self.net_class_at(e.src)),
+            BaseElement::Device(_) | BaseElement::Arrow(_) => None,
+        });
+        let first = classes.next()??;
+        classes.all(|c| c == Some(first)).then(|| first.to_string())
+    }
+    /// assign the nets of the selected wires to net class, or to no class if none. Returns false if no wire is selected.
+    pub fn assign_net_class_selected(&mut self, class: Option<String>) -> bool {
+        let wires: Vec<SSPoint> = self.selected.iter().filter_map(|be| match be {
+            BaseElement::NetEdge(e) => Some(e.src),
+            BaseElement::Device(_) | BaseElement::Arrow(_) => None,
+        }).collect();
+        if wires.is_empty() {
+            return false;
+        }
+        self.checkpoint();
+        for pos in wires {
+            self.net_classes.set(&self.nets, pos, class.clone());
+        }
+        true
+    }
+    /// returns the initial voltage shared by the nets of all selected wires, none if there is no wire selected or they differ
+    pub fn selected_initial_condition(&self) -> Option<f32> {
+        let mut selected = self.selected.iter().filter_map(|be| match be {
+            BaseElement::NetEdge(e) => Some(self.initial_conditions.get(&self.nets, e.src).copied()),
+            BaseElement::Device(_) | BaseElement::Arrow(_) => None,
+        });
+        let first = selected.next()??;
+        selected.all(|v| v == Some(first)).then_some(first)
+    }
+    /// set the initial voltage of the nets of the selected wires, or remove their initial conditions if none. 
+    /// Returns false if no wire is selected.
+    pub fn set_initial_condition_selected(&mut self, volts: Option<f32>) -> bool {
+        let wires: Vec<SSPoint> = self.selected.iter().filter_map(|be| match be {
+            BaseElement::NetEdge(e) => Some(e.src),
+            BaseElement::Device(_) | BaseElement::Arrow(_) => None,
+        }).collect();
+        if wires.is_empty() {
+            return false;
+        }
+        self.checkpoint();
+        for pos in wires {
+            self.initial_conditions.set(&self.nets, pos, volts);
+        }
+        true
+    }
+    /// returns true if a control statement runs a transient analysis, as `.tran` or as `tran` command of a `.control` block
+    fn runs_transient(&self) -> bool {
+        let mut in_control = false;
+        self.sim_config.directives.iter().any(|l| {
+            let lower = l.trim().to_ascii_lowercase();
+            let command = lower.split_whitespace().next().unwrap_or("");
+            match command {
+                ".control" => in_control = true,
+                ".endc" => in_control = false,
+                _ => {},
+            }
+            command == ".tran" || in_control && command == "tran"
+        })
+    }
+    /// returns .ic lines setting the initial voltages of nets, if a control statement runs a transient analysis. 
+    /// Net names are taken after relabeling. Ground is always at 0 V, its initial conditions are left out.
+    fn ic_directive(&self) -> String {
+        if !self.runs_transient() {
+            return String::new();
+        }
+        let ground: Vec<String> = self.devices.ground_ports_ssp().into_iter()
+            .filter_map(|ssp| self.nets.label_at(ssp).map(|l| l.to_string()))
+            .chain([String::from("0")])
+            .collect();
+        self.initial_conditions.by_net(&self.nets).into_iter()
+            .filter(|(net, _)| !ground.contains(net))
+            .map(|(net, volts)| format!(".ic V({})={}\n", net, volts))
+            .collect()
+    }
+    /// returns the point the drawn grid is anchored at, the coordinate origin unless moved with shift+g
+    pub fn grid_origin(&self) -> SSPoint {
+        self.grid_origin
+    }
+    /// add arrow a to the schematic with a new identity. Returns the added arrow.
+    fn add_arrow(&mut self, a: ArrowAnnotation) -> ArrowAnnotation {
+        self.arrow_wm += 1;
+        let a = ArrowAnnotation { id: self.arrow_wm, tentative: false, ..a };
+        self.arrows.push(a.clone());
+        a
+    }
+    /// set the label of newly placed arrow annotations
+    pub fn set_arrow_label(&mut self, label: &str) {
+        self.arrow_label = label.trim().to_string();
+    }
+    /// set the label of every selected arrow annotation to the label of new arrows. Returns false if no arrow is selected.
+    pub fn relabel_selected_arrows(&mut self) -> bool {
+        let arrows: Vec<ArrowAnnotation> = self.selected.iter().filter_map(|be| match be {
+            BaseElement::Arrow(a) => Some(a.clone()),
+            BaseElement::NetEdge(_) | BaseElement::Device(_) => None,
+        }).collect();
+        if arrows.is_empty() {
+            return false;
+        }
+        self.checkpoint();
+        for a in arrows {
+            self.selected.remove(&BaseElement::Arrow(a.clone()));
+            let relabeled = ArrowAnnotation { label: self.arrow_label.clone(), ..a.clone() };
+            if let Some(x) = self.arrows.iter_mut().find(|x| **x == a) {
+                *x = relabeled.clone();
+            }
+            self.selected.insert(BaseElement::Arrow(relabeled));
+        }
+        true
+    }
+    /// swap the netlist order of the ports of every selected symmetric device. Returns true if any device was changed.
+    pub fn swap_ports_selected(&mut self) -> bool {
+        let devices: Vec<RcRDevice> = self.selected.iter().filter_map(|be| match be {
+            BaseElement::Device(d) if d.0.borrow().class().ports_swappable() => Some(d.clone()),
+            _ => None,
+        }).collect();
+        if devices.is_empty() {
+            return false;
+        }
+        self.checkpoint();
+        for d in devices {
+            d.0.borrow_mut().swap_ports();
+        }
+        true
+    }
+    /// turn selected unlocked devices which support it 45 deg counter clockwise, returns true if any was turned
+    pub fn rotate_diagonal_selected(&mut self) -> bool {
+        let devices: Vec<RcRDevice> = self.selected.iter().filter_map(|be| match be {
+            BaseElement::Device(d) if d.0.borrow().class().rotates_diagonally() && !d.0.borrow().interactable.locked => Some(d.clone()),
+            _ => None,
+        }).collect();
+        if devices.is_empty() {
+            return false;
+        }
+        self.checkpoint();
+        for d in devices {
+            d.0.borrow_mut().rotate_diagonal();
+        }
+        self.prune_nets();
+        true
+    }
+    /// delete all elements which appear in the selected array. Locked elements are left in place and remain selected.
+    /// If prune_stubs, wires left dangling at the ports of deleted devices are removed as well, see `Nets::prune_stubs`.
+    pub fn delete_selected(&mut self, prune_stubs: bool) {
         if let SchematicState::Idle = self.state {
-            for be in &self.selected {
+            self.checkpoint();
+            let (locked, unlocked): (HashSet<BaseElement>, HashSet<BaseElement>) = self.selected.iter().cloned().partition(|be| self.is_locked(be));
+            let mut orphans = vec![];
+            for be in &unlocked {
                 match be {
                     BaseElement::NetEdge(e) => {
                         self.nets.delete_edge(e);
                     }
                     BaseElement::Device(d) => {
+                        orphans.extend(d.0.borrow().ports_ssp());
                         self.devices.delete_device(d);
                     }
+                    BaseElement::Arrow(a) => {
+                        self.arrows.retain(|x| x != a);
+                    }
                 }
             }
-            self.selected.clear();
+            if prune_stubs {
+                self.nets.prune_stubs(orphans, &self.devices.ports_ssp());
+            }
+            self.selected = locked;
             self.prune_nets();
         }
     }
-    /// create netlist for the current schematic and save it.
-    fn netlist(&mut self) {
-        self.nets.pre_netlist();
+    /// create netlist for the current schematic and return it.
+    fn netlist_string(&mut self) -> String {
+        self.netlist_string_at(self.sim_config.temperature, true)
+    }
+    /// create netlist for the current schematic simulated at temperature, in degrees celsius, and return it.
+    /// If save_probed, analyses of control statements only save the voltages of probed nets, see `save_directive`.
+    fn netlist_string_at(&mut self, temperature: Option<f32>, save_probed: bool) -> String {
+        self.pre_netlist();
         let mut netlist = String::from("Netlist Created by Circe\n");
-        for d in self.devices.get_set() {
-            netlist.push_str(
-                &d.0.borrow_mut().spice_line(&mut self.nets)
-            );
+        let mut models = BTreeMap::new();
+        self.netlist_lines.clear();
+        let mut line = 2;
+        for d in self.devices.sorted() {
+            let spice_line = d.0.borrow_mut().spice_line(&mut self.nets);
+            let next = line + spice_line.matches('\n').count();
+            self.netlist_lines.push((line..next, d.clone()));
+            line = next;
+            netlist.push_str(&spice_line);
+            if d.0.borrow().disabled() {
+                continue;
+            }
+            if let Some((name, card)) = d.0.borrow().class().model_card() {
+                models.entry(name.to_ascii_lowercase()).or_insert(card);
+            }
+        }
+        for card in models.values() {
+            netlist.push_str(card);
+        }
+        if let Some(t) = temperature {
+            netlist.push_str(&format!(".temp {}\n", t));
+        }
+        if let (true, Some(save)) = (save_probed, self.save_directive()) {
+            netlist.push_str(&save);
+        }
+        netlist.push_str(&self.ic_directive());
+        for line in self.sim_config.directives.iter().filter(|l| !l.trim().is_empty()) {
+            netlist.push_str(line.trim());
+            netlist.push('\n');
         }
         netlist.push('\n');
-        fs::write("netlist.cir", netlist.as_bytes()).expect("Unable to write file");
+        netlist
+    }
+    /// select the device which produced the netlist line the first of ngspice errors referring to a line points at. 
+    /// Returns the bounding box of the device to fit the view to, or none if no error refers to a device line of the last netlist.
+    pub fn locate_ngspice_error(&mut self, errors: &[String]) -> Option<VSBox> {
+        let line = errors.iter().find_map(|e| error_line(e))?;
+        let d = self.netlist_lines.iter().find(|(lines, _)| lines.contains(&line))?.1.clone();
+        if !self.devices.get_set().contains(&d) {
+            return None;
+        }
+        self.clear_tentatives();
+        self.clear_selected();
+        self.selected.insert(BaseElement::Device(d));
+        self.selected_bounding_box()
+    }
+    /// returns a .save line limiting the vectors ngspice keeps to the voltages of probed nets, if there are probes and control statements. 
+    /// Without control statements, space runs a dc op which needs every net voltage for its annotations.
+    fn save_directive(&mut self) -> Option<String> {
+        if self.probes.is_empty() || !self.has_directives() {
+            return None;
+        }
+        self.refresh_probes();
+        let nets: std::collections::BTreeSet<&str> = self.probes.iter().map(|p| p.net.as_str()).collect();
+        Some(format!(".save {}\n", nets.into_iter().map(|n| format!("v({})", n)).collect::<Vec<_>>().join(" ")))
+    }
+    /// returns the control statements appended to the netlist, one per line
+    pub fn directives(&self) -> &[String] {
+        &self.sim_config.directives
+    }
+    /// set the control statements appended to the netlist, one per line. Blank lines are ignored.
+    pub fn set_directives(&mut self, directives: Vec<String>) {
+        self.sim_config.directives = directives;
+    }
+    /// returns true if any control statement is set, in which case simulations run the analyses of the control statements
+    pub fn has_directives(&self) -> bool {
+        self.sim_config.directives.iter().any(|l| !l.trim().is_empty())
+    }
+    /// check that every control statement is a dot command or comment, and that `.control` blocks are closed. 
+    /// Lines inside `.control` blocks are ngspice commands and are not checked.
+    pub fn validate_directives(&self) -> Result<(), String> {
+        let mut in_control = false;
+        for line in self.sim_config.directives.iter().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+            let lower = line.to_ascii_lowercase();
+            if in_control {
+                in_control = lower != ".endc";
+            } else if lower == ".control" {
+                in_control = true;
+            } else if !line.starts_with(['.', '*']) {
+                return Err(format!("control statement must start with '.': {}", line));
+            }
+        }
+        if in_control {
+            return Err(String::from(".control without .endc"));
+        }
+        Ok(())
+    }
+    /// returns the names of the models defined by `.model` control statements and by the files included with `.include` or `.lib`, 
+    /// lower case as spice names are case insensitive. Included files which cannot be read define nothing.
+    fn defined_models(&self) -> BTreeSet<String> {
+        fn model_names(lines: &str, models: &mut BTreeSet<String>) {
+            for line in lines.lines() {
+                let mut words = line.split_whitespace();
+                if words.next().is_some_and(|w| w.eq_ignore_ascii_case(".model")) {
+                    if let Some(name) = words.next() {
+                        models.insert(name.to_ascii_lowercase());
+                    }
+                }
+            }
+        }
+        let mut models = BTreeSet::new();
+        for line in &self.sim_config.directives {
+            model_names(line, &mut models);
+            let mut words = line.split_whitespace();
+            let is_include = words.next().is_some_and(|w| w.eq_ignore_ascii_case(".include") || w.eq_ignore_ascii_case(".lib"));
+            if let (true, Some(path)) = (is_include, words.next()) {
+                if let Ok(contents) = fs::read_to_string(path.trim_matches('"')) {
+                    model_names(&contents, &mut models);
+                }
+            }
+        }
+        models
+    }
+    /// returns every model referenced by a device which is neither emitted as a .model card by a device 
+    /// nor defined by the control statements, with the identifiers of the devices referencing it
+    pub fn missing_models(&self) -> Vec<(String, Vec<String>)> {
+        let mut defined = self.defined_models();
+        let devices: Vec<RcRDevice> = self.devices.sorted().into_iter().filter(|d| !d.0.borrow().disabled()).collect();
+        for d in &devices {
+            if let Some((name, _)) = d.0.borrow().class().model_card() {
+                defined.insert(name.to_ascii_lowercase());
+            }
+        }
+        let mut missing: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for d in &devices {
+            let d = d.0.borrow();
+            if let Some(model) = d.class().model_ref() {
+                if !defined.contains(&model.to_ascii_lowercase()) {
+                    missing.entry(model.to_string()).or_default().push(d.ng_id());
+                }
+            }
+        }
+        missing.into_iter().collect()
+    }
+    /// returns every model emitted as .model card by devices with differing model parameters, 
+    /// with the identifiers of the devices emitting it. Only the first card of a model is netlisted.
+    pub fn conflicting_models(&self) -> Vec<(String, Vec<String>)> {
+        let mut cards: BTreeMap<String, (String, Vec<(String, String)>)> = BTreeMap::new();
+        for d in self.devices.sorted().into_iter().filter(|d| !d.0.borrow().disabled()) {
+            let d = d.0.borrow();
+            if let Some((name, card)) = d.class().model_card() {
+                cards.entry(name.to_ascii_lowercase()).or_insert_with(|| (name, vec![])).1.push((card.to_ascii_lowercase(), d.ng_id()));
+            }
+        }
+        cards.into_values().filter(|(_, cards)| cards.iter().any(|(card, _)| *card != cards[0].0))
+            .map(|(name, cards)| (name, cards.into_iter().map(|(_, id)| id).collect()))
+            .collect()
+    }
+    /// check the schematic can be simulated: control statements and the parameters of every device must be valid. 
+    /// Devices with invalid parameters are drawn highlighted.
+    pub fn validate(&self) -> Result<(), String> {
+        self.validate_directives()?;
+        let invalid: Vec<String> = self.devices.sorted().iter().filter_map(|d| {
+            let d = d.0.borrow();
+            if d.disabled() {
+                return None;
+            }
+            d.class().validate_params().err().map(|e| format!("{}: {}", d.ng_id(), e))
+        }).collect();
+        if !invalid.is_empty() {
+            return Err(format!("invalid device parameters: {}", invalid.join(", ")));
+        }
+        let missing = self.missing_models();
+        if !missing.is_empty() {
+            let missing: Vec<String> = missing.iter().map(|(model, ids)| format!("{} ({})", model, ids.join(", "))).collect();
+            return Err(format!("undefined models: {}", missing.join(", ")));
+        }
+        let conflicting = self.conflicting_models();
+        if !conflicting.is_empty() {
+            let conflicting: Vec<String> = conflicting.iter().map(|(model, ids)| format!("{} ({})", model, ids.join(", "))).collect();
+            return Err(format!("conflicting model parameters: {}", conflicting.join(", ")));
+        }
+        if !self.zero_ohm_jumpers {
+            let zero: Vec<String> = self.devices.sorted().iter().filter_map(|d| {
+                let d = d.0.borrow();
+                let ssp = d.position();
+                (d.class().is_zero_value() && !d.disabled()).then(|| format!("{} at ({}, {})", d.ng_id(), ssp.x, ssp.y))
+            }).collect();
+            if !zero.is_empty() {
+                return Err(format!("zero value devices: {}", zero.join(", ")));
+            }
+        }
+        Ok(())
+    }
+    /// create netlist for the current schematic and save it to path.
+    pub fn netlist_to(&mut self, path: &str) -> io::Result<()> {
+        let netlist = self.netlist_string();
+        fs::write(path, netlist.as_bytes())
+    }
+    /// create netlist for the current schematic simulated at temperature, in degrees celsius, and save it to path.
+    pub fn netlist_at_temperature_to(&mut self, path: &str, temperature: f32) -> io::Result<()> {
+        let netlist = self.netlist_string_at(Some(temperature), false);
+        fs::write(path, netlist.as_bytes())
+    }
+    /// returns the simulation temperature in degrees celsius, none if the ngspice default is used
+    pub fn temperature(&self) -> Option<f32> {
+        self.sim_config.temperature
+    }
+    /// set the simulation temperature in degrees celsius, none to use the ngspice default
+    pub fn set_temperature(&mut self, temperature: Option<f32>) {
+        self.sim_config.temperature = temperature;
+    }
+    /// returns the temperatures in degrees celsius the operating point is swept over
+    pub fn temperature_sweep(&self) -> &[f32] {
+        &self.sim_config.temperature_sweep
+    }
+    /// set the temperatures in degrees celsius the operating point is swept over
+    pub fn set_temperature_sweep(&mut self, temperatures: Vec<f32>) {
+        self.sim_config.temperature_sweep = temperatures;
+    }
+    /// create netlist for the current schematic and save it, unless netlist.cir exists and overwriting it waits for confirmation.
+    pub fn netlist(&mut self) -> io::Result<SaveStatus> {
+        self.write_file(FileKind::Netlist, "netlist.cir")
+    }
+    /// returns the path of the scratch file name in a private directory of this session. 
+    /// Netlists are simulated from scratch files, so that running a simulation overwrites no file of the user.
+    pub fn session_file(name: &str) -> io::Result<String> {
+        Ok(session_dir()?.join(name).to_string_lossy().into_owned())
+    }
+    /// returns the names of the nets of the selected wires if they belong to exactly two distinct nets
+    pub fn selected_net_pair(&self) -> Option<(String, String)> {
+        let nets: std::collections::BTreeSet<String> = self.selected.iter().filter_map(|be| {
+            match be {
+                BaseElement::NetEdge(e) => e.label.as_ref().map(|l| l.to_string()),
+                BaseElement::Device(_) | BaseElement::Arrow(_) => None,
+            }
+        }).collect();
+        let mut nets = nets.into_iter();
+        match (nets.next(), nets.next(), nets.next()) {
+            (Some(a), Some(b), None) => Some((a, b)),
+            _ => None,
+        }
+    }
+    /// create netlist for the current schematic with a 1A ac test current source injected into net `a` from net `b`, and save it to path.
+    /// The voltage between `a` and `b` from an ac analysis is then the impedance between them.
+    /// The test source is not added to the schematic.
+    pub fn measure_netlist_to(&mut self, path: &str, a: &str, b: &str) -> io::Result<()> {
+        // every net is saved, the measured voltage is rarely between probed nets
+        let mut netlist = self.netlist_string_at(self.sim_config.temperature, false);
+        netlist.push_str(&format!("{} {} {} dc 0 ac 1\n", Schematic::MEASURE_SOURCE_ID, b, a));
+        fs::write(path, netlist.as_bytes())
+    }
+    /// returns the error message of the last failed file operation, if any
+    pub fn error(&self) -> Option<String> {
+        self.error.clone()
+    }
+    /// returns the warning from the last electrical rule check or file operation, if any
+    pub fn warning(&self) -> Option<String> {
+        self.warning.clone()
+    }
+    /// set whether floating nets are reported as errors which block simulation, or as warnings
+    pub fn set_block_floating_nets(&mut self, block: bool) {
+        self.block_floating_nets = block;
+    }
+    /// set whether placing a device with parameters first asks for its value, see `placement_prompt`
+    pub fn set_prompt_on_place(&mut self, prompt: bool) {
+        self.prompt_on_place = prompt;
+    }
+    /// returns the class name of the device waiting for its value before placement, 
+    /// with the name and default value of the parameter asked for
+    pub fn placement_prompt(&self) -> Option<(&'static str, String, String)> {
+        let d = self.pending_placement.as_ref()?.0.borrow();
+        let (name, value) = d.class().params().into_iter().next()?;
+        Some((d.class().name(), name, value))
+    }
+    /// set the value of the device waiting for it, see `placement_prompt`, and start placing it at ssp. 
+    /// A blank value keeps the default. Returns false if no device is waiting or the schematic is busy.
+    pub fn submit_placement_value(&mut self, value: &str, ssp: SSPoint) -> bool {
+        if !matches!(self.state, SchematicState::Idle) {
+            return false;
+        }
+        let Some(d) = self.pending_placement.take() else {return false};
+        if !value.trim().is_empty() {
+            let name = d.0.borrow().class().params().into_iter().next().map(|p| p.0);
+            if let Some(name) = name {
+                d.0.borrow_mut().class_mut().set_params(&[(name, value.trim().to_string())]);
+            }
+        }
+        self.state = self.start_moving_new(d, ssp);
+        true
+    }
+    /// discard the device waiting for its value
+    pub fn cancel_placement_prompt(&mut self) {
+        self.pending_placement = None;
+    }
+    /// set whether 0 ohm resistors are allowed as jumpers, or reported as errors which block simulation
+    pub fn set_zero_ohm_jumpers(&mut self, allow: bool) {
+        self.zero_ohm_jumpers = allow;
+    }
+    /// returns every net with exactly one device pin connected, as (net name, pin location), ordered by net name.
+    /// Net names match those of the netlist.
+    pub fn floating_nets(&mut self) -> Vec<(String, SSPoint)> {
+        self.pre_netlist();
+        let mut pins: BTreeMap<String, Vec<SSPoint>> = BTreeMap::new();
+        for d in self.devices.sorted().iter().filter(|d| !d.0.borrow().disabled()) {
+            for ssp in d.0.borrow().ports_ssp() {
+                pins.entry(self.nets.net_at(ssp)).or_default().push(ssp);
+            }
+        }
+        pins.into_iter().filter_map(|(net, ssps)| match ssps[..] {
+            [ssp] => Some((net, ssp)),
+            _ => None,
+        }).collect()
+    }
+    /// returns the number of enabled device pins connected to every net with wires, and the lowest left vertex of the net to badge it at, keyed by net name
+    fn connection_counts(&self) -> BTreeMap<String, (SSPoint, usize)> {
+        let mut counts: BTreeMap<String, (SSPoint, usize)> = BTreeMap::new();
+        for (src, dst, e) in self.nets.graph.all_edges() {
+            let Some(label) = &e.label else {continue};
+            let anchor = std::cmp::min_by_key(src.0, dst.0, |p| (p.y, p.x));
+            let entry = counts.entry(label.to_string()).or_insert((anchor, 0));
+            entry.0 = std::cmp::min_by_key(entry.0, anchor, |p| (p.y, p.x));
+        }
+        for d in self.devices.get_set().iter().filter(|d| !d.0.borrow().disabled()) {
+            for ssp in d.0.borrow().ports_ssp() {
+                if let Some(entry) = self.nets.label_at(ssp).and_then(|net| counts.get_mut(net.as_str())) {
+                    entry.1 += 1;
+                }
+            }
+        }
+        counts
+    }
+    /// draw a badge with the number of connected pins on every net. Nets with fewer than 2 pins connect nothing and are drawn red.
+    fn draw_connection_counts(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
+        let r = (0.6 * vcscale).max(6.0);
+        for (anchor, count) in self.connection_counts().into_values() {
+            let color = if count < 2 {Color::from_rgb(1.0, 0.2, 0.2)} else {Color::from_rgb(0.2, 0.7, 0.3)};
+            let center = Point::from(vct.transform_point(anchor.cast().cast_unit()) + CSVec::new(r, -r)).into();
+            frame.fill(&Path::circle(center, r), color);
+            frame.fill_text(Text {
+                content: count.to_string(),
+                position: center,
+                color: Color::WHITE,
+                size: 1.5 * r,
+                horizontal_alignment: Horizontal::Center,
+                vertical_alignment: Vertical::Center,
+                ..Default::default()
+            });
+        }
+    }
+    /// check for floating nets and report them as a warning, or as an error if they are set to block simulation
+    fn check_floating_nets(&mut self) {
+        let floating = self.floating_nets();
+        self.warning = None;
+        if floating.is_empty() {
+            return;
+        }
+        let list = floating.iter().map(|(net, ssp)| format!("{} at ({}, {})", net, ssp.x, ssp.y)).collect::<Vec<_>>().join(", ");
+        if self.block_floating_nets {
+            self.error = Some(format!("Error: Floating nets: {}", list));
+        } else {
+            self.warning = Some(format!("Warning: Floating nets: {}", list));
+        }
+    }
+    /// records the outcome of a file operation for display. what describes the operation, e.g. "write netlist"
+    fn report<T>(&mut self, what: &str, res: io::Result<T>) {
+        self.error = res.err().map(|e| format!("Error: Could not {}: {}", what, e));
+    }
+    /// save the schematic to path
+    pub fn save_to(&self, path: &str) -> io::Result<()> {
+        file::SchematicFile {
+            version: file::VERSION,
+            devices: self.devices.records(),
+            wires: self.nets.records(),
+            anchors: self.nets.anchors(),
+            sim: self.sim_config.clone(),
+            probes: self.probes.clone(),
+            net_classes: self.net_classes.clone(),
+            initial_conditions: self.initial_conditions.clone(),
+            grid_origin: self.grid_origin,
+            arrows: self.arrows.clone(),
+            view: self.view,
+        }.write(path)
+    }
+    /// save the schematic to path, which becomes the file ctrl+s and ctrl+o use, see `write_file`.
+    pub fn save_as(&mut self, path: &str) -> io::Result<SaveStatus> {
+        self.write_file(FileKind::Schematic, path)
+    }
+    /// write the file of kind to path. 
+    /// If path is an existing file other than the one opened, saved or exported last and overwriting is confirmed, 
+    /// nothing is written until `confirm_overwrite`.
+    pub fn write_file(&mut self, kind: FileKind, path: &str) -> io::Result<SaveStatus> {
+        let known = match kind {
+            FileKind::Schematic => self.file_path.as_deref() == Some(path),
+            FileKind::Netlist | FileKind::Bom | FileKind::KicadNetlist => self.exported.contains(path),
+        };
+        let overwrote = std::path::Path::new(path).exists() && !known;
+        if overwrote && self.confirm_overwrite {
+            self.pending_overwrite = Some((path.to_string(), kind));
+            return Ok(SaveStatus::NeedsConfirmation);
+        }
+        self.write_unchecked(kind, path)?;
+        self.pending_overwrite = None;
+        Ok(SaveStatus::Saved { overwrote })
+    }
+    /// write the file of kind to path, whether it exists or not
+    fn write_unchecked(&mut self, kind: FileKind, path: &str) -> io::Result<()> {
+        match kind {
+            FileKind::Schematic => {
+                self.save_to(path)?;
+                self.file_path = Some(path.to_string());
+                return Ok(());
+            },
+            FileKind::Netlist => self.netlist_to(path)?,
+            FileKind::Bom => bom::write_csv(&self.bom(), path)?,
+            FileKind::KicadNetlist => self.kicad_netlist_to(path)?,
+        }
+        self.exported.insert(path.to_string());
+        Ok(())
+    }
+    /// set where the next event places non-electrical elements, see Viewport::curpos_placement
+    pub fn set_curpos_placement(&mut self, vsp: VSPoint) {
+        self.placement_vsp = Some(vsp);
+    }
+    /// set the view of the canvas, saved with the schematic
+    pub fn set_view(&mut self, view: ViewRecord) {
+        self.view = Some(view);
+    }
+    /// returns the view saved with the schematic opened since last called, if it had one
+    pub fn take_opened_view(&mut self) -> Option<ViewRecord> {
+        self.opened_view.take()
+    }
+    /// returns true if a schematic was opened since last called
+    pub fn take_opened(&mut self) -> bool {
+        std::mem::take(&mut self.opened)
+    }
+    /// returns the file the schematic is saved to and opened from
+    pub fn file_path(&self) -> &str {
+        self.file_path.as_deref().unwrap_or(Schematic::SCHEMATIC_PATH)
+    }
+    /// set whether writing over an existing file other than the one opened, saved or exported last waits for confirmation
+    pub fn set_confirm_overwrite(&mut self, confirm: bool) {
+        self.confirm_overwrite = confirm;
+    }
+    /// returns the existing file waiting for confirmation before it is written over
+    pub fn overwrite_prompt(&self) -> Option<&str> {
+        self.pending_overwrite.as_ref().map(|(path, _)| path.as_str())
+    }
+    /// write over the file waiting for confirmation, see `overwrite_prompt`
+    pub fn confirm_overwrite(&mut self) -> io::Result<()> {
+        let Some((path, kind)) = self.pending_overwrite.take() else {return Ok(())};
+        self.write_unchecked(kind, &path)
+    }
+    /// discard the pending save, leaving the existing file as is
+    pub fn cancel_overwrite(&mut self) {
+        self.pending_overwrite = None;
+    }
+    /// save to the current file, see `report_write`
+    fn save_file(&mut self) {
+        let path = self.file_path().to_string();
+        self.report_write(FileKind::Schematic, &path, "save schematic");
+    }
+    /// write the file of kind to path, reporting a file replaced without confirmation as a warning. 
+    /// Other warnings are kept, e.g. of the last electrical rule check.
+    fn report_write(&mut self, kind: FileKind, path: &str, what: &str) {
+        let res = self.write_file(kind, path);
+        if let Ok(SaveStatus::Saved { overwrote: true }) = res {
+            self.warning = Some(format!("Warning: overwrote {}", path));
+        }
+        self.report(what, res);
+    }
+    /// replace the schematic with the one saved at path. The change is recorded in history.
+    pub fn open(&mut self, path: &str) -> io::Result<()> {
+        let file = file::SchematicFile::read(path)?;
+        let snapshot = self.snapshot();
+        self.devices.load_records(&file.devices).map_err(|class| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("unknown device class {}", class))
+        })?;
+        self.history.push(snapshot);
+        self.nets.load_records(&file.wires, &file.anchors);
+        self.sim_config = file.sim;
+        self.probes = file.probes;
+        self.net_classes = file.net_classes;
+        self.initial_conditions = file.initial_conditions;
+        self.grid_origin = file.grid_origin;
+        self.arrows.clear();
+        for a in file.arrows {
+            self.add_arrow(a);
+        }
+        self.file_path = Some(path.to_string());
+        self.view = file.view;
+        self.opened_view = file.view;
+        self.opened = true;
+        self.selected.clear();
+        self.state = SchematicState::Idle;
+        self.prune_nets();
+        Ok(())
+    }
+    /// returns the bill of materials for the current schematic. Devices of identical type, value and attributes are grouped together.
+    /// Devices which are not physical parts, e.g. grounds and sources, are left out.
+    pub fn bom(&self) -> Vec<BomLine> {
+        let mut groups: BTreeMap<(&'static str, String, BTreeMap<String, String>), Vec<String>> = BTreeMap::new();
+        for d in self.devices.get_set() {
+            let d = d.0.borrow();
+            if !d.class().is_part() {
+                continue;
+            }
+            groups.entry((d.class().name(), d.class().param_summary(), d.attributes().clone())).or_default().push(d.ng_id());
+        }
+        groups.into_iter().map(|((class, value, attributes), mut ids)| {
+            ids.sort_by(|a, b| bom::cmp_designators(a, b));
+            BomLine { count: ids.len(), ids, class: class.to_string(), value, attributes }
+        }).collect()
+    }
+    /// renumber device identifiers sequentially per device class, ordered top-left to bottom-right.
+    pub fn renumber(&mut self) {
+        self.checkpoint();
+        self.devices.renumber();
+    }
+    /// returns the components and nets of the current schematic for KiCad export, using the same net names as the spice netlist.
+    /// Devices which are not physical parts are left out, as are nets without any part connected.
+    fn kicad_netlist(&mut self) -> (Vec<kicad::Component>, Vec<kicad::Net>) {
+        self.pre_netlist();
+        let mut components = vec![];
+        let mut nets: BTreeMap<String, Vec<(String, usize)>> = BTreeMap::new();
+        for d in self.devices.sorted() {
+            if !d.0.borrow().class().is_part() {
+                continue;
+            }
+            let reference = d.0.borrow().ng_id();
+            for i in 0..d.0.borrow().ports_ssp().len() {
+                if let Some(net) = self.pin_net(&d, i) {
+                    nets.entry(net).or_default().push((reference.clone(), i + 1));
+                }
+            }
+            components.push(kicad::Component {
+                reference,
+                value: d.0.borrow().class().param_summary(),
+                footprint: d.0.borrow().attributes().get("footprint").cloned(),
+            });
+        }
+        let nets = nets.into_iter().map(|(name, nodes)| kicad::Net { name, nodes }).collect();
+        (components, nets)
+    }
+    /// create a KiCad netlist for the current schematic and save it to path.
+    pub fn kicad_netlist_to(&mut self, path: &str) -> io::Result<()> {
+        let (components, nets) = self.kicad_netlist();
+        kicad::write(&components, &nets, path)
+    }
+    /// clean up wires across the whole schematic: merge collinear segments, remove zero-length segments and stray vertices.
+    pub fn cleanup_wires(&mut self) {
+        self.checkpoint();
+        self.selected.retain(|be| matches!(be, BaseElement::Device(_)));
+        self.nets.cleanup(self.devices.enabled_ports_ssp());
+        self.prune_nets();
     }
     /// clear up nets graph: merging segments, cleaning up segment net names, etc.
     fn prune_nets(&mut self) {
-        self.nets.prune(self.devices.ports_ssp());
+        self.nets.set_rails(self.devices.rails());
+        self.nets.prune(self.devices.enabled_ports_ssp());
+        self.nets.prune_anchors(&self.devices.ports_ssp());
+        self.net_classes.prune(&self.nets);
+        self.initial_conditions.prune(&self.nets);
+        self.refresh_probes();
+    }
+    /// name nets for netlisting, nets touching power rails are named after the rail
+    fn pre_netlist(&mut self) {
+        self.nets.set_rails(self.devices.rails());
+        self.nets.pre_netlist();
+    }
+    /// rename probes after the net at their position, net names may change whenever nets are edited. 
+    /// Probes no longer on any net keep their last name.
+    fn refresh_probes(&mut self) {
+        for p in &mut self.probes {
+            if let Some(label) = self.nets.label_at(p.pos) {
+                p.net = label.to_string();
+            }
+            p.update(&self.op_voltages);
+        }
+    }
+    /// place a probe on the net at ssp, or remove the probe at ssp if there is one. 
+    /// Returns false if there is neither a probe nor a net at ssp.
+    pub fn toggle_probe(&mut self, ssp: SSPoint) -> bool {
+        if let Some(i) = self.probes.iter().position(|p| p.pos == ssp) {
+            self.probes.remove(i);
+            return true;
+        }
+        match self.nets.label_at(ssp) {
+            Some(label) => {
+                let mut probe = Probe::new(label.to_string(), ssp);
+                probe.update(&self.op_voltages);
+                self.probes.push(probe);
+                true
+            },
+            None => false,
+        }
     }
-    /// move all elements in the selected array by sst
+    /// returns the voltage probes
+    pub fn probes(&self) -> &[Probe] {
+        &self.probes
+    }
+    /// replace the wire segment e by wires routed from its source to handle and on to its destination, bending it at handle
+    fn bend_wire(&mut self, e: &NetEdge, handle: SSPoint) {
+        self.checkpoint();
+        self.nets.delete_edge(e);
+        let mut g = Nets::default();
+        g.route(e.src, handle);
+        g.route(handle, e.dst);
+        self.nets.merge(&g, self.devices.ports_ssp());
+        self.selected.retain(|be| match be {
+            BaseElement::NetEdge(e) => self.nets.graph.contains_edge(NetVertex(e.src), NetVertex(e.dst)),
+            BaseElement::Device(_) | BaseElement::Arrow(_) => true,
+        });
+        self.refresh_probes();
+    }
+    /// move all elements in the selected array by sst. Locked elements are left in place.
     fn move_selected(&mut self, sst: SSTransform) {
-        let selected = self.selected.clone();
-        self.selected.clear();
-        for be in selected {
+        let selected: Vec<BaseElement> = self.selected.drain().collect();
+        let mut unlocked: Vec<BaseElement> = selected.into_iter().filter(|be| !self.is_locked(be)).collect();
+        // move wires first, so that wires moved along with a device are not dragged by its ports
+        unlocked.sort_by_key(|be| matches!(be, BaseElement::Device(_)));
+        // wires ending at device ports follow translated devices. 
+        // Rotating or mirroring may swap port positions, rerouting would then cross wires - only anchored wires follow then.
+        let translation = sst.m11 == 1 && sst.m12 == 0 && sst.m21 == 0 && sst.m22 == 1;
+        for be in unlocked {
             match be {
                 BaseElement::NetEdge(e) => {
                     self.nets.transform(e, sst);  // how to handle copying? e.g. adds new nets
                 }
                 BaseElement::Device(d) => {
+                    let anchored = self.anchored_ports(&d, translation);
+                    let ports0 = d.0.borrow().ports_ssp();
                     d.0.borrow_mut().transform(sst);
+                    let ports1 = d.0.borrow().ports_ssp();
+                    let moves: Vec<(SSPoint, SSPoint)> = anchored.into_iter().map(|i| (ports0[i], ports1[i])).collect();
+                    self.nets.drag_vertices(&moves);
                     self.devices.insert(d);
                 }
+                BaseElement::Arrow(mut a) => {
+                    self.arrows.retain(|x| *x != a);
+                    a.transform(sst);
+                    self.arrows.push(a);
+                }
             }
         }
     }
+    /// returns the indices of the ports of d whose wires follow d when it is moved: ports wires were started from, 
+    /// and if connected, every port a wire ends at. Devices being placed are not part of the schematic and drag no wires.
+    fn anchored_ports(&self, d: &RcRDevice, connected: bool) -> Vec<usize> {
+        if !self.devices.contains(d) {
+            return vec![];
+        }
+        let ports = d.0.borrow().ports_ssp();
+        (0..ports.len()).filter(|&i| {
+            self.nets.is_anchored(ports[i]) || (connected && self.nets.graph.contains_node(NetVertex(ports[i])))
+        }).collect()
+    }
+    /// if ssp is a device port, anchor wires at the port to it so that they follow the device when it is moved
+    fn anchor_port(&mut self, ssp: SSPoint) {
+        if self.devices.ports_ssp().contains(&ssp) {
+            self.nets.anchor(ssp);
+        }
+    }
+    /// discard newly created devices which were being placed. 
+    /// Devices are only inserted into the schematic once placed, so any selected device not in the schematic was never confirmed.
+    fn cancel_placement(&mut self) {
+        let devices = &self.devices;
+        self.selected.retain(|be| match be {
+            BaseElement::Device(d) => devices.contains(d),
+            BaseElement::NetEdge(_) | BaseElement::Arrow(_) => true,
+        });
+    }
+    /// netlist name of the net at port port_index of d, in netlist order, if a wire is connected. Names may change after any edit
+    pub fn pin_net(&self, d: &RcRDevice, port_index: usize) -> Option<String> {
+        let ssp = *d.0.borrow().ports_ssp().get(port_index)?;
+        self.nets.label_at_vertex(ssp).map(|l| l.to_string())
+    }
+    /// returns every device port connected to the net named name, as (device, port index) in netlist order. 
+    /// See `pin_net` for the stability of net names.
+    pub fn net_members(&self, name: &str) -> Vec<(RcRDevice, usize)> {
+        self.devices.sorted().into_iter().flat_map(|d| {
+            let nports = d.0.borrow().ports_ssp().len();
+            (0..nports)
+            .filter(|i| self.pin_net(&d, *i).as_deref() == Some(name))
+            .map(|i| (d.clone(), i))
+            .collect::<Vec<_>>()
+        }).collect()
+    }
+    /// set the default parameters of newly placed devices of the class named class_name
+    pub fn set_device_defaults(&mut self, class_name: &str, params: Vec<(String, String)>) {
+        self.devices.set_defaults(class_name, params);
+    }
+    /// returns the default parameters of every device class, keyed by class name
+    pub fn device_defaults(&self) -> &BTreeMap<String, Vec<(String, String)>> {
+        self.devices.defaults()
+    }
+    /// replace the recently applied parameters of every device class, keyed by class name
+    pub fn set_recent_params(&mut self, recent: BTreeMap<String, Vec<Vec<(String, String)>>>) {
+        self.devices.set_recent(recent);
+    }
+    /// returns the recently applied parameters of every device class, most recent first, keyed by class name
+    pub fn recent_params(&self) -> &BTreeMap<String, Vec<Vec<(String, String)>>> {
+        self.devices.recent()
+    }
+    /// set whether optional indicators are left out when drawing, to reduce per-frame drawing
+    pub fn set_low_quality(&mut self, low_quality: bool) {
+        self.low_quality = low_quality;
+    }
+    /// set whether wire bends are drawn with rounded corners
+    pub fn set_rounded_corners(&mut self, rounded: bool) {
+        self.rounded_corners = rounded;
+    }
+    /// set whether every net is drawn with a badge counting its connected pins
+    pub fn set_inspect_connections(&mut self, inspect: bool) {
+        self.inspect_connections = inspect;
+    }
+    /// set whether the ports of multi-terminal devices are labeled with their names
+    pub fn set_show_pin_names(&mut self, show: bool) {
+        self.devices.set_show_pin_names(show);
+    }
+    /// returns the number of significant figures of operating point annotations
+    pub fn op_precision(&self) -> usize {
+        self.devices.op_precision()
+    }
+    /// set the number of significant figures of operating point annotations
+    pub fn set_op_precision(&mut self, precision: usize) {
+        self.devices.set_op_precision(precision);
+    }
+    /// set the separators of operating point annotations and probe readouts
+    pub fn set_number_format(&mut self, number_format: NumberFormat) {
+        self.devices.set_number_format(number_format);
+    }
+    /// set the text color of operating point annotations, to contrast the canvas background
+    pub fn set_annotation_color(&mut self, color: Color) {
+        self.devices.set_op_color(color);
+    }
+    /// set the operating point current limit above which devices are drawn flagged. None to disable.
+    pub fn set_current_limit(&mut self, limit: Option<f32>) {
+        self.devices.set_current_limit(limit);
+    }
     /// register op sim results with schematic
     pub fn op(&mut self, pkvecvaluesall: &paprika::PkVecvaluesall) {
         self.devices.op(pkvecvaluesall);
+        // node voltages are named after their net, branch currents are suffixed with #branch
+        self.op_voltages = pkvecvaluesall.vecsa.iter()
+        .filter(|v| !v.name.contains('#'))
+        .map(|v| (v.name.clone(), v.creal as f32))
+        .collect();
+        for p in &mut self.probes {
+            p.update(&self.op_voltages);
+        }
+    }
+    /// register the op sim results at temperature, in degrees celsius, as a point of a temperature sweep
+    pub fn op_sweep_point(&mut self, temperature: f32, pkvecvaluesall: &paprika::PkVecvaluesall) {
+        self.op(pkvecvaluesall);
+        self.sweep_results.push((format!("{} °C", temperature), self.op_voltages.clone()));
+    }
+    /// clear the results of the last temperature sweep
+    pub fn clear_sweep_results(&mut self) {
+        self.sweep_results.clear();
+    }
+    /// returns the net voltages of the operating point at each temperature of the last temperature sweep, labeled by temperature
+    pub fn sweep_results(&self) -> &[(String, OpVoltages)] {
+        &self.sweep_results
+    }
+    /// save the results of the last operating point as the baseline named name. Returns false if there are no results.
+    pub fn save_op_baseline(&mut self, name: &str) -> bool {
+        if self.op_voltages.is_empty() {
+            return false;
+        }
+        self.op_baseline = Some(opcompare::OpBaseline { name: name.to_string(), voltages: self.op_voltages.clone() });
+        true
+    }
+    /// returns the name of the operating point baseline and the voltage of every net in it and in the last operating point, 
+    /// or none if no baseline is saved
+    pub fn op_comparison(&self) -> Option<(String, Vec<OpDelta>)> {
+        let baseline = self.op_baseline.as_ref()?;
+        Some((baseline.name.clone(), opcompare::compare(&baseline.voltages, &self.op_voltages)))
     }
     /// mutate schematic based on event
+    /// returns the tentative net name, whether the passive cache must be redrawn, and whether the active cache must be redrawn
     pub fn events_handler(
         &mut self, 
         event: Event, 
         curpos_ssp: SSPoint, 
-    ) -> (Option<String>, bool) {
+    ) -> (Option<String>, bool, bool) {
         let mut ret = None;
         let mut clear_passive = false;
+        // events which match no handler leave the active layer unchanged, unless the cursor moved
+        let mut clear_active = matches!(event, Event::Mouse(iced::mouse::Event::CursorMoved { .. }));
+
+        self.snap_port = self.port_near(curpos_ssp);
+        self.hover_ssp = curpos_ssp;
+        let curpos_vsp = self.placement_vsp.take().unwrap_or(curpos_ssp.cast().cast_unit());
+        let selection: Vec<BaseElement> = self.selected.iter().cloned().collect();
+        let curpos_ssp = match (&self.state, self.snap_port) {
+            (SchematicState::Wiring(..), Some(ssp)) => ssp,
+            _ => curpos_ssp,
+        };
 
         if let Event::Mouse(iced::mouse::Event::CursorMoved { .. }) = event {
-            let mut skip = self.selskip.saturating_sub(1);
-            ret = self.tentative_by_sspoint(curpos_ssp, &mut skip);
-            self.selskip = skip;
+            if self.last_click.as_ref().is_some_and(|(ssp, _)| *ssp != curpos_ssp) {
+                self.last_click = None;
+            }
+            // hovering a device port highlights the connected net rather than the device
+            ret = match self.state {
+                SchematicState::Idle => self.tentative_net_by_port(curpos_ssp),
+                _ => None,
+            };
+            if ret.is_none() {
+                let mut skip = self.selskip.saturating_sub(1);
+                ret = self.tentative_by_sspoint(curpos_ssp, &mut skip);
+                self.selskip = skip;
+            }
         }
 
+        let action = self.action(&event);
         let mut state = self.state.clone();
-        match (&mut state, event) {
+        match (&mut state, event, action) {
             // wiring
             (
                 _, 
-                Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::W, modifiers: _})
+                _,
+                Some(Action::Wire),
             ) => {
-                state = SchematicState::Wiring(None);
+                state = SchematicState::Wiring(None, (curpos_ssp, self.connects_at(curpos_ssp)));
             },
             (
-                SchematicState::Wiring(Some((g, prev_ssp))), 
-                Event::Mouse(iced::mouse::Event::CursorMoved { .. })
+                SchematicState::Wiring(opt_ws, end), 
+                Event::Mouse(iced::mouse::Event::CursorMoved { .. }),
+                _,
             ) => {
-                g.as_mut().clear();
-                g.route(*prev_ssp, curpos_ssp);
+                if let Some((g, prev_ssp, _)) = opt_ws {
+                    g.as_mut().clear();
+                    g.route(*prev_ssp, curpos_ssp);
+                }
+                *end = (curpos_ssp, self.connects_at(curpos_ssp));
             },
             (
-                SchematicState::Wiring(opt_ws), 
-                Event::Mouse(iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left))
+                SchematicState::Wiring(opt_ws, _), 
+                Event::Mouse(iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left)),
+                _,
             ) => {
                 let ssp = curpos_ssp;
                 let mut new_ws = None;
-                if let Some((g, prev_ssp)) = opt_ws {  // subsequent click
+                if let Some((g, prev_ssp, total)) = opt_ws {  // subsequent click
                     if ssp == *prev_ssp { 
-                    } else if self.occupies_ssp(ssp) {
-                        self.nets.merge(g.as_ref(), self.devices.ports_ssp());
-                        new_ws = None;
                     } else {
+                        let ends = self.occupies_ssp(ssp);
+                        self.checkpoint();
                         self.nets.merge(g.as_ref(), self.devices.ports_ssp());
-                        new_ws = Some((Box::<Nets>::default(), ssp));
+                        // a wire started at a port is anchored to it once its first segment is placed
+                        if *total == 0 {
+                            self.anchor_port(*prev_ssp);
+                        }
+                        if !ends {
+                            new_ws = Some((Box::<Nets>::default(), ssp, *total + wire_length(*prev_ssp, ssp)));
+                        }
                     }
                 } else {  // first click
-                    new_ws = Some((Box::<Nets>::default(), ssp));
+                    new_ws = Some((Box::<Nets>::default(), ssp, 0));
                 }
-                state = SchematicState::Wiring(new_ws);
+                state = SchematicState::Wiring(new_ws, (ssp, self.connects_at(ssp)));
                 clear_passive = true;
             },
             // selecting
             (
                 SchematicState::Idle, 
-                Event::Mouse(iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left))
+                Event::Mouse(iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left)),
+                _,
             ) => {
-                state = SchematicState::Selecting(SSBox::new(curpos_ssp, curpos_ssp));
+                state = SchematicState::Selecting(SSBox::new(curpos_ssp, curpos_ssp), false);
             },
             (
-                SchematicState::Selecting(ssb), 
-                Event::Mouse(iced::mouse::Event::CursorMoved { .. })
+                SchematicState::Selecting(ssb, dragged), 
+                Event::Mouse(iced::mouse::Event::CursorMoved { .. }),
+                _,
             ) => {
+                *dragged |= ssb.max != curpos_ssp;
                 ssb.max = curpos_ssp;
                 self.tentatives_by_ssbox(ssb);
             },
             (
-                SchematicState::Selecting(_), 
-                Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left))
+                SchematicState::Selecting(ssb, dragged), 
+                Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left)),
+                _,
             ) => {
-                self.tentatives_to_selected();
+                // a plain click selects the element under the cursor, a drag selects by box
+                if !*dragged {
+                    self.select_at(ssb.min);
+                } else {
+                    self.tentatives_to_selected();
+                }
                 state = SchematicState::Idle;
                 clear_passive = true;
             },
+            // copy/paste
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::Copy),
+            ) => {
+                self.copy_selected(curpos_ssp);
+            },
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::Paste),
+            ) => {
+                clear_passive = self.paste(curpos_ssp);
+            },
+            // save/open
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::Save),
+            ) => {
+                self.save_file();
+            },
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::Open),
+            ) => {
+                let path = self.file_path().to_string();
+                let res = self.open(&path);
+                self.report("open schematic", res);
+                clear_passive = true;
+            },
+            // rotate selected in place
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::RotateSelected),
+            ) => {
+                clear_passive = self.rotate_selected(transforms::SST_CCWR);
+            },
+            // turn selected devices 45 deg
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::RotateDiagonal),
+            ) => {
+                clear_passive = self.rotate_diagonal_selected();
+            },
             // device placement
             (
                 SchematicState::Idle, 
-                Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::R, modifiers: _})
+                _,
+                Some(Action::PlaceResistor),
             ) => {
-                self.selected.clear();
                 let d = self.devices.new_res();
-                d.0.borrow_mut().set_position(curpos_ssp);
-                self.selected.insert(BaseElement::Device(d));
-                state = SchematicState::Moving(Some((curpos_ssp, curpos_ssp, SSTransform::identity())));
+                state = self.start_placement(d, curpos_ssp);
+            },
+            // grid origin
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::MoveGridOrigin),
+            ) => {
+                self.checkpoint();
+                self.grid_origin = curpos_ssp;
+                clear_passive = true;
             },
             (
                 SchematicState::Idle, 
-                Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::G, modifiers: _})
+                _,
+                Some(Action::PlaceGround),
             ) => {
-                self.selected.clear();
                 let d = self.devices.new_gnd();
-                d.0.borrow_mut().set_position(curpos_ssp);
-                self.selected.insert(BaseElement::Device(d));
-                state = SchematicState::Moving(Some((curpos_ssp, curpos_ssp, SSTransform::identity())));
+                state = self.start_placement(d, curpos_ssp);
             },
             (
                 SchematicState::Idle, 
-                Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::V, modifiers: _})
+                _,
+                Some(Action::PlaceVoltageSource),
             ) => {
-                self.selected.clear();
                 let d = self.devices.new_vs();
-                d.0.borrow_mut().set_position(curpos_ssp);
-                self.selected.insert(BaseElement::Device(d));
-                state = SchematicState::Moving(Some((curpos_ssp, curpos_ssp, SSTransform::identity())));
+                state = self.start_placement(d, curpos_ssp);
+            },
+            // voltage probe
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::ToggleProbe),
+            ) => {
+                if self.toggle_probe(curpos_ssp) {
+                    clear_passive = true;
+                }
+            },
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::PlacePotentiometer),
+            ) => {
+                let d = self.devices.new_pot();
+                state = self.start_placement(d, curpos_ssp);
+            },
+            // wire bending
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::Bend),
+            ) => {
+                state = SchematicState::Bending(None);
+            },
+            (
+                SchematicState::Bending(None), 
+                Event::Mouse(iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left)),
+                _,
+            ) => {
+                let e = self.nets.graph.all_edges()
+                .find(|e| e.2.interactable.contains_ssp(curpos_ssp) && !e.2.interactable.locked)
+                .map(|e| e.2.clone());
+                if let Some(e) = e {
+                    state = SchematicState::Bending(Some((e, curpos_ssp)));
+                }
+            },
+            (
+                SchematicState::Bending(Some((_, handle))), 
+                Event::Mouse(iced::mouse::Event::CursorMoved { .. }),
+                _,
+            ) => {
+                *handle = curpos_ssp;
+            },
+            (
+                SchematicState::Bending(Some((e, handle))), 
+                Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left)),
+                _,
+            ) => {
+                self.bend_wire(e, *handle);
+                state = SchematicState::Bending(None);
+                clear_passive = true;
+            },
+            // arrow annotation
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::Annotate),
+            ) => {
+                state = SchematicState::Annotating(None, curpos_vsp);
+            },
+            (
+                SchematicState::Annotating(_, to), 
+                Event::Mouse(iced::mouse::Event::CursorMoved { .. }),
+                _,
+            ) => {
+                *to = curpos_vsp;
+            },
+            (
+                SchematicState::Annotating(from, _), 
+                Event::Mouse(iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left)),
+                _,
+            ) => {
+                match from {
+                    Some(from) if *from != curpos_vsp => {
+                        self.checkpoint();
+                        self.add_arrow(ArrowAnnotation::new(*from, curpos_vsp, self.arrow_label.clone()));
+                        state = SchematicState::Annotating(None, curpos_vsp);
+                        clear_passive = true;
+                    },
+                    Some(_) => {},
+                    None => *from = Some(curpos_vsp),
+                }
             },
             // moving
             (
                 _, 
-                Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::M, modifiers: _})
+                _,
+                Some(Action::Move),
             ) => {
                 state = SchematicState::Moving(None);
             },
             (
                 SchematicState::Moving(Some((_ssp0, ssp1, _sst))),
-                Event::Mouse(iced::mouse::Event::CursorMoved { .. })
+                Event::Mouse(iced::mouse::Event::CursorMoved { .. }),
+                _,
             ) => {
                 *ssp1 = curpos_ssp;
             },
             (
                 SchematicState::Moving(Some((_ssp0, _ssp1, sst))), 
-                Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::R, modifiers: _})
+                _,
+                Some(Action::RotatePlacement),
             ) => {
                 *sst = sst.then(&transforms::SST_CWR);
             },
+            (
+                SchematicState::Moving(Some((_ssp0, _ssp1, sst))), 
+                _,
+                Some(Action::RotatePlacementBack),
+            ) => {
+                *sst = sst.then(&transforms::SST_CCWR);
+            },
+            (
+                SchematicState::Moving(Some((_ssp0, _ssp1, sst))), 
+                _,
+                Some(Action::MirrorX),
+            ) => {
+                *sst = sst.then(&transforms::SST_XMIR);
+            },
+            (
+                SchematicState::Moving(Some((_ssp0, _ssp1, sst))), 
+                _,
+                Some(Action::MirrorY),
+            ) => {
+                *sst = sst.then(&transforms::SST_YMIR);
+            },
             (
                 SchematicState::Moving(mut opt_pts),
-                Event::Mouse(iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left))
+                Event::Mouse(iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left)),
+                _,
             ) => {
                 if let Some((ssp0, ssp1, vvt)) = &mut opt_pts {
+                    self.checkpoint();
                     self.move_selected(SchematicState::move_transform(ssp0, ssp1, vvt));
                     self.prune_nets();
                     state = SchematicState::Idle;
@@ -464,13 +2438,21 @@ impl Schematic {
             // esc
             (
                 st, 
-                Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::Escape, modifiers: _})
+                _,
+                Some(Action::Cancel),
             ) => {
                 match st {
                     SchematicState::Idle => {
                         self.clear_selected();
+                        self.last_click = None;
+                        self.found_net = None;
+                        self.pending_placement = None;
                         clear_passive = true;
                     }
+                    SchematicState::Moving(_) => {
+                        self.cancel_placement();
+                        state = SchematicState::Idle;
+                    }
                     _ => {
                         state = SchematicState::Idle;
                     }
@@ -479,36 +2461,1816 @@ impl Schematic {
             // delete
             (
                 SchematicState::Idle, 
-                Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::Delete, modifiers: _})
+                _,
+                Some(Action::Delete),
             ) => {
-                self.delete_selected();
+                self.delete_selected(false);
                 clear_passive = true;
             },
-            // cycle
             (
                 SchematicState::Idle, 
-                Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::C, modifiers: _})
+                _,
+                Some(Action::DeleteWithWires),
             ) => {
-                ret = self.tentative_next_by_ssp(curpos_ssp);
+                self.delete_selected(true);
+                clear_passive = true;
             },
-            // test
+            // undo/redo
             (
                 SchematicState::Idle, 
-                Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::T, modifiers: _})
+                _,
+                Some(Action::Undo),
             ) => {
-                self.netlist();
+                clear_passive = self.undo();
             },
-            // dc op
             (
                 SchematicState::Idle, 
-                Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::Space, modifiers: _})
+                _,
+                Some(Action::Redo),
             ) => {
-                self.netlist();
-                clear_passive = true;
+                clear_passive = self.redo();
+            },
+            // duplicate
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::Duplicate),
+            ) => {
+                clear_passive = self.duplicate_selected();
+            },
+            // disable
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::Disable),
+            ) => {
+                clear_passive = self.toggle_disable_selected();
+            },
+            // cycle
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::Cycle),
+            ) => {
+                ret = self.tentative_next_by_ssp(curpos_ssp);
+            },
+            // test
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::WriteNetlist),
+            ) => {
+                self.report_write(FileKind::Netlist, "netlist.cir", "write netlist");
+            },
+            // swap ports
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::SwapPorts),
+            ) => {
+                clear_passive = self.swap_ports_selected();
+            },
+            // lock
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::Lock),
+            ) => {
+                clear_passive = self.toggle_lock_selected();
+            },
+            // clean up wires
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::CleanUpWires),
+            ) => {
+                self.cleanup_wires();
+                clear_passive = true;
+            },
+            // renumber
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::Renumber),
+            ) => {
+                self.renumber();
+                clear_passive = true;
+            },
+            // bill of materials
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::WriteBom),
+            ) => {
+                self.report_write(FileKind::Bom, "bom.csv", "write bill of materials");
+            },
+            // kicad netlist
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::WriteKicadNetlist),
+            ) => {
+                self.report_write(FileKind::KicadNetlist, "netlist.net", "write KiCad netlist");
+            },
+            // dc op
+            (
+                SchematicState::Idle, 
+                _,
+                Some(Action::RunDcOp),
+            ) => {
+                if let Err(e) = self.validate() {
+                    self.error = Some(format!("Error: {}", e));
+                } else {
+                    let res = Schematic::session_file("netlist.cir").and_then(|path| self.netlist_to(&path));
+                    self.report("write netlist", res);
+                    if self.error.is_none() {
+                        self.check_floating_nets();
+                    }
+                }
+                clear_passive = true;
+            },
+            _ => {
+                self.state = state;
+                self.note_selection_change(&selection);
+                return (ret, clear_passive, clear_active);
             },
-            _ => {},
         }
+        clear_active = true;
         self.state = state;
-        (ret, clear_passive)
+        self.note_selection_change(&selection);
+        (ret, clear_passive, clear_active)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a path in the temp dir unique to this test run, whose file is removed when dropped
+    struct TempFile(String);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            static COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+            let n = COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let path = std::env::temp_dir().join(format!("circe_test_{}_{}_{}", std::process::id(), n, name));
+            TempFile(path.to_string_lossy().into_owned())
+        }
+
+        fn path(&self) -> &str {
+            &self.0
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    /// saves schematic to a temp file and returns a schematic opened from it
+    fn round_trip(schematic: &Schematic) -> Schematic {
+        let file = TempFile::new("round_trip.circe");
+        schematic.save_to(file.path()).unwrap();
+        let mut opened = Schematic::default();
+        opened.open(file.path()).unwrap();
+        opened
+    }
+
+    /// key press of key_code without modifiers
+    fn key(key_code: iced::keyboard::KeyCode) -> Event {
+        Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code, modifiers: iced::keyboard::Modifiers::empty()})
+    }
+
+    /// left mouse button press
+    fn click() -> Event {
+        Event::Mouse(iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left))
+    }
+
+    /// left mouse button release
+    fn release() -> Event {
+        Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left))
+    }
+
+    /// cursor movement. The schematic only reads the cursor position passed along with the event to `events_handler`
+    fn moved() -> Event {
+        Event::Mouse(iced::mouse::Event::CursorMoved { position: iced::Point::ORIGIN })
+    }
+
+    /// places a voltage source at the origin with a wire leading away from each port.
+    /// returns the device and the net names connected to its + and - ports, in that order.
+    fn vs_with_wires(schematic: &mut Schematic) -> (RcRDevice, String, String) {
+        let d = schematic.devices.new_vs();
+        device_with_wires(schematic, d)
+    }
+
+    /// a new schematic with a voltage source from `vs_with_wires`
+    fn schematic_with_vs() -> (Schematic, RcRDevice, String, String) {
+        let mut schematic = Schematic::default();
+        let (d, pos, neg) = vs_with_wires(&mut schematic);
+        (schematic, d, pos, neg)
+    }
+
+    /// places d at the origin with a wire leading away from each port, see `vs_with_wires`
+    fn device_with_wires(schematic: &mut Schematic, d: RcRDevice) -> (RcRDevice, String, String) {
+        d.0.borrow_mut().set_position(SSPoint::origin());
+        schematic.devices.insert(d.clone());
+
+        let mut wires = Nets::default();
+        wires.route(SSPoint::new(0, 3), SSPoint::new(4, 3));
+        wires.route(SSPoint::new(0, -3), SSPoint::new(4, -3));
+        schematic.nets.merge(&wires, schematic.devices.ports_ssp());
+
+        let pos = schematic.nets.net_at(SSPoint::new(4, 3));
+        let neg = schematic.nets.net_at(SSPoint::new(4, -3));
+        (d, pos, neg)
+    }
+
+    /// wires r at the origin from its port at (0, 3) to (6, 3), started at the port
+    fn wire_from_port(schematic: &mut Schematic, r: &RcRDevice) {
+        r.0.borrow_mut().set_position(SSPoint::origin());
+        schematic.devices.insert(r.clone());
+        schematic.state = SchematicState::Wiring(None, (SSPoint::origin(), false));
+        schematic.events_handler(click(), SSPoint::new(0, 3));
+        schematic.events_handler(moved(), SSPoint::new(6, 3));
+        schematic.events_handler(click(), SSPoint::new(6, 3));
+        schematic.state = SchematicState::Idle;
+    }
+
+    /// flips the device in place through the same path used when placing/moving devices
+    fn flip(schematic: &mut Schematic, d: &RcRDevice, sst: SSTransform) {
+        schematic.selected.insert(BaseElement::Device(d.clone()));
+        let ssp = SSPoint::origin();
+        schematic.move_selected(SchematicState::move_transform(&ssp, &ssp, &sst));
+        schematic.prune_nets();
+    }
+
+    #[test]
+    fn undo_restores_selection() {
+        let (mut schematic, d, _, _) = schematic_with_vs();
+        let e = schematic.nets.graph.all_edges().next().unwrap().2.clone();
+
+        schematic.selected.insert(BaseElement::Device(d.clone()));
+        schematic.checkpoint();
+        schematic.selected.clear();
+        schematic.selected.insert(BaseElement::NetEdge(e.clone()));
+        schematic.delete_selected(false);
+        assert!(schematic.selected.is_empty());
+
+        assert!(schematic.undo());
+        assert!(schematic.selected.contains(&BaseElement::NetEdge(e.clone())));
+        assert!(schematic.undo());
+        assert_eq!(schematic.selected.len(), 1);
+        assert!(schematic.selected.contains(&BaseElement::Device(d.clone())));
+        assert!(!schematic.undo());
+
+        assert!(schematic.redo());
+        assert!(schematic.selected.contains(&BaseElement::NetEdge(e)));
+    }
+
+    #[test]
+    fn undo_drops_selected_elements_which_no_longer_exist() {
+        let mut schematic = Schematic::default();
+        schematic.checkpoint();
+        let (d, _, _) = vs_with_wires(&mut schematic);
+        schematic.selected.insert(BaseElement::Device(d.clone()));
+
+        assert!(schematic.undo());
+        assert!(schematic.selected.is_empty());
+        assert!(!schematic.devices.contains(&d));
+
+        assert!(schematic.redo());
+        assert!(schematic.devices.contains(&d));
+        assert!(schematic.selected.contains(&BaseElement::Device(d)));
+    }
+
+    #[test]
+    fn paste_adds_distinct_devices_and_reports_bounds() {
+        let (mut schematic, d, _, _) = schematic_with_vs();
+        let edges = schematic.nets.graph.edge_count();
+        schematic.selected.insert(BaseElement::Device(d.clone()));
+        schematic.copy_selected(SSPoint::origin());
+
+        assert!(schematic.paste(SSPoint::new(0, 20)));
+        assert_eq!(schematic.devices.get_set().len(), 2);
+        assert_eq!(schematic.nets.graph.edge_count(), edges);
+        let pasted = schematic.active_device().unwrap();
+        assert_ne!(pasted, d);
+        assert_ne!(pasted.0.borrow().ng_id(), d.0.borrow().ng_id());
+        assert_eq!(pasted.0.borrow().ports_ssp(), vec![SSPoint::new(0, 23), SSPoint::new(0, 17)]);
+        assert_eq!(schematic.take_added_bounds(), schematic.selected_bounding_box());
+        assert!(schematic.take_added_bounds().is_none());
+    }
+
+    #[test]
+    fn renumber_orders_top_left_to_bottom_right() {
+        let mut schematic = Schematic::default();
+        let mut rs = vec![];
+        for ssp in [SSPoint::new(10, 0), SSPoint::new(0, 10), SSPoint::new(0, 0)] {
+            let d = schematic.devices.new_res();
+            d.0.borrow_mut().set_position(ssp);
+            schematic.devices.insert(d.clone());
+            rs.push(d);
+        }
+        schematic.devices.delete_device(&rs[0]);
+        let d = schematic.devices.new_res();
+        d.0.borrow_mut().set_position(SSPoint::new(10, 0));
+        schematic.devices.insert(d.clone());
+        rs[0] = d;
+        assert_eq!(rs[0].0.borrow().ng_id(), "R4");
+
+        schematic.renumber();
+        assert_eq!(rs[1].0.borrow().ng_id(), "R1");
+        assert_eq!(rs[2].0.borrow().ng_id(), "R2");
+        assert_eq!(rs[0].0.borrow().ng_id(), "R3");
+    }
+
+    #[test]
+    fn netlist_to_invalid_path_returns_err() {
+        let mut schematic = Schematic::default();
+        vs_with_wires(&mut schematic);
+        assert!(schematic.netlist_to("/nonexistent_circe_dir/netlist.cir").is_err());
+    }
+
+    #[test]
+    fn measure_netlist_adds_test_source_between_selected_nets() {
+        let (mut schematic, d, pos, neg) = schematic_with_vs();
+        let edges: Vec<NetEdge> = schematic.nets.graph.all_edges().map(|e| e.2.clone()).collect();
+        for e in edges {
+            schematic.selected.insert(BaseElement::NetEdge(e));
+        }
+        let (a, b) = schematic.selected_net_pair().unwrap();
+        assert_eq!([&a, &b].into_iter().collect::<HashSet<_>>(), [&pos, &neg].into_iter().collect());
+
+        let file = TempFile::new("measure.cir");
+        schematic.measure_netlist_to(file.path(), &a, &b).unwrap();
+        let netlist = fs::read_to_string(file.path()).unwrap();
+        assert!(netlist.contains(&format!("{} {} {} dc 0 ac 1", Schematic::MEASURE_SOURCE_ID, b, a)));
+        assert!(netlist.contains(&d.0.borrow().ng_id()));
+        assert_eq!(schematic.devices.get_set().len(), 1);
+    }
+
+    #[test]
+    fn duplicate_selects_offset_copies_with_unique_ids() {
+        let (mut schematic, d, _, _) = schematic_with_vs();
+        schematic.selected.insert(BaseElement::Device(d.clone()));
+
+        assert!(schematic.duplicate_selected());
+        let dup = schematic.active_device().unwrap();
+        assert_ne!(dup, d);
+        assert_ne!(dup.0.borrow().ng_id(), d.0.borrow().ng_id());
+        assert_eq!(dup.0.borrow().position().y, 0);
+        assert!(!dup.0.borrow().interactable.bounds.intersects(&d.0.borrow().interactable.bounds));
+        assert!(schematic.clipboard.devices.is_empty());
+        assert!(schematic.take_added_bounds().is_none());
+
+        assert!(schematic.duplicate_selected());
+        let ids: HashSet<String> = schematic.devices.get_set().iter().map(|d| d.0.borrow().ng_id()).collect();
+        assert_eq!(ids.len(), 3);
+    }
+
+    #[test]
+    fn duplicate_shares_no_nets_with_original() {
+        let (mut schematic, d, pos, neg) = schematic_with_vs();
+        schematic.selected.insert(BaseElement::Device(d.clone()));
+        let edges: Vec<NetEdge> = schematic.nets.graph.all_edges().map(|(_, _, e)| e.clone()).collect();
+        for e in edges {
+            schematic.selected.insert(BaseElement::NetEdge(e));
+        }
+
+        assert!(schematic.duplicate_selected());
+        let dup = schematic.active_device().unwrap();
+        let dup_nets = [schematic.pin_net(&dup, 0).unwrap(), schematic.pin_net(&dup, 1).unwrap()];
+        let nets = [schematic.pin_net(&d, 0).unwrap(), schematic.pin_net(&d, 1).unwrap()];
+        assert_eq!(nets, [pos, neg]);
+        assert!(dup_nets.iter().all(|n| !nets.contains(n)));
+        assert_ne!(dup_nets[0], dup_nets[1]);
+    }
+
+    #[test]
+    fn undo_reverts_parameter_change() {
+        let mut schematic = Schematic::default();
+        let d = schematic.devices.new_res();
+        schematic.devices.insert(d.clone());
+        let line = schematic.netlist_string();
+        let value = d.0.borrow().class().param_summary();
+
+        schematic.set_device_params(&d, &[(String::from("value"), String::from("10k"))], "");
+        assert_eq!(d.0.borrow().class().param_summary(), "10k");
+        assert_ne!(schematic.netlist_string(), line);
+
+        assert!(schematic.undo());
+        assert_eq!(d.0.borrow().class().param_summary(), value);
+        assert_eq!(schematic.netlist_string(), line);
+
+        assert!(schematic.redo());
+        assert_eq!(d.0.borrow().class().param_summary(), "10k");
+    }
+
+    #[test]
+    fn mirrored_ports_swap_netlist_order() {
+        let (mut schematic, d, pos, neg) = schematic_with_vs();
+        assert_ne!(pos, neg);
+
+        let id = d.0.borrow().ng_id();
+        let netlist = schematic.netlist_string();
+        assert!(netlist.contains(&format!("{} {} {} ", id, pos, neg)));
+
+        flip(&mut schematic, &d, transforms::SST_YMIR);
+        assert_eq!(d.0.borrow().ports_ssp(), vec![SSPoint::new(0, -3), SSPoint::new(0, 3)]);
+        let netlist = schematic.netlist_string();
+        assert!(netlist.contains(&format!("{} {} {} ", id, neg, pos)));
+    }
+
+    #[test]
+    fn mirror_along_port_axis_keeps_netlist_order() {
+        let (mut schematic, d, pos, neg) = schematic_with_vs();
+
+        flip(&mut schematic, &d, transforms::SST_XMIR);
+        assert_eq!(d.0.borrow().ports_ssp(), vec![SSPoint::new(0, 3), SSPoint::new(0, -3)]);
+        let id = d.0.borrow().ng_id();
+        let netlist = schematic.netlist_string();
+        assert!(netlist.contains(&format!("{} {} {} ", id, pos, neg)));
+    }
+
+    #[test]
+    fn new_devices_use_class_defaults() {
+        let mut schematic = Schematic::default();
+        schematic.set_device_defaults("Resistor", vec![(String::from("value"), String::from("10k"))]);
+        let r = schematic.devices.new_res();
+        assert_eq!(r.0.borrow().class().params(), vec![(String::from("value"), String::from("10k"))]);
+        schematic.devices.insert(r);
+        schematic.renumber();
+        assert!(schematic.device_defaults().contains_key("Resistor"));
+        let v = schematic.devices.new_vs();
+        assert_ne!(v.0.borrow().class().params(), vec![(String::from("value"), String::from("10k"))]);
+    }
+
+    #[test]
+    fn locked_elements_survive_delete_and_area_selection() {
+        let (mut schematic, d, ..) = schematic_with_vs();
+        let edges = schematic.nets.graph.edge_count();
+        schematic.selected = schematic.nets.graph.all_edges().map(|e| BaseElement::NetEdge(e.2.clone())).collect();
+        schematic.selected.insert(BaseElement::Device(d.clone()));
+        assert!(schematic.toggle_lock_selected());
+        schematic.delete_selected(false);
+        assert!(schematic.devices.contains(&d));
+        assert_eq!(schematic.nets.graph.edge_count(), edges);
+
+        schematic.clear_selected();
+        schematic.tentatives_by_ssbox(&SSBox::new(SSPoint::new(-20, -20), SSPoint::new(20, 20)));
+        schematic.tentatives_to_selected();
+        assert!(schematic.selected.is_empty());
+
+        schematic.selected.insert(BaseElement::Device(d.clone()));
+        assert!(schematic.toggle_lock_selected());
+        schematic.delete_selected(false);
+        assert!(!schematic.devices.contains(&d));
+    }
+
+    #[test]
+    fn swap_ports_reverses_netlist_order_of_symmetric_devices() {
+        let mut schematic = Schematic::default();
+        let r = schematic.devices.new_res();
+        let (r, pos, neg) = device_with_wires(&mut schematic, r);
+        let id = r.0.borrow().ng_id();
+
+        schematic.selected.insert(BaseElement::Device(r.clone()));
+        assert!(schematic.swap_ports_selected());
+        assert_eq!(r.0.borrow().ports_ssp(), vec![SSPoint::new(0, -3), SSPoint::new(0, 3)]);
+        let netlist = schematic.netlist_string();
+        assert!(netlist.contains(&format!("{} {} {} ", id, neg, pos)));
+
+        let (mut schematic, v, ..) = schematic_with_vs();
+        schematic.selected.insert(BaseElement::Device(v.clone()));
+        assert!(!schematic.swap_ports_selected());
+        assert_eq!(v.0.borrow().ports_ssp(), vec![SSPoint::new(0, 3), SSPoint::new(0, -3)]);
+    }
+
+    #[test]
+    fn save_and_open_round_trip() {
+        let (mut schematic, v, ..) = schematic_with_vs();
+        let r = schematic.devices.new_res();
+        r.0.borrow_mut().set_position(SSPoint::new(10, 0));
+        r.0.borrow_mut().class_mut().set_params(&[(String::from("value"), String::from("4.7k"))]);
+        r.0.borrow_mut().set_attributes("footprint=0805");
+        schematic.devices.insert(r.clone());
+        r.0.borrow_mut().swap_ports();
+        v.0.borrow_mut().interactable.locked = true;
+        schematic.set_directives(vec![String::from(".tran 1u 1m")]);
+        schematic.set_temperature(Some(50.0));
+        schematic.set_temperature_sweep(vec![-40.0, 27.0, 85.0]);
+        assert!(schematic.toggle_probe(SSPoint::new(4, 3)));
+
+        let mut opened = round_trip(&schematic);
+
+        let mut expected = schematic.devices.records();
+        let mut actual = opened.devices.records();
+        expected.sort_by_key(|r| r.class.clone());
+        actual.sort_by_key(|r| r.class.clone());
+        assert_eq!(actual, expected);
+        assert_eq!(opened.nets.graph.edge_count(), schematic.nets.graph.edge_count());
+        assert_eq!(opened.sim_config, schematic.sim_config);
+        assert_eq!(opened.probes(), schematic.probes());
+        assert!(opened.undo());
+        assert!(opened.devices.get_set().is_empty());
+    }
+
+    #[test]
+    fn opened_file_replaces_simulation_setup() {
+        let mut schematic = Schematic::default();
+        schematic.set_directives(vec![String::from(".tran 1u 1m")]);
+        schematic.set_temperature(Some(50.0));
+        let file = TempFile::new("sim.circe");
+
+        // files saved before the setup was, or with only part of it, open with the defaults for the rest
+        fs::write(file.path(), r#"{"version": 1, "devices": [], "wires": []}"#).unwrap();
+        schematic.open(file.path()).unwrap();
+        assert_eq!(schematic.sim_config, SimConfig::default());
+        fs::write(file.path(), r#"{"version": 1, "devices": [], "wires": [], "sim": {"directives": [".op"]}}"#).unwrap();
+        schematic.open(file.path()).unwrap();
+        assert_eq!(schematic.sim_config, SimConfig { directives: vec![String::from(".op")], ..Default::default() });
+    }
+
+    #[test]
+    fn open_invalid_file_leaves_schematic_unchanged() {
+        let (mut schematic, d, ..) = schematic_with_vs();
+        assert!(schematic.open("does/not/exist.circe").is_err());
+        assert!(schematic.devices.contains(&d));
+    }
+
+    #[test]
+    fn netlist_is_identical_when_generated_twice() {
+        let mut schematic = Schematic::default();
+        vs_with_wires(&mut schematic);
+        let first = schematic.netlist_string();
+        let second = schematic.netlist_string();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn netlist_device_order_does_not_depend_on_insertion_order() {
+        let netlist = |classes: &[&str]| {
+            let mut schematic = Schematic::default();
+            for (i, class) in classes.iter().enumerate() {
+                for n in 0..11 {
+                    let d = match *class {
+                        "R" => schematic.devices.new_res(),
+                        _ => schematic.devices.new_vs(),
+                    };
+                    d.0.borrow_mut().set_position(SSPoint::new(10 * n, 10 * i as transforms::SSCoord));
+                    schematic.devices.insert(d);
+                }
+            }
+            schematic.netlist_string()
+        };
+        let netlist = (netlist(&["R", "V"]), netlist(&["V", "R"]));
+        assert_eq!(netlist.0, netlist.1);
+        let ids: Vec<&str> = netlist.0.lines().skip(1).filter_map(|l| l.split(' ').next()).collect();
+        assert_eq!(&ids[..3], &["R1", "R2", "R3"]);
+        assert_eq!(ids[10], "R11");
+        assert_eq!(ids[11], "V1");
+    }
+
+    #[test]
+    fn hovering_port_highlights_connected_net() {
+        let (mut schematic, d, pos, _) = schematic_with_vs();
+        assert_eq!(schematic.tentative_net_by_port(SSPoint::new(0, 3)), Some(pos.clone()));
+        assert!(!d.0.borrow().interactable.tentative);
+        let tentatives: Vec<NetEdge> = schematic.nets.tentatives().collect();
+        assert!(!tentatives.is_empty());
+        assert!(tentatives.iter().all(|e| e.label.as_deref() == Some(&pos)));
+        assert_eq!(schematic.tentative_net_by_port(SSPoint::new(4, 3)), None);
+    }
+
+    #[test]
+    fn geometry_and_netlist_beyond_i16_range() {
+        let mut schematic = Schematic::default();
+        let origin = SSPoint::new(1_000_000, -1_000_000);
+        let r = schematic.devices.new_res();
+        r.0.borrow_mut().set_position(origin);
+        schematic.devices.insert(r.clone());
+        assert_eq!(r.0.borrow().ports_ssp(), vec![origin + SSVec::new(0, 3), origin + SSVec::new(0, -3)]);
+
+        let mut wires = Nets::default();
+        wires.route(origin + SSVec::new(0, 3), origin + SSVec::new(0, -3));
+        schematic.nets.merge(&wires, schematic.devices.ports_ssp());
+        let netlist = schematic.netlist_string();
+        let net = schematic.nets.net_at(origin + SSVec::new(0, 3));
+        assert_eq!(schematic.nets.net_at(origin + SSVec::new(0, -3)), net);
+        assert!(netlist.contains(&format!("{} {} {} ", r.0.borrow().ng_id(), net, net)));
+
+        let vsb = schematic.bounding_box();
+        assert!(vsb.contains(VSPoint::new(1_000_000.0, -1_000_000.0)));
+    }
+
+    #[test]
+    fn net_connectivity_queries() {
+        let (mut schematic, v, pos, neg) = schematic_with_vs();
+        // resistor across the far ends of the wires
+        let r = schematic.devices.new_res();
+        r.0.borrow_mut().set_position(SSPoint::new(4, 0));
+        schematic.devices.insert(r.clone());
+        schematic.prune_nets();
+        assert_eq!(schematic.pin_net(&v, 0), Some(pos.clone()));
+        assert_eq!(schematic.pin_net(&v, 1), Some(neg.clone()));
+        assert_eq!(schematic.pin_net(&v, 2), None);
+
+        let members = schematic.net_members(&pos);
+        assert_eq!(members, vec![(r.clone(), 0), (v.clone(), 0)]);
+        assert!(schematic.net_members("no_such_net").is_empty());
+
+        let floating = schematic.devices.new_res();
+        floating.0.borrow_mut().set_position(SSPoint::new(20, 20));
+        schematic.devices.insert(floating.clone());
+        assert_eq!(schematic.pin_net(&floating, 0), None);
+    }
+
+    #[test]
+    fn wire_end_shows_whether_it_connects() {
+        let mut schematic = Schematic::default();
+        vs_with_wires(&mut schematic);
+        let connects = |schematic: &Schematic| match schematic.state {
+            SchematicState::Wiring(_, (_, connects)) => connects,
+            _ => panic!("not wiring"),
+        };
+        schematic.events_handler(key(iced::keyboard::KeyCode::W), SSPoint::new(10, 10));
+        assert!(!connects(&schematic));
+        schematic.events_handler(moved(), SSPoint::new(4, 3));
+        assert!(connects(&schematic), "wire end");
+        schematic.events_handler(click(), SSPoint::new(10, 10));
+        schematic.events_handler(moved(), SSPoint::new(2, -3));
+        assert!(connects(&schematic), "along a wire");
+        schematic.events_handler(moved(), SSPoint::new(10, 0));
+        assert!(!connects(&schematic));
+        schematic.events_handler(moved(), SSPoint::new(0, 3));
+        assert!(connects(&schematic), "device port");
+    }
+
+    #[test]
+    fn wire_lengths_accumulate_while_routing() {
+        let mut schematic = Schematic::default();
+        schematic.state = SchematicState::Wiring(None, (SSPoint::origin(), false));
+        assert_eq!(schematic.wire_lengths(), None);
+        schematic.events_handler(click(), SSPoint::new(0, 0));
+        schematic.events_handler(moved(), SSPoint::new(3, 4));
+        assert_eq!(schematic.wire_lengths(), Some((7, 7)));
+        schematic.events_handler(click(), SSPoint::new(3, 4));
+        schematic.events_handler(moved(), SSPoint::new(3, 10));
+        assert_eq!(schematic.wire_lengths(), Some((6, 13)));
+    }
+
+    #[test]
+    fn rotate_selected_in_place_keeps_selection() {
+        let (mut schematic, d, ..) = schematic_with_vs();
+        schematic.selected.insert(BaseElement::Device(d.clone()));
+        assert!(schematic.rotate_selected(transforms::SST_CCWR));
+        assert_eq!(d.0.borrow().ports_ssp(), vec![SSPoint::new(-3, 0), SSPoint::new(3, 0)]);
+        assert!(schematic.selected.contains(&BaseElement::Device(d.clone())));
+        assert!(schematic.rotate_selected(transforms::SST_CWR));
+        assert_eq!(d.0.borrow().ports_ssp(), vec![SSPoint::new(0, 3), SSPoint::new(0, -3)]);
+        assert!(schematic.undo());
+        assert_eq!(d.0.borrow().ports_ssp(), vec![SSPoint::new(-3, 0), SSPoint::new(3, 0)]);
+
+        // locked elements do not rotate and leave nothing to undo
+        d.0.borrow_mut().interactable.locked = true;
+        assert!(!schematic.rotate_selected(transforms::SST_CCWR));
+        assert!(schematic.undo());
+        assert!(!schematic.undo());
+    }
+
+    #[test]
+    fn rotate_selected_about_unlocked_elements() {
+        let (mut schematic, d, ..) = schematic_with_vs();
+        let locked = schematic.devices.new_res();
+        locked.0.borrow_mut().set_position(SSPoint::new(20, 0));
+        locked.0.borrow_mut().interactable.locked = true;
+        schematic.devices.insert(locked.clone());
+        let locked_ports = locked.0.borrow().ports_ssp();
+        schematic.selected.insert(BaseElement::Device(d.clone()));
+        schematic.selected.insert(BaseElement::Device(locked.clone()));
+
+        // the locked resistor does not pull the center away from the source
+        assert!(schematic.rotate_selected(transforms::SST_CCWR));
+        assert_eq!(d.0.borrow().ports_ssp(), vec![SSPoint::new(-3, 0), SSPoint::new(3, 0)]);
+        assert_eq!(locked.0.borrow().ports_ssp(), locked_ports);
+    }
+
+    #[test]
+    fn datasheet_url_does_not_affect_netlist() {
+        let (mut schematic, d, ..) = schematic_with_vs();
+        let before = schematic.netlist_string();
+        d.0.borrow_mut().set_attributes("datasheet_url=https://example.com/ds.pdf");
+        schematic.selected.insert(BaseElement::Device(d.clone()));
+        assert_eq!(schematic.active_datasheet_url().as_deref(), Some("https://example.com/ds.pdf"));
+
+        let after = schematic.netlist_string();
+        let electrical = |netlist: &str| netlist.lines().filter(|l| !l.starts_with('*')).map(String::from).collect::<Vec<_>>();
+        assert_eq!(electrical(&after), electrical(&before));
+    }
+
+    #[test]
+    fn attributes_are_netlist_comments_and_split_bom_lines() {
+        let mut schematic = Schematic::default();
+        let (r1, r2) = (schematic.devices.new_res(), schematic.devices.new_res());
+        r2.0.borrow_mut().set_position(SSPoint::new(10, 0));
+        schematic.devices.insert(r1.clone());
+        schematic.devices.insert(r2.clone());
+        r1.0.borrow_mut().set_attributes("footprint=0805 note=\"hand solder\"");
+
+        // the device line is left alone, the attributes follow it as a comment
+        let netlist = schematic.netlist_string();
+        let id = r1.0.borrow().ng_id();
+        let lines: Vec<&str> = netlist.lines().collect();
+        let i = lines.iter().position(|l| l.starts_with(&format!("{} ", id))).unwrap();
+        assert!(!lines[i].contains("footprint"), "{}", netlist);
+        assert_eq!(lines[i + 1], format!("* {} footprint=0805 note=\"hand solder\"", id));
+
+        let bom = schematic.bom();
+        assert_eq!(bom.len(), 2);
+        assert!(bom.iter().any(|l| l.ids == [id.clone()] && l.attributes.get("note").map(String::as_str) == Some("hand solder")));
+        r2.0.borrow_mut().set_attributes(&r1.0.borrow().attributes_summary());
+        assert_eq!(schematic.bom().len(), 1);
+        assert_eq!(schematic.bom()[0].count, 2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn kicad_netlist_lists_parts_and_their_nets() {
+        let (mut schematic, _v, pos, neg) = schematic_with_vs();
+        let r = schematic.devices.new_res();
+        r.0.borrow_mut().set_position(SSPoint::new(4, 0));
+        r.0.borrow_mut().set_attributes("footprint=R_0603");
+        schematic.devices.insert(r.clone());
+        schematic.prune_nets();
+
+        let (components, nets) = schematic.kicad_netlist();
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].reference, r.0.borrow().ng_id());
+        assert_eq!(components[0].footprint.as_deref(), Some("R_0603"));
+        let names: Vec<&str> = nets.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, {let mut v = vec![pos.as_str(), neg.as_str()]; v.sort(); v});
+        let sexpr = kicad::to_sexpr(&components, &nets);
+        assert!(sexpr.starts_with("(export"));
+        assert!(sexpr.contains(&format!("(node (ref \"{}\") (pin \"1\"))", r.0.borrow().ng_id())));
+    }
+
+    #[test]
+    fn floating_nets_warn_or_block_simulation() {
+        let (mut schematic, _v, pos, neg) = schematic_with_vs();
+        let mut expected = vec![(pos, SSPoint::new(0, 3)), (neg, SSPoint::new(0, -3))];
+        expected.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(schematic.floating_nets(), expected);
+
+        schematic.check_floating_nets();
+        assert!(schematic.warning().unwrap().contains("(0, 3)"));
+        schematic.set_block_floating_nets(true);
+        schematic.check_floating_nets();
+        assert_eq!(schematic.warning(), None);
+        assert!(schematic.error().unwrap().contains("(0, 3)"));
+
+        // close the loop with a resistor
+        let r = schematic.devices.new_res();
+        r.0.borrow_mut().set_position(SSPoint::new(4, 0));
+        schematic.devices.insert(r);
+        schematic.prune_nets();
+        assert!(schematic.floating_nets().is_empty());
+        schematic.check_floating_nets();
+        assert_eq!(schematic.warning(), None);
+    }
+
+    #[test]
+    fn applied_parameters_are_remembered_most_recent_first() {
+        let mut schematic = Schematic::default();
+        let r = schematic.devices.new_res();
+        schematic.devices.insert(r.clone());
+        let raw = |v: &str| vec![(String::from("value"), String::from(v))];
+        for v in ["1k", "10k", "1k"] {
+            schematic.set_device_params(&r, &raw(v), "");
+        }
+        assert_eq!(schematic.recent_params().get("Resistor"), Some(&vec![raw("1k"), raw("10k")]));
+        assert_eq!(schematic.recent_params().get("Ground"), None);
+    }
+
+    #[test]
+    fn escape_during_fresh_placement_discards_device() {
+        let mut schematic = Schematic::default();
+        schematic.events_handler(key(iced::keyboard::KeyCode::R), SSPoint::origin());
+        schematic.events_handler(key(iced::keyboard::KeyCode::Escape), SSPoint::origin());
+        assert!(matches!(schematic.state, SchematicState::Idle));
+        assert!(schematic.selected.is_empty());
+        // moving again must not place the discarded device
+        schematic.events_handler(key(iced::keyboard::KeyCode::M), SSPoint::origin());
+        schematic.events_handler(click(), SSPoint::origin());
+        schematic.events_handler(click(), SSPoint::new(2, 2));
+        assert!(schematic.devices.get_set().is_empty());
+
+        // cancelling a move of placed devices keeps them selected and in place
+        let (d, ..) = vs_with_wires(&mut schematic);
+        schematic.selected.insert(BaseElement::Device(d.clone()));
+        schematic.events_handler(key(iced::keyboard::KeyCode::M), SSPoint::origin());
+        schematic.events_handler(key(iced::keyboard::KeyCode::Escape), SSPoint::origin());
+        assert!(schematic.selected.contains(&BaseElement::Device(d.clone())));
+        assert!(schematic.devices.contains(&d));
+        assert_eq!(d.0.borrow().position(), SSPoint::origin());
+    }
+
+    #[test]
+    fn highlight_net_by_name() {
+        let (mut schematic, _d, pos, neg) = schematic_with_vs();
+        assert!(schematic.highlight_net(&pos));
+        let tentatives: Vec<NetEdge> = schematic.nets.tentatives().collect();
+        assert!(!tentatives.is_empty());
+        assert!(tentatives.iter().all(|e| e.label.as_deref().map(|l| l.as_str()) == Some(pos.as_str())));
+        assert!(schematic.highlight_net(&neg));
+        assert!(schematic.nets.tentatives().all(|e| e.label.as_deref().map(|l| l.as_str()) == Some(neg.as_str())));
+        assert!(!schematic.highlight_net("no_such_net"));
+        assert_eq!(schematic.nets.tentatives().count(), 0);
+    }
+
+    #[test]
+    fn directives_are_appended_to_netlist_and_validated() {
+        let mut schematic = Schematic::default();
+        vs_with_wires(&mut schematic);
+        schematic.set_directives(vec![String::from(" .tran 1u 1m "), String::new(), String::from(".four 1k v(net_0)")]);
+        assert!(schematic.validate_directives().is_ok());
+        let netlist = schematic.netlist_string();
+        assert!(netlist.ends_with(".tran 1u 1m\n.four 1k v(net_0)\n\n"));
+
+        schematic.set_directives(vec![String::from(".control"), String::from("run"), String::from(".endc")]);
+        assert!(schematic.validate_directives().is_ok());
+        schematic.set_directives(vec![String::from(".control"), String::from("run")]);
+        assert!(schematic.validate_directives().is_err());
+        schematic.set_directives(vec![String::from("tran 1u 1m")]);
+        assert!(schematic.validate_directives().is_err());
+        schematic.set_directives(vec![String::from("  ")]);
+        assert!(!schematic.has_directives());
+    }
+
+    #[test]
+    fn wiring_snaps_to_nearby_port() {
+        let mut schematic = Schematic::default();
+        let d = schematic.devices.new_vs();
+        d.0.borrow_mut().set_position(SSPoint::origin());
+        schematic.devices.insert(d);
+
+        schematic.events_handler(moved(), SSPoint::new(1, 4));
+        assert_eq!(schematic.snap_port, Some(SSPoint::new(0, 3)));
+        schematic.events_handler(moved(), SSPoint::new(2, 4));
+        assert_eq!(schematic.snap_port, None);
+
+        schematic.state = SchematicState::Wiring(None, (SSPoint::origin(), false));
+        schematic.events_handler(click(), SSPoint::new(1, 4));
+        schematic.events_handler(moved(), SSPoint::new(6, 3));
+        schematic.events_handler(click(), SSPoint::new(6, 3));
+        assert!(schematic.nets.graph.contains_edge(NetVertex(SSPoint::new(0, 3)), NetVertex(SSPoint::new(6, 3))));
+    }
+
+    #[test]
+    fn unhandled_events_do_not_redraw_active_layer() {
+        let mut schematic = Schematic::default();
+        assert!(!schematic.events_handler(key(iced::keyboard::KeyCode::Q), SSPoint::origin()).2);
+        assert!(schematic.events_handler(moved(), SSPoint::origin()).2);
+        assert!(schematic.events_handler(key(iced::keyboard::KeyCode::W), SSPoint::origin()).2);
+        assert!(matches!(schematic.state, SchematicState::Wiring(..)));
+    }
+
+    #[test]
+    fn wire_started_at_port_follows_device() {
+        let mut schematic = Schematic::default();
+        let r = schematic.devices.new_res();
+        wire_from_port(&mut schematic, &r);
+
+        schematic.selected.insert(BaseElement::Device(r.clone()));
+        schematic.move_selected(SchematicState::move_transform(&SSPoint::origin(), &SSPoint::new(0, 2), &SSTransform::identity()));
+        schematic.prune_nets();
+        assert_eq!(r.0.borrow().ports_ssp()[0], SSPoint::new(0, 5));
+        assert!(!schematic.nets.graph.contains_node(NetVertex(SSPoint::new(0, 3))));
+        // the wire runs from its far end to the moved port
+        assert!(schematic.pin_net(&r, 0).is_some());
+        assert_eq!(schematic.pin_net(&r, 0), schematic.nets.label_at_vertex(SSPoint::new(6, 3)).map(|l| l.to_string()));
+    }
+
+    #[test]
+    fn anchors_are_undone_and_saved_with_the_wires() {
+        let mut schematic = Schematic::default();
+        let r = schematic.devices.new_res();
+        wire_from_port(&mut schematic, &r);
+        assert!(schematic.nets.is_anchored(SSPoint::new(0, 3)));
+        assert!(round_trip(&schematic).nets.is_anchored(SSPoint::new(0, 3)));
+
+        // the anchored wire end follows the rotated port
+        schematic.selected.insert(BaseElement::Device(r.clone()));
+        assert!(schematic.rotate_selected(transforms::SST_CCWR));
+        let port = r.0.borrow().ports_ssp()[0];
+        assert!(schematic.nets.is_anchored(port));
+        assert!(!schematic.nets.graph.contains_node(NetVertex(SSPoint::new(0, 3))));
+        assert_eq!(schematic.pin_net(&r, 0), schematic.nets.label_at_vertex(SSPoint::new(6, 3)).map(|l| l.to_string()));
+
+        assert!(schematic.undo());
+        assert!(schematic.nets.is_anchored(SSPoint::new(0, 3)));
+        assert!(!schematic.nets.is_anchored(port));
+    }
+
+    #[test]
+    fn reconnected_port_is_not_anchored_by_deleted_wire() {
+        let mut schematic = Schematic::default();
+        let r = schematic.devices.new_res();
+        wire_from_port(&mut schematic, &r);
+        let edges: Vec<NetEdge> = schematic.nets.graph.all_edges().map(|(_, _, e)| e.clone()).collect();
+        for e in edges {
+            schematic.selected.insert(BaseElement::NetEdge(e));
+        }
+        schematic.delete_selected(false);
+        assert!(!schematic.nets.is_anchored(SSPoint::new(0, 3)));
+
+        // a wire ending at the port, not started there, stays put when the device is rotated
+        schematic.state = SchematicState::Wiring(None, (SSPoint::origin(), false));
+        schematic.events_handler(click(), SSPoint::new(6, 3));
+        schematic.events_handler(moved(), SSPoint::new(0, 3));
+        schematic.events_handler(click(), SSPoint::new(0, 3));
+        schematic.state = SchematicState::Idle;
+        assert!(!schematic.nets.is_anchored(SSPoint::new(0, 3)));
+        schematic.selected.insert(BaseElement::Device(r.clone()));
+        assert!(schematic.rotate_selected(transforms::SST_CCWR));
+        assert!(schematic.nets.graph.contains_node(NetVertex(SSPoint::new(0, 3))));
+        assert!(schematic.pin_net(&r, 0).is_none());
+    }
+
+    #[test]
+    fn moving_wired_resistor_keeps_net_connected() {
+        let mut schematic = Schematic::default();
+        let r = schematic.devices.new_res();
+        let (r, pos, neg) = device_with_wires(&mut schematic, r);
+        assert_ne!(pos, neg);
+        schematic.selected.insert(BaseElement::Device(r.clone()));
+        schematic.move_selected(SchematicState::move_transform(&SSPoint::origin(), &SSPoint::new(-3, 1), &SSTransform::identity()));
+        schematic.prune_nets();
+
+        let far_pos = schematic.nets.label_at_vertex(SSPoint::new(4, 3)).map(|l| l.to_string());
+        let far_neg = schematic.nets.label_at_vertex(SSPoint::new(4, -3)).map(|l| l.to_string());
+        assert!(far_pos.is_some() && far_neg.is_some());
+        assert_eq!(schematic.pin_net(&r, 0), far_pos);
+        assert_eq!(schematic.pin_net(&r, 1), far_neg);
+        assert_ne!(far_pos, far_neg);
+    }
+
+    #[test]
+    fn potentiometer_netlists_two_resistors_sharing_wiper() {
+        let mut schematic = Schematic::default();
+        let pot = schematic.devices.new_pot();
+        pot.0.borrow_mut().class_mut().set_params(&[
+            (String::from("resistance"), String::from("10k")), 
+            (String::from("position"), String::from("0.25")),
+        ]);
+        let (pot, pos, neg) = device_with_wires(&mut schematic, pot);
+        let mut wiper = Nets::default();
+        wiper.route(SSPoint::new(3, 0), SSPoint::new(6, 0));
+        schematic.nets.merge(&wiper, schematic.devices.ports_ssp());
+        let w = schematic.nets.net_at(SSPoint::new(6, 0));
+
+        let netlist = schematic.netlist_string();
+        let id = pot.0.borrow().ng_id();
+        assert!(netlist.contains(&format!("{id}a {pos} {w} 7.5k\n")), "{}", netlist);
+        assert!(netlist.contains(&format!("{id}b {w} {neg} 2.5k\n")), "{}", netlist);
+        // legs are rounded to what f32 resolves, not netlisted as e.g. 3000.0002
+        pot.0.borrow_mut().class_mut().set_params(&[(String::from("position"), String::from("0.3"))]);
+        let netlist = schematic.netlist_string();
+        assert!(netlist.contains(&format!("{id}b {w} {neg} 3k\n")), "{}", netlist);
+
+        // a wiper at either end leaves a small resistance rather than a zero ohm resistor
+        pot.0.borrow_mut().class_mut().set_params(&[(String::from("position"), String::from("0"))]);
+        let netlist = schematic.netlist_string();
+        assert!(netlist.contains(&format!("{id}a {pos} {w} 10k\n")), "{}", netlist);
+        assert!(netlist.contains(&format!("{id}b {w} {neg} 1m\n")), "{}", netlist);
+        pot.0.borrow_mut().class_mut().set_params(&[(String::from("resistance"), String::from("{2*R}"))]);
+        let netlist = schematic.netlist_string();
+        assert!(netlist.contains(&format!("{id}b {w} {neg} {{max((2*R)*0, 0.001)}}\n")), "{}", netlist);
+    }
+
+    #[test]
+    fn selection_box_includes_touching_elements_only() {
+        let mut schematic = Schematic::default();
+        let r = schematic.devices.new_res();
+        r.0.borrow_mut().set_position(SSPoint::origin());
+        schematic.devices.insert(r.clone());
+        // resistor bounds span x from -2 to 2
+        schematic.tentatives_by_ssbox(&SSBox::new(SSPoint::new(3, 0), SSPoint::new(6, 1)));
+        assert_eq!(schematic.devices.tentatives().count(), 0);
+        schematic.tentatives_by_ssbox(&SSBox::new(SSPoint::new(6, 1), SSPoint::new(2, 0)));
+        assert_eq!(schematic.devices.tentatives().count(), 1);
+    }
+
+    #[test]
+    fn click_without_drag_selects_element_under_cursor() {
+        let mut schematic = Schematic::default();
+        let r = schematic.devices.new_res();
+        r.0.borrow_mut().set_position(SSPoint::origin());
+        schematic.devices.insert(r.clone());
+        schematic.clear_tentatives();
+
+        let ssp = SSPoint::new(1, 1);
+        schematic.events_handler(click(), ssp);
+        schematic.events_handler(release(), ssp);
+        assert!(schematic.selected.contains(&BaseElement::Device(r)));
+        assert_eq!(schematic.selected.len(), 1);
+        assert!(matches!(schematic.state, SchematicState::Idle));
+    }
+
+    #[test]
+    fn drag_returning_to_start_selects_by_box() {
+        let mut schematic = Schematic::default();
+        let r = schematic.devices.new_res();
+        r.0.borrow_mut().set_position(SSPoint::origin());
+        schematic.devices.insert(r);
+        // a wire overlapping the resistor: a click selects one of them, a box both
+        let mut wire = Nets::default();
+        wire.route(SSPoint::new(1, -1), SSPoint::new(1, 2));
+        schematic.nets.merge(&wire, schematic.devices.ports_ssp());
+
+        let ssp = SSPoint::new(1, 1);
+        schematic.events_handler(click(), ssp);
+        schematic.events_handler(moved(), SSPoint::new(8, 8));
+        schematic.events_handler(moved(), ssp);
+        schematic.events_handler(release(), ssp);
+        assert_eq!(schematic.selected.len(), 2);
+
+        schematic.selected.clear();
+        schematic.events_handler(click(), ssp);
+        schematic.events_handler(release(), ssp);
+        assert_eq!(schematic.selected.len(), 1);
+    }
+
+    #[test]
+    fn temperature_is_netlisted() {
+        let mut schematic = Schematic::default();
+        vs_with_wires(&mut schematic);
+        schematic.set_directives(vec![String::from(".tran 1u 1m")]);
+        assert!(!schematic.netlist_string().contains(".temp"));
+        schematic.set_temperature(Some(50.0));
+        assert!(schematic.netlist_string().ends_with(".temp 50\n.tran 1u 1m\n\n"));
+        assert!(schematic.netlist_string_at(Some(-40.0), false).contains(".temp -40\n"));
+    }
+
+    #[test]
+    fn invalid_device_params_block_simulation() {
+        let mut schematic = Schematic::default();
+        let r = schematic.devices.new_res();
+        let (r, ..) = device_with_wires(&mut schematic, r);
+        assert!(schematic.validate().is_ok());
+        r.0.borrow_mut().class_mut().set_params(&[(String::from("value"), String::from(""))]);
+        let e = schematic.validate().unwrap_err();
+        assert!(e.contains(&r.0.borrow().ng_id()), "{}", e);
+    }
+
+    #[test]
+    fn placed_device_follows_cursor_until_clicked() {
+        let mut schematic = Schematic::default();
+        assert!(!schematic.place_device("Transistor", SSPoint::origin()));
+        assert!(schematic.place_device("Potentiometer", SSPoint::origin()));
+        assert!(schematic.devices.get_set().is_empty());
+        schematic.events_handler(moved(), SSPoint::new(5, 5));
+        schematic.events_handler(click(), SSPoint::new(5, 5));
+        assert!(matches!(schematic.state, SchematicState::Idle));
+        let placed: Vec<RcRDevice> = schematic.devices.get_set().iter().cloned().collect();
+        assert_eq!(placed.len(), 1);
+        assert_eq!(placed[0].0.borrow().class().name(), "Potentiometer");
+        assert!(placed[0].0.borrow().ports_ssp().contains(&SSPoint::new(5, 8)));
+    }
+
+    #[test]
+    fn placement_rotates_about_the_crosshair() {
+        let mut schematic = Schematic::default();
+        assert!(schematic.place_device("Resistor", SSPoint::origin()));
+        schematic.events_handler(moved(), SSPoint::new(5, 5));
+        schematic.events_handler(key(iced::keyboard::KeyCode::R), SSPoint::new(5, 5));
+        // the crosshair is drawn at the cursor, which the rotated device stays centered on
+        assert!(matches!(schematic.state, SchematicState::Moving(Some((_, ssp1, _))) if ssp1 == SSPoint::new(5, 5)));
+        schematic.events_handler(click(), SSPoint::new(5, 5));
+        let placed: Vec<RcRDevice> = schematic.devices.get_set().iter().cloned().collect();
+        assert_eq!(placed[0].0.borrow().position(), SSPoint::new(5, 5));
+        let mut ports = placed[0].0.borrow().ports_ssp();
+        ports.sort_by_key(|p| p.x);
+        assert_eq!(ports, vec![SSPoint::new(2, 5), SSPoint::new(8, 5)]);
+    }
+
+    #[test]
+    fn find_net_by_name() {
+        let (mut schematic, _d, pos, _neg) = schematic_with_vs();
+        let vsb = schematic.find_net(&format!(" {} ", pos)).unwrap();
+        assert_eq!(vsb, VSBox::new(VSPoint::new(0., 3.), VSPoint::new(4., 3.)));
+        assert_eq!(schematic.found_net, Some(pos.clone()));
+        assert!(schematic.nets.tentatives().all(|e| e.label.as_deref() == Some(&pos)));
+
+        assert!(schematic.find_net("no_such_net").is_err());
+        assert_eq!(schematic.found_net, None);
+
+        schematic.find_net(&pos).unwrap();
+        schematic.events_handler(key(iced::keyboard::KeyCode::Escape), SSPoint::origin());
+        assert_eq!(schematic.found_net, None);
+    }
+
+    #[test]
+    fn probes_show_latest_voltage_of_their_net() {
+        let (mut schematic, _d, pos, neg) = schematic_with_vs();
+        assert!(!schematic.toggle_probe(SSPoint::new(20, 20)));
+        assert!(schematic.toggle_probe(SSPoint::new(2, 3)));
+        assert!(schematic.toggle_probe(SSPoint::new(4, -3)));
+        assert_eq!(schematic.probes().iter().map(|p| p.net.clone()).collect::<Vec<_>>(), vec![pos.clone(), neg.clone()]);
+        assert_eq!(schematic.probes()[0].value, None);
+
+        schematic.op_voltages = OpVoltages::from([(pos.clone(), 3.0), (neg, 0.0)]);
+        schematic.refresh_probes();
+        assert_eq!(schematic.probes().iter().map(|p| p.value).collect::<Vec<_>>(), vec![Some(3.0), Some(0.0)]);
+
+        // toggling at a probe removes it
+        assert!(schematic.toggle_probe(SSPoint::new(2, 3)));
+        assert_eq!(schematic.probes().len(), 1);
+    }
+
+    #[test]
+    fn hovered_device_lists_all_parameters() {
+        let mut schematic = Schematic::default();
+        let d = schematic.devices.new_pot();
+        let (d, ..) = device_with_wires(&mut schematic, d);
+        schematic.events_handler(moved(), SSPoint::new(-1, 1));
+        let hovered = schematic.hovered_device().unwrap();
+        assert!(hovered == d);
+        let lines = hovered.0.borrow().tooltip_lines();
+        assert_eq!(lines[0], format!("{} {}", d.0.borrow().ng_id(), d.0.borrow().class().name()));
+        assert_eq!(lines[1], d.0.borrow().class().param_summary());
+        assert!(lines.iter().any(|l| l.starts_with("resistance = ")));
+        assert!(lines.iter().any(|l| l.starts_with("position = ")));
+
+        schematic.events_handler(moved(), SSPoint::new(20, 20));
+        assert!(schematic.hovered_device().is_none());
+    }
+
+    #[test]
+    fn only_probed_nets_are_saved() {
+        let (mut schematic, _d, pos, _neg) = schematic_with_vs();
+        schematic.toggle_probe(SSPoint::new(2, 3));
+        // dc op annotations need every net
+        assert!(!schematic.netlist_string().contains(".save"));
+
+        schematic.set_directives(vec![String::from(".tran 1u 1m")]);
+        let netlist = schematic.netlist_string();
+        assert!(netlist.contains(&format!(".save v({})\n.tran 1u 1m\n", pos)));
+        assert!(!schematic.netlist_string_at(None, false).contains(".save"));
+
+        schematic.toggle_probe(SSPoint::new(2, 3));
+        assert!(!schematic.netlist_string().contains(".save"));
+    }
+
+    #[test]
+    fn zero_ohm_resistors_block_simulation_unless_jumpers() {
+        let mut schematic = Schematic::default();
+        let r = schematic.devices.new_res();
+        let (r, ..) = device_with_wires(&mut schematic, r);
+        r.0.borrow_mut().class_mut().set_params(&[(String::from("value"), String::from("0"))]);
+        let e = schematic.validate().unwrap_err();
+        assert!(e.contains(&format!("{} at (0, 0)", r.0.borrow().ng_id())), "{}", e);
+        schematic.set_zero_ohm_jumpers(true);
+        assert!(schematic.validate().is_ok());
+    }
+
+    #[test]
+    fn dragging_a_wire_bends_it() {
+        let mut schematic = Schematic::default();
+        let mut wires = Nets::default();
+        wires.route(SSPoint::new(0, 0), SSPoint::new(10, 0));
+        schematic.nets.merge(&wires, vec![]);
+        schematic.events_handler(key(iced::keyboard::KeyCode::H), SSPoint::new(5, 0));
+        schematic.events_handler(click(), SSPoint::new(5, 0));
+        schematic.events_handler(moved(), SSPoint::new(5, 3));
+        schematic.events_handler(release(), SSPoint::new(5, 3));
+        assert!(matches!(schematic.state, SchematicState::Bending(None)));
+        for (src, dst) in [((0, 0), (0, 3)), ((0, 3), (5, 3)), ((5, 3), (5, 0)), ((5, 0), (10, 0))] {
+            assert!(schematic.nets.graph.contains_edge(NetVertex(SSPoint::new(src.0, src.1)), NetVertex(SSPoint::new(dst.0, dst.1))), "{:?}", (src, dst));
+        }
+        assert_eq!(schematic.nets.graph.edge_count(), 4);
+        // the whole bend is a single net
+        assert_eq!(schematic.nets.label_at(SSPoint::new(0, 0)), schematic.nets.label_at(SSPoint::new(10, 0)));
+
+        assert!(schematic.undo());
+        assert_eq!(schematic.nets.graph.edge_count(), 1);
+    }
+
+    #[test]
+    fn mosfets_sharing_a_model_share_its_card() {
+        let mut schematic = Schematic::default();
+        for (i, name) in ["NMOS", "NMOS 3 terminal", "PMOS"].iter().enumerate() {
+            let m = schematic.devices.new_named(name).unwrap();
+            m.0.borrow_mut().set_position(SSPoint::new(10 * i as i32, 0));
+            schematic.devices.insert(m);
+        }
+        let netlist = schematic.netlist_string();
+        assert_eq!(netlist.matches(".model nmos1 nmos").count(), 1, "{}", netlist);
+        assert_eq!(netlist.matches(".model pmos1 pmos").count(), 1, "{}", netlist);
+        assert_eq!(netlist.lines().filter(|l| l.starts_with('M')).count(), 3, "{}", netlist);
+    }
+
+    #[test]
+    fn models_without_definition_are_reported() {
+        let mut schematic = Schematic::default();
+        for (i, model) in ["ext", "EXT", "nmos1"].iter().enumerate() {
+            let m = schematic.devices.new_named("NMOS").unwrap();
+            m.0.borrow_mut().set_position(SSPoint::new(10 * i as i32, 0));
+            let params = if *model == "nmos1" {vec![]} else {vec![(String::from("model_params"), String::new())]};
+            m.0.borrow_mut().class_mut().set_params(&params);
+            m.0.borrow_mut().class_mut().set_params(&[(String::from("model"), model.to_string())]);
+            schematic.devices.insert(m);
+        }
+        assert!(schematic.netlist_string().contains(".model nmos1 nmos"));
+        let missing = schematic.missing_models();
+        assert_eq!(missing.len(), 2);
+        assert!(missing.iter().all(|(_, ids)| ids.len() == 1));
+        assert!(schematic.validate().unwrap_err().starts_with("undefined models"));
+        schematic.set_directives(vec![String::from(".MODEL Ext nmos (level=1)")]);
+        assert!(schematic.missing_models().is_empty());
+        assert!(schematic.validate().is_ok());
+    }
+
+    #[test]
+    fn conflicting_model_parameters_are_reported() {
+        let mut schematic = Schematic::default();
+        let mut ms = vec![];
+        for (i, (model, model_params)) in [("mine", "level=1 vto=0.7"), ("MINE", "level=1 vto=0.7"), ("other", "level=1")].iter().enumerate() {
+            let m = schematic.devices.new_named("NMOS").unwrap();
+            m.0.borrow_mut().set_position(SSPoint::new(10 * i as i32, 0));
+            m.0.borrow_mut().class_mut().set_params(&[
+                (String::from("model"), model.to_string()), 
+                (String::from("model_params"), model_params.to_string()),
+            ]);
+            schematic.devices.insert(m.clone());
+            ms.push(m);
+        }
+        assert!(schematic.conflicting_models().is_empty());
+        assert!(schematic.validate().is_ok());
+        assert_eq!(schematic.netlist_string().to_ascii_lowercase().matches(".model mine nmos").count(), 1);
+
+        ms[1].0.borrow_mut().class_mut().set_params(&[(String::from("model_params"), String::from("level=1 vto=0.5"))]);
+        let ids: Vec<String> = ms[..2].iter().map(|m| m.0.borrow().ng_id()).collect();
+        assert_eq!(schematic.conflicting_models(), vec![(String::from("mine"), ids)]);
+        assert!(schematic.validate().unwrap_err().starts_with("conflicting model parameters: mine"));
+        ms[1].0.borrow_mut().set_disabled(true);
+        assert!(schematic.validate().is_ok());
+    }
+
+    #[test]
+    fn shift_delete_removes_stubs_of_deleted_devices() {
+        let (mut schematic, d, ..) = schematic_with_vs();
+        let mut wires = Nets::default();
+        wires.route(SSPoint::new(4, -3), SSPoint::new(4, -10));
+        wires.route(SSPoint::new(4, -3), SSPoint::new(8, -3));
+        schematic.nets.merge(&wires, schematic.devices.ports_ssp());
+        schematic.selected.insert(BaseElement::Device(d));
+        let del = |modifiers| Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::Delete, modifiers});
+        schematic.events_handler(del(iced::keyboard::Modifiers::SHIFT), SSPoint::origin());
+        // the stubs up to the junction are gone, the wires beyond it remain
+        assert_eq!(schematic.devices.get_set().len(), 0);
+        assert!(!schematic.nets.occupies_ssp(SSPoint::new(2, 3)));
+        assert!(!schematic.nets.occupies_ssp(SSPoint::new(2, -3)));
+        assert!(schematic.nets.occupies_ssp(SSPoint::new(4, -6)) && schematic.nets.occupies_ssp(SSPoint::new(6, -3)));
+
+        assert!(schematic.undo());
+        let edges = schematic.nets.graph.edge_count();
+        schematic.selected = schematic.devices.get_set().iter().map(|d| BaseElement::Device(d.clone())).collect();
+        schematic.events_handler(del(iced::keyboard::Modifiers::empty()), SSPoint::origin());
+        assert_eq!(schematic.nets.graph.edge_count(), edges);
+    }
+
+    #[test]
+    fn replace_values_changes_matching_devices_in_one_step() {
+        let mut schematic = Schematic::default();
+        let value = |v: &str| [(String::from("value"), v.to_string())];
+        let rs: Vec<RcRDevice> = ["10k", "10k", "4.7k"].iter().enumerate().map(|(i, v)| {
+            let r = schematic.devices.new_res();
+            r.0.borrow_mut().set_position(SSPoint::new(i as i32 * 10, 0));
+            r.0.borrow_mut().class_mut().set_params(&value(v));
+            schematic.devices.insert(r.clone());
+            r
+        }).collect();
+        assert_eq!(schematic.replace_values("Voltage Source", "value", "10k", "12k"), Ok(0));
+        assert_eq!(schematic.replace_values("Resistor", "value", " 10k ", "12k"), Ok(2));
+        let summaries = |rs: &[RcRDevice]| rs.iter().map(|r| r.0.borrow().class().param_summary()).collect::<Vec<_>>();
+        assert_eq!(summaries(&rs), vec!["12k", "12k", "4.7k"]);
+        assert_eq!(schematic.recent_params()["Resistor"][0], value("12k").to_vec());
+        assert!(schematic.undo());
+        let restored: Vec<String> = schematic.devices.get_set().iter().map(|r| r.0.borrow().class().param_summary()).collect();
+        assert_eq!(restored.iter().filter(|v| *v == "10k").count(), 2);
+
+        // numbers match however they are written, invalid values change nothing, unchanged devices are not counted
+        assert_eq!(schematic.replace_values("Resistor", "value", "10000", "10K"), Ok(0));
+        assert!(schematic.replace_values("Resistor", "value", "10K", "ten").is_err());
+        assert_eq!(schematic.replace_values("Resistor", "value", "10000", "12k"), Ok(2));
+    }
+
+    #[test]
+    fn replace_values_matches_the_picked_parameter() {
+        let mut schematic = Schematic::default();
+        let pot = schematic.devices.new_named("Potentiometer").unwrap();
+        pot.0.borrow_mut().class_mut().set_params(&[(String::from("resistance"), String::from("10k"))]);
+        schematic.devices.insert(pot.clone());
+        let v = schematic.devices.new_vs();
+        v.0.borrow_mut().class_mut().set_params(&[
+            (String::from("value"), String::from("5")), 
+            (String::from("ac_magnitude"), String::from("1")),
+        ]);
+        schematic.devices.insert(v.clone());
+
+        assert_eq!(schematic.replace_values("Potentiometer", "resistance", "10k", "22k"), Ok(1));
+        assert_eq!(pot.0.borrow().class().params()[0].1, "22k");
+        assert_eq!(schematic.replace_values("Voltage Source", "value", "5", "3.3"), Ok(1));
+        assert_eq!(schematic.replace_values("Voltage Source", "ac_magnitude", "1", "2"), Ok(1));
+        assert_eq!(v.0.borrow().class().params()[..2], [
+            (String::from("value"), String::from("3.3")), 
+            (String::from("ac_magnitude"), String::from("2")),
+        ]);
+    }
+
+    #[test]
+    fn placement_waits_for_prompted_value() {
+        let mut schematic = Schematic::default();
+        schematic.set_prompt_on_place(true);
+        schematic.events_handler(key(iced::keyboard::KeyCode::R), SSPoint::origin());
+        assert!(matches!(schematic.state, SchematicState::Idle));
+        let (class, name, _) = schematic.placement_prompt().unwrap();
+        assert_eq!((class, name.as_str()), ("Resistor", "value"));
+        assert!(schematic.submit_placement_value(" 4.7k ", SSPoint::new(2, 2)));
+        assert!(matches!(schematic.state, SchematicState::Moving(_)));
+        assert_eq!(schematic.active_device().unwrap().0.borrow().class().param_summary(), "4.7k");
+        assert!(schematic.placement_prompt().is_none());
+        schematic.events_handler(key(iced::keyboard::KeyCode::Escape), SSPoint::origin());
+
+        // devices without parameters are placed right away
+        schematic.events_handler(key(iced::keyboard::KeyCode::G), SSPoint::origin());
+        assert!(matches!(schematic.state, SchematicState::Moving(_)));
+    }
+
+    #[test]
+    fn disabled_devices_are_commented_out() {
+        let mut schematic = Schematic::default();
+        vs_with_wires(&mut schematic);
+        let r = schematic.devices.new_res();
+        r.0.borrow_mut().set_position(SSPoint::new(4, 0));
+        schematic.devices.insert(r.clone());
+        schematic.prune_nets();
+        schematic.selected.insert(BaseElement::Device(r.clone()));
+        schematic.events_handler(key(iced::keyboard::KeyCode::D), SSPoint::origin());
+        assert!(r.0.borrow().disabled());
+        let netlist = schematic.netlist_string();
+        assert!(netlist.lines().any(|l| l.starts_with("* R1 ")), "{}", netlist);
+        assert!(netlist.lines().any(|l| l.starts_with("V1 ")), "{}", netlist);
+        // the pins of the disabled resistor do not count, only the source is connected to the wires
+        assert_eq!(schematic.floating_nets().len(), 2);
+        // the flag is saved and undone like any other edit
+        assert!(r.0.borrow().record().disabled);
+        assert!(schematic.undo());
+        assert!(schematic.devices.get_set().iter().all(|d| !d.0.borrow().disabled()));
+    }
+
+    #[test]
+    fn net_classes_style_their_nets() {
+        let (mut schematic, _, pos, _) = schematic_with_vs();
+        schematic.set_net_styles(crate::config::NetClasses::default().0);
+        assert!(!schematic.assign_net_class_selected(Some(String::from("power"))));
+
+        let e = schematic.nets.graph.all_edges().find(|e| e.2.label.as_deref() == Some(&pos)).unwrap().2.clone();
+        schematic.selected.insert(BaseElement::NetEdge(e));
+        assert!(schematic.assign_net_class_selected(Some(String::from("power"))));
+        assert_eq!(schematic.selected_net_class(), Some(String::from("power")));
+        let styles = schematic.net_class_styles();
+        assert_eq!(styles.keys().collect::<Vec<_>>(), vec![&pos]);
+        assert_eq!(styles[&pos].width, 2.0);
+
+        // reassigning replaces the class instead of adding to it
+        assert!(schematic.assign_net_class_selected(Some(String::from("signal"))));
+        assert_eq!(schematic.net_classes.by_net(&schematic.nets).len(), 1);
+        assert!(schematic.assign_net_class_selected(None));
+        assert_eq!(schematic.selected_net_class(), None);
+        assert!(schematic.net_class_styles().is_empty());
+
+        // assignments are undone, and dropped with their net
+        assert!(schematic.undo());
+        assert_eq!(schematic.selected_net_class(), Some(String::from("signal")));
+        let wires: Vec<BaseElement> = schematic.nets.graph.all_edges().filter(|e| e.2.label.as_deref() == Some(&pos))
+            .map(|e| BaseElement::NetEdge(e.2.clone())).collect();
+        schematic.selected = wires.into_iter().collect();
+        schematic.delete_selected(false);
+        assert!(schematic.net_classes.by_net(&schematic.nets).is_empty());
+        assert_eq!(schematic.net_classes, NetAssignments::default());
+    }
+
+    #[test]
+    fn net_classes_of_joined_nets_keep_the_last_assignment() {
+        let (mut schematic, _, pos, neg) = schematic_with_vs();
+        assert!(schematic.net_classes.set(&schematic.nets, SSPoint::new(4, 3), Some(String::from("power"))));
+        assert!(schematic.net_classes.set(&schematic.nets, SSPoint::new(4, -3), Some(String::from("signal"))));
+        assert!(!schematic.net_classes.set(&schematic.nets, SSPoint::new(20, 20), Some(String::from("signal"))));
+        assert_eq!(schematic.net_classes.by_net(&schematic.nets).len(), 2);
+        assert!(schematic.net_classes.get(&schematic.nets, SSPoint::new(2, 3)).is_some_and(|c| c == "power"));
+        assert_ne!(pos, neg);
+
+        let mut wire = Nets::default();
+        wire.route(SSPoint::new(4, 3), SSPoint::new(4, -3));
+        schematic.nets.merge(&wire, schematic.devices.ports_ssp());
+        schematic.prune_nets();
+        assert_eq!(schematic.net_classes.by_net(&schematic.nets).into_values().collect::<Vec<_>>(), vec![String::from("signal")]);
+    }
+
+    /// returns the end points and label of each arrow
+    fn arrow_geometry(arrows: &[ArrowAnnotation]) -> Vec<(VSPoint, VSPoint, &str)> {
+        arrows.iter().map(|a| (a.from, a.to, a.label.as_str())).collect()
+    }
+
+    #[test]
+    fn arrows_are_placed_moved_and_saved() {
+        let mut schematic = Schematic::default();
+        schematic.set_arrow_label(" I1 ");
+        schematic.events_handler(key(iced::keyboard::KeyCode::A), SSPoint::origin());
+        schematic.events_handler(click(), SSPoint::new(0, 0));
+        schematic.events_handler(click(), SSPoint::new(0, 4));
+        assert_eq!(arrow_geometry(&schematic.arrows), vec![(VSPoint::new(0.0, 0.0), VSPoint::new(0.0, 4.0), "I1")]);
+        schematic.events_handler(key(iced::keyboard::KeyCode::Escape), SSPoint::origin());
+        assert!(schematic.netlist_string().lines().all(|l| !l.contains("I1")));
+
+        schematic.select_at(SSPoint::new(0, 2));
+        schematic.set_arrow_label("V_out");
+        assert!(schematic.relabel_selected_arrows());
+        schematic.move_selected(SSTransform::translation(2, 0));
+        assert_eq!(arrow_geometry(&schematic.arrows), vec![(VSPoint::new(2.0, 0.0), VSPoint::new(2.0, 4.0), "V_out")]);
+
+        let mut opened = round_trip(&schematic);
+        assert_eq!(arrow_geometry(&opened.arrows), arrow_geometry(&schematic.arrows));
+
+        opened.select_at(SSPoint::new(2, 1));
+        opened.delete_selected(false);
+        assert!(opened.arrows.is_empty());
+        assert!(opened.undo());
+        assert_eq!(opened.arrows.len(), 1);
+    }
+
+    #[test]
+    fn events_follow_remapped_keys() {
+        let mut schematic = Schematic::default();
+        schematic.set_key_bindings(serde_json::from_str(r#"{"wire": ["Q"]}"#).unwrap());
+        schematic.events_handler(key(iced::keyboard::KeyCode::W), SSPoint::origin());
+        assert!(matches!(schematic.state, SchematicState::Idle));
+        schematic.events_handler(key(iced::keyboard::KeyCode::Q), SSPoint::origin());
+        assert!(matches!(schematic.state, SchematicState::Wiring(..)));
+    }
+
+    #[test]
+    fn identical_arrows_are_distinct() {
+        let mut schematic = Schematic::default();
+        schematic.events_handler(key(iced::keyboard::KeyCode::A), SSPoint::origin());
+        schematic.events_handler(click(), SSPoint::new(0, 0));
+        schematic.events_handler(click(), SSPoint::new(0, 4));
+        schematic.events_handler(key(iced::keyboard::KeyCode::Escape), SSPoint::origin());
+
+        schematic.select_at(SSPoint::new(0, 2));
+        assert!(schematic.duplicate_selected());
+        let offset = (schematic.arrows[0].from - schematic.arrows[1].from).round().cast::<SSCoord>();
+        schematic.move_selected(SSTransform::translation(offset.x, offset.y));
+        assert_ne!(schematic.arrows[0], schematic.arrows[1]);
+        assert_eq!(arrow_geometry(&schematic.arrows[..1]), arrow_geometry(&schematic.arrows[1..]));
+
+        // only the duplicate is deleted
+        let duplicate = schematic.arrows[1].clone();
+        schematic.selected.insert(BaseElement::Arrow(duplicate));
+        schematic.delete_selected(false);
+        assert_eq!(schematic.arrows.len(), 1);
+        assert_eq!(schematic.arrows[0].id, 1);
+    }
+
+    #[test]
+    fn arrows_are_placed_off_grid() {
+        let mut schematic = Schematic::default();
+        schematic.events_handler(key(iced::keyboard::KeyCode::A), SSPoint::origin());
+        assert!(!schematic.snap_required());
+        schematic.set_curpos_placement(VSPoint::new(0.5, 0.25));
+        schematic.events_handler(click(), SSPoint::new(1, 0));
+        schematic.set_curpos_placement(VSPoint::new(3.5, 0.25));
+        schematic.events_handler(click(), SSPoint::new(4, 0));
+        assert_eq!(arrow_geometry(&schematic.arrows), vec![(VSPoint::new(0.5, 0.25), VSPoint::new(3.5, 0.25), "")]);
+        assert!(!BaseElement::Arrow(schematic.arrows[0].clone()).is_electrical());
+
+        // the placement position applies to one event only
+        schematic.events_handler(click(), SSPoint::new(0, 2));
+        schematic.events_handler(click(), SSPoint::new(0, 4));
+        assert_eq!(schematic.arrows[1].to, VSPoint::new(0.0, 4.0));
+    }
+
+    #[test]
+    fn repeated_clicks_cycle_overlapping_elements() {
+        let (mut schematic, d, ..) = schematic_with_vs();
+        // the wire ends on the device port, both are under the cursor there
+        let port = SSPoint::new(0, 3);
+        let click_port = |schematic: &mut Schematic| {
+            schematic.events_handler(click(), port);
+            schematic.events_handler(release(), port);
+            schematic.selected.iter().cloned().collect::<Vec<_>>()
+        };
+
+        let first = click_port(&mut schematic);
+        assert_eq!(first.len(), 1);
+        assert_eq!(click_port(&mut schematic), first, "cycling is off by default");
+
+        schematic.set_click_cycles(true);
+        schematic.clear_selected();
+        schematic.last_click = None;
+        let first = click_port(&mut schematic);
+        let second = click_port(&mut schematic);
+        assert_eq!(second.len(), 1);
+        assert_ne!(first, second);
+        assert!([&first[0], &second[0]].contains(&&BaseElement::Device(d)));
+        assert_eq!(click_port(&mut schematic), first, "cycling wraps around");
+    }
+
+    #[test]
+    fn initial_conditions_emitted_for_transient_analysis() {
+        let (mut schematic, _, pos, _) = schematic_with_vs();
+        assert!(!schematic.set_initial_condition_selected(Some(2.5)));
+
+        let e = schematic.nets.graph.all_edges().find(|e| e.2.label.as_deref() == Some(&pos)).unwrap().2.clone();
+        schematic.selected.insert(BaseElement::NetEdge(e));
+        assert!(schematic.set_initial_condition_selected(Some(2.5)));
+        assert_eq!(schematic.selected_initial_condition(), Some(2.5));
+        // only transient analyses take initial conditions
+        assert!(!schematic.netlist_string().contains(".ic"));
+        schematic.set_directives(vec![String::from(".tran 1u 1m")]);
+        assert!(schematic.netlist_string().ends_with(&format!(".ic V({})=2.5\n.tran 1u 1m\n\n", pos)));
+
+        let opened = round_trip(&schematic);
+        assert_eq!(opened.initial_conditions, schematic.initial_conditions);
+
+        assert!(schematic.set_initial_condition_selected(None));
+        assert!(!schematic.netlist_string().contains(".ic"));
+        assert!(schematic.undo());
+        assert_eq!(schematic.selected_initial_condition(), Some(2.5));
+
+        // transient analyses run from a .control block take initial conditions as well
+        schematic.set_directives(vec![String::from(".control"), String::from("tran 1u 1m"), String::from(".endc")]);
+        assert!(schematic.netlist_string().contains(&format!(".ic V({})=2.5\n", pos)));
+        schematic.set_directives(vec![String::from(".control"), String::from(".endc"), String::from("tran 1u 1m")]);
+        assert!(!schematic.runs_transient());
+    }
+
+    #[test]
+    fn initial_conditions_skip_ground_and_follow_their_net() {
+        let (mut schematic, _, pos, neg) = schematic_with_vs();
+        let gnd = schematic.devices.new_gnd();
+        // the ground port is 2 above its position, at the end of the wire of the negative net
+        gnd.0.borrow_mut().set_position(SSPoint::new(4, -5));
+        schematic.devices.insert(gnd);
+        schematic.prune_nets();
+        schematic.set_directives(vec![String::from(".tran 1u 1m")]);
+        assert!(schematic.initial_conditions.set(&schematic.nets, SSPoint::new(4, 3), Some(1.0)));
+        assert!(schematic.initial_conditions.set(&schematic.nets, SSPoint::new(4, -3), Some(2.0)));
+        let netlist = schematic.netlist_string();
+        assert!(netlist.contains(&format!(".ic V({})=1\n", pos)), "{}", netlist);
+        assert!(!netlist.contains(&format!("V({})", neg)), "{}", netlist);
+
+        // conditions are dropped with their net
+        let wires: Vec<BaseElement> = schematic.nets.graph.all_edges().filter(|e| e.2.label.as_deref() == Some(&pos))
+            .map(|e| BaseElement::NetEdge(e.2.clone())).collect();
+        schematic.selected = wires.into_iter().collect();
+        schematic.delete_selected(false);
+        assert_eq!(schematic.initial_conditions.by_net(&schematic.nets).len(), 1);
+    }
+
+    #[test]
+    fn marching_ants_advance_a_dash_every_quarter_second() {
+        assert_eq!(ants_offset(0.0, 4.0), 0);
+        assert_eq!(ants_offset(0.125, 4.0), 2);
+        assert_eq!(ants_offset(0.25, 4.0), 4);
+        assert_eq!(ants_offset(0.5, 4.0), 0);
+        assert_eq!(ants_offset(10.25, 8.0), 8);
+    }
+
+    #[test]
+    fn ngspice_errors_select_the_device_of_their_line() {
+        assert_eq!(error_line("Error on line 3 :"), Some(3));
+        assert_eq!(error_line("command line options, Error on line 12 or its substitute:"), Some(12));
+        assert_eq!(error_line("Error: no such vector"), None);
+
+        let (mut schematic, vs, _, _) = schematic_with_vs();
+        let r = schematic.devices.new_res();
+        r.0.borrow_mut().set_position(SSPoint::new(10, 0));
+        schematic.devices.insert(r.clone());
+        let netlist = schematic.netlist_string();
+        let line = netlist.lines().position(|l| l.starts_with(&r.0.borrow().ng_id())).unwrap() + 1;
+
+        let errors = vec![String::from("Error on line 99 :"), String::from("unrelated")];
+        assert!(schematic.locate_ngspice_error(&errors).is_none());
+        let errors = vec![String::from("some warning"), format!("Error on line {} :", line)];
+        assert!(schematic.locate_ngspice_error(&errors).is_some());
+        assert!(schematic.selected.contains(&BaseElement::Device(r)));
+        assert!(!schematic.selected.contains(&BaseElement::Device(vs)));
+    }
+
+    #[test]
+    fn grid_origin_is_set_and_saved() {
+        let mut schematic = Schematic::default();
+        let shift_g = Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::G, modifiers: iced::keyboard::Modifiers::SHIFT});
+        let (_, clear_passive, _) = schematic.events_handler(shift_g, SSPoint::new(3, -5));
+        assert!(clear_passive);
+        assert_eq!(schematic.grid_origin(), SSPoint::new(3, -5));
+        assert!(matches!(schematic.state, SchematicState::Idle));
+        assert!(schematic.undo());
+        assert_eq!(schematic.grid_origin(), SSPoint::origin());
+        assert!(schematic.redo());
+
+        let opened = round_trip(&schematic);
+        assert_eq!(opened.grid_origin(), SSPoint::new(3, -5));
+    }
+
+    #[test]
+    fn saving_over_another_file_waits_for_confirmation() {
+        let file = TempFile::new("overwrite.circe");
+        let path = file.path();
+        std::fs::write(path, "not a schematic").unwrap();
+        let mut schematic = Schematic::default();
+        vs_with_wires(&mut schematic);
+        schematic.set_confirm_overwrite(true);
+        assert_eq!(schematic.save_as(path).unwrap(), SaveStatus::NeedsConfirmation);
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "not a schematic");
+        assert_eq!(schematic.overwrite_prompt(), Some(path));
+        schematic.cancel_overwrite();
+        assert!(schematic.overwrite_prompt().is_none());
+        assert_eq!(schematic.file_path(), Schematic::SCHEMATIC_PATH);
+
+        assert_eq!(schematic.save_as(path).unwrap(), SaveStatus::NeedsConfirmation);
+        schematic.confirm_overwrite().unwrap();
+        assert_eq!(schematic.file_path(), path);
+        // the file saved last is saved over without asking
+        assert_eq!(schematic.save_as(path).unwrap(), SaveStatus::Saved { overwrote: false });
+        let mut opened = Schematic::default();
+        opened.open(path).unwrap();
+        assert_eq!(opened.devices.get_set().len(), 1);
+    }
+
+    #[test]
+    fn writing_netlist_over_existing_file_waits_for_confirmation() {
+        let file = TempFile::new("netlist.cir");
+        let path = file.path();
+        std::fs::write(path, "* hand written netlist").unwrap();
+        let mut schematic = Schematic::default();
+        vs_with_wires(&mut schematic);
+        schematic.set_confirm_overwrite(true);
+        assert_eq!(schematic.write_file(FileKind::Netlist, path).unwrap(), SaveStatus::NeedsConfirmation);
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "* hand written netlist");
+        assert_eq!(schematic.overwrite_prompt(), Some(path));
+
+        schematic.confirm_overwrite().unwrap();
+        assert!(std::fs::read_to_string(path).unwrap().contains("V1"));
+        // the netlist is not the file ctrl+s saves to
+        assert_eq!(schematic.file_path(), Schematic::SCHEMATIC_PATH);
+        // the file exported last is written over without asking
+        assert_eq!(schematic.write_file(FileKind::Netlist, path).unwrap(), SaveStatus::Saved { overwrote: false });
+    }
+
+    #[test]
+    fn saving_keeps_warnings_it_has_nothing_to_add_to() {
+        let file = TempFile::new("keeps_warning.circe");
+        let path = file.path();
+        let mut schematic = Schematic::default();
+        vs_with_wires(&mut schematic);
+        schematic.file_path = Some(path.to_string());
+        schematic.warning = Some(String::from("Warning: Floating nets: net_0"));
+        schematic.save_file();
+        assert_eq!(schematic.warning().as_deref(), Some("Warning: Floating nets: net_0"));
+        assert!(schematic.error().is_none());
+    }
+
+    #[test]
+    fn view_is_saved_and_handed_back_on_open() {
+        let mut schematic = Schematic::default();
+        vs_with_wires(&mut schematic);
+        let view = ViewRecord { center: VSPoint::new(4.5, -2.0), scale: 25.0 };
+        schematic.set_view(view);
+        let mut opened = round_trip(&schematic);
+        assert_eq!(opened.take_opened_view(), Some(view));
+        assert_eq!(opened.take_opened_view(), None);
+        assert!(opened.take_opened());
+        assert!(!opened.take_opened());
+    }
+
+    #[test]
+    fn diagonal_rotation_keeps_ports_on_grid() {
+        let (mut schematic, vs, ..) = schematic_with_vs();
+        let gnd = schematic.devices.new_gnd();
+        gnd.0.borrow_mut().set_position(SSPoint::new(10, 0));
+        schematic.devices.insert(gnd.clone());
+        schematic.selected.insert(BaseElement::Device(gnd.clone()));
+        assert!(!schematic.rotate_diagonal_selected());
+
+        schematic.selected.insert(BaseElement::Device(vs.clone()));
+        let ctrl_r = Event::Keyboard(iced::keyboard::Event::KeyPressed{key_code: iced::keyboard::KeyCode::R, modifiers: iced::keyboard::Modifiers::CTRL});
+        let (_, clear_passive, _) = schematic.events_handler(ctrl_r, SSPoint::origin());
+        assert!(clear_passive);
+        assert!(vs.0.borrow().diagonal());
+        assert!(!gnd.0.borrow().diagonal());
+        assert_eq!(vs.0.borrow().ports_ssp(), vec![SSPoint::new(-2, 2), SSPoint::new(2, -2)]);
+        assert!(vs.0.borrow().interactable.bounds.contains_inclusive(SSPoint::new(-2, 2)));
+
+        // a second turn completes a 90 deg rotation
+        assert!(schematic.rotate_diagonal_selected());
+        assert!(!vs.0.borrow().diagonal());
+        assert_eq!(vs.0.borrow().ports_ssp(), vec![SSPoint::new(-3, 0), SSPoint::new(3, 0)]);
+
+        assert!(schematic.rotate_diagonal_selected());
+        let opened = round_trip(&schematic);
+        let mut ports: Vec<SSPoint> = opened.devices.get_set().iter().flat_map(|d| d.0.borrow().ports_ssp()).collect();
+        let mut expected: Vec<SSPoint> = schematic.devices.get_set().iter().flat_map(|d| d.0.borrow().ports_ssp()).collect();
+        ports.sort_by_key(|p| (p.x, p.y));
+        expected.sort_by_key(|p| (p.x, p.y));
+        assert_eq!(ports, expected);
+        assert!(schematic.undo());
+        assert!(!vs.0.borrow().diagonal());
+    }
+
+    #[test]
+    fn connection_counts_count_pins_per_net() {
+        let (mut schematic, _, pos, neg) = schematic_with_vs();
+        schematic.prune_nets();
+        let counts = schematic.connection_counts();
+        assert_eq!(counts[&pos], (SSPoint::new(0, 3), 1));
+        assert_eq!(counts[&neg], (SSPoint::new(0, -3), 1));
+
+        // a resistor from the end of the positive wire down to the negative wire
+        let r = schematic.devices.new_res();
+        r.0.borrow_mut().set_position(SSPoint::new(4, 0));
+        schematic.devices.insert(r.clone());
+        schematic.prune_nets();
+        let counts = schematic.connection_counts();
+        assert_eq!(counts.values().map(|c| c.1).collect::<Vec<_>>(), vec![2, 2]);
+
+        r.0.borrow_mut().set_disabled(true);
+        assert_eq!(schematic.connection_counts().values().map(|c| c.1).collect::<Vec<_>>(), vec![1, 1]);
+    }
+
+    #[test]
+    fn power_rails_join_their_nets_by_name() {
+        let mut schematic = Schematic::default();
+        let mut wires = Nets::default();
+        for x in [0, 20] {
+            let r = schematic.devices.new_res();
+            r.0.borrow_mut().set_position(SSPoint::new(x, 0));
+            schematic.devices.insert(r);
+            let rail = schematic.devices.new_named("Power Rail").unwrap();
+            rail.0.borrow_mut().set_position(SSPoint::new(x + 4, 5));
+            schematic.devices.insert(rail);
+            wires.route(SSPoint::new(x, 3), SSPoint::new(x + 4, 3));
+        }
+        wires.route(SSPoint::new(0, -3), SSPoint::new(4, -3));
+        schematic.nets.merge(&wires, schematic.devices.ports_ssp());
+        schematic.prune_nets();
+        assert_eq!(schematic.nets.label_at(SSPoint::new(2, 3)).as_deref().map(|l| l.as_str()), Some("VCC"));
+
+        let netlist = schematic.netlist_string();
+        let lines: Vec<&str> = netlist.lines().filter(|l| l.starts_with('R')).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|l| l.split_whitespace().nth(1) == Some("VCC")));
+        assert!(!netlist.contains("PWR"));
+
+        for d in schematic.devices.get_set().iter().filter(|d| d.0.borrow().class().rail_name().is_some()) {
+            d.0.borrow_mut().class_mut().set_params(&[(String::from("name"), String::from("net_0"))]);
+        }
+        // generated names skip rail names, the wire at the negative port of the first resistor stays a net of its own
+        let netlist = schematic.netlist_string();
+        assert_eq!(netlist.lines().filter(|l| l.split_whitespace().any(|n| n == "net_0")).count(), 2);
+        assert!(netlist.lines().all(|l| l.split_whitespace().filter(|n| *n == "net_0").count() < 2));
+    }
+
+    #[test]
+    fn power_rails_name_the_pins_they_sit_on() {
+        let mut schematic = Schematic::default();
+        let r = schematic.devices.new_res();
+        schematic.devices.insert(r);
+        let rail = schematic.devices.new_named("Power Rail").unwrap();
+        rail.0.borrow_mut().set_position(SSPoint::new(0, 5));
+        schematic.devices.insert(rail);
+        schematic.prune_nets();
+        assert!(schematic.nets.graph.all_edges().next().is_none());
+
+        let netlist = schematic.netlist_string();
+        let r = netlist.lines().find(|l| l.starts_with('R')).unwrap();
+        assert_eq!(r.split_whitespace().nth(1), Some("VCC"));
+        assert!(r.split_whitespace().nth(2).unwrap().starts_with("fn_"));
+    }
+
+    #[test]
+    fn events_report_selection_changes() {
+        let mut schematic = Schematic::default();
+        vs_with_wires(&mut schematic);
+        schematic.events_handler(moved(), SSPoint::new(4, 3));
+        assert!(!schematic.take_selection_changed());
+        schematic.events_handler(click(), SSPoint::new(4, 3));
+        schematic.events_handler(release(), SSPoint::new(4, 3));
+        assert!(schematic.take_selection_changed());
+        assert!(!schematic.take_selection_changed());
+        schematic.events_handler(moved(), SSPoint::new(20, 20));
+        assert!(!schematic.take_selection_changed());
+    }
+}
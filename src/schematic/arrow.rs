@@ -0,0 +1,139 @@
+//! arrow annotations
+//! directional arrows labeled with a voltage or current name, for documentation only. They are not part of the netlist.
+
+use crate::transforms::{self, SSBox, SSPoint, SSTransform, VCTransform, VSBox, VSPoint, VSVec, Point, ViewportSpace};
+use iced::{widget::canvas::{Frame, path::Builder, Stroke, Text, self}, Color, alignment::{Horizontal, Vertical}};
+
+use super::interactable::{Interactable, Interactive, VisualState};
+
+/// an arrow pointing from one point to another, labeled at its middle. 
+/// Arrows are not electrical, their end points may lie off the grid.
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct ArrowAnnotation {
+    /// tail of the arrow
+    pub from: VSPoint,
+    /// head of the arrow
+    pub to: VSPoint,
+    /// name of the voltage or current the arrow stands for, e.g. V1 or I_load
+    pub label: String,
+    /// under the cursor or selection box, not yet selected
+    #[serde(skip)]
+    pub tentative: bool,
+    /// identity of the arrow within the schematic, assigned when added to it
+    #[serde(skip)]
+    pub id: usize,
+}
+
+/// two arrows are equal if they are the same arrow, wherever it was moved and however it was labeled
+impl PartialEq for ArrowAnnotation {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for ArrowAnnotation {}
+
+/// hash based on the identity
+impl std::hash::Hash for ArrowAnnotation {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl Interactive for ArrowAnnotation {
+    fn transform(&mut self, sst: SSTransform) {
+        let vvt = transforms::sst_to_xxt::<ViewportSpace>(sst);
+        self.from = vvt.transform_point(self.from);
+        self.to = vvt.transform_point(self.to);
+    }
+}
+
+impl ArrowAnnotation {
+    /// color of arrows drawn normally
+    const COLOR: Color = Color::from_rgb(0.8, 0.5, 1.0);
+    /// distance from the shaft within which the arrow is under the cursor
+    const PICK_DISTANCE: f32 = 0.5;
+
+    /// creates a new arrow, without identity until added to a schematic
+    pub fn new(from: VSPoint, to: VSPoint, label: String) -> Self {
+        ArrowAnnotation { from, to, label, tentative: false, id: 0 }
+    }
+    /// returns the grid cells covering the end points
+    pub fn bounds(&self) -> SSBox {
+        VSBox::from_points([self.from, self.to]).round_out().cast().cast_unit()
+    }
+    /// returns true if ssp lies on or next to the shaft
+    pub fn contains_ssp(&self, ssp: SSPoint) -> bool {
+        let (a, b, p): (VSPoint, VSPoint, VSPoint) = (self.from, self.to, ssp.cast().cast_unit());
+        let ab = b - a;
+        let t = if ab.square_length() > 0.0 {((p - a).dot(ab) / ab.square_length()).clamp(0.0, 1.0)} else {0.0};
+        (a + ab * t - p).length() <= ArrowAnnotation::PICK_DISTANCE
+    }
+    /// returns true if the bounds of the arrow intersect ssb, see `Interactable::intersects_ssb`
+    pub fn intersects_ssb(&self, ssb: &SSBox) -> bool {
+        Interactable { bounds: self.bounds(), ..Interactable::new() }.intersects_ssb(ssb)
+    }
+    /// returns the visual state of the arrow, given whether it is selected. None if it is drawn normally.
+    pub fn visual_state(&self, selected: bool) -> Option<VisualState> {
+        Interactable { tentative: self.tentative, ..Interactable::new() }.visual_state(selected)
+    }
+    /// draw the arrow in its normal color
+    pub fn draw_persistent(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
+        self.draw_with(vct, vcscale, frame, ArrowAnnotation::COLOR);
+    }
+    /// draw the arrow highlighted as selected
+    pub fn draw_selected(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
+        self.draw_with(vct, vcscale, frame, VisualState::Selected.color());
+    }
+    /// draw the arrow highlighted as tentative, also used while it is placed or moved
+    pub fn draw_preview(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
+        self.draw_with(vct, vcscale, frame, VisualState::Tentative.color());
+    }
+    /// draw the shaft, a head at `to` and the label beside the middle of the shaft in color
+    fn draw_with(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame, color: Color) {
+        let (from, to) = (self.from, self.to);
+        let dir = (to - from).try_normalize().unwrap_or(VSVec::new(1.0, 0.0));
+        let normal = VSVec::new(-dir.y, dir.x);
+        let mut path_builder = Builder::new();
+        path_builder.move_to(Point::from(vct.transform_point(from)).into());
+        path_builder.line_to(Point::from(vct.transform_point(to)).into());
+        for side in [-0.3, 0.3] {
+            path_builder.move_to(Point::from(vct.transform_point(to - dir * 0.6 + normal * side)).into());
+            path_builder.line_to(Point::from(vct.transform_point(to)).into());
+        }
+        let stroke = Stroke {
+            width: (0.05 * vcscale).max(0.05 * 2.0),
+            style: canvas::stroke::Style::Solid(color),
+            line_cap: canvas::LineCap::Round,
+            ..Stroke::default()
+        };
+        frame.stroke(&path_builder.build(), stroke);
+        if !self.label.is_empty() {
+            let mid = from.lerp(to, 0.5) + normal * 0.5;
+            frame.fill_text(Text {
+                content: self.label.clone(),
+                position: Point::from(vct.transform_point(mid)).into(),
+                color,
+                size: vcscale,
+                horizontal_alignment: Horizontal::Center,
+                vertical_alignment: Vertical::Center,
+                ..Default::default()
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picked_along_the_shaft_only() {
+        let arrow = ArrowAnnotation::new(VSPoint::new(0.0, 0.0), VSPoint::new(4.0, 4.0), String::from("V1"));
+        assert!(arrow.contains_ssp(SSPoint::new(2, 2)));
+        assert!(arrow.contains_ssp(SSPoint::new(4, 4)));
+        assert!(!arrow.contains_ssp(SSPoint::new(4, 0)));
+        assert!(!arrow.contains_ssp(SSPoint::new(5, 5)));
+        assert!(arrow.intersects_ssb(&SSBox::new(SSPoint::new(3, 3), SSPoint::new(6, 6))));
+    }
+}
@@ -0,0 +1,77 @@
+//! bill of materials
+//! devices of identical type, value and attributes are grouped into a single line
+
+use std::{cmp::Ordering, fs, io, collections::{BTreeMap, BTreeSet}};
+use super::devices::designator_key;
+
+/// a single line in the bill of materials
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BomLine {
+    /// reference designators of every device in this line, e.g. R1 R2
+    pub ids: Vec<String>,
+    /// device type, e.g. Resistor
+    pub class: String,
+    /// device value as it appears in the netlist
+    pub value: String,
+    /// number of devices in this line
+    pub count: usize,
+    /// non-electrical attributes shared by every device in this line, e.g. footprint
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// orders reference designators like `Devices::sorted`: by prefix, then by numeric suffix, so that R2 comes before R10
+pub fn cmp_designators(a: &str, b: &str) -> Ordering {
+    designator_key(a).cmp(&designator_key(b)).then_with(|| a.cmp(b))
+}
+
+/// quote a csv field if it contains characters which would otherwise break the row
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// returns the bill of materials as csv text. Every attribute key in use gets its own column.
+pub fn to_csv(bom: &[BomLine]) -> String {
+    let keys: BTreeSet<&String> = bom.iter().flat_map(|l| l.attributes.keys()).collect();
+    let mut csv = String::from("Reference,Type,Value,Count");
+    for k in &keys {
+        csv.push(',');
+        csv.push_str(&csv_field(k));
+    }
+    csv.push('\n');
+    for l in bom {
+        csv.push_str(&format!(
+            "{},{},{},{}",
+            csv_field(&l.ids.join(" ")),
+            csv_field(&l.class),
+            csv_field(&l.value),
+            l.count,
+        ));
+        for k in &keys {
+            csv.push(',');
+            csv.push_str(&csv_field(l.attributes.get(*k).map(|v| v.as_str()).unwrap_or_default()));
+        }
+        csv.push('\n');
+    }
+    csv
+}
+
+/// write the bill of materials as csv to path
+pub fn write_csv(bom: &[BomLine], path: &str) -> io::Result<()> {
+    fs::write(path, to_csv(bom).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn designators_sort_by_number() {
+        let mut ids = vec!["R10", "R2", "C1", "R1", "Rx"];
+        ids.sort_by(|a, b| cmp_designators(a, b));
+        assert_eq!(ids, vec!["C1", "R1", "R2", "R10", "Rx"]);
+    }
+}
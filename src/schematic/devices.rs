@@ -1,27 +1,68 @@
 //! devices, e.g. resistors, voltage sources, etc.
 
-use std::{rc::Rc, cell::RefCell, hash::Hasher, collections::HashSet};
+use std::{rc::Rc, cell::RefCell, hash::Hasher, collections::{HashSet, BTreeMap}};
 
 mod params;
 mod devicetype;
 mod deviceinstance;
 
-use super::{SchematicSet, BaseElement};
-use devicetype::{DeviceClass, r::R, gnd::Gnd, v::V};
+use super::{SchematicSet, BaseElement, file::DeviceRecord};
+use devicetype::{DeviceClass, r::R, gnd::Gnd, v::V, pot::Pot};
 use deviceinstance::Device;
+pub use params::{parse_spice_value, format_significant, same_value};
 use crate::{
+    config::NumberFormat,
     schematic::Drawable,
     transforms::{
-        SSPoint, VSBox, VCTransform, SSBox
+        SSPoint, VSBox, VCTransform, SSBox, VSPoint
     }, 
 };
 
-use iced::widget::canvas::Frame;
+use iced::{widget::canvas::Frame, Color};
 use by_address::ByAddress;
 
+/// names of the device classes which can be placed, e.g. listed in a palette
+pub fn class_names() -> &'static [&'static str] {
+    &DeviceClass::NAMES
+}
+
+/// names of the parameters of the device class named class_name, the value asked for on placement first
+pub fn param_names(class_name: &str) -> Vec<String> {
+    DeviceClass::from_name(class_name).map_or(vec![], |class| class.params().into_iter().map(|(name, _)| name).collect())
+}
+
+/// splits a reference designator into its prefix and numeric suffix, ordering R2 before R10
+pub fn designator_key(id: &str) -> (String, Option<u64>) {
+    let prefix = id.trim_end_matches(|c: char| c.is_ascii_digit());
+    (prefix.to_string(), id[prefix.len()..].parse().ok())
+}
+
+/// draw the symbol of the device class named class_name fitted into frame, e.g. as a palette icon
+pub fn draw_symbol_icon(class_name: &str, frame: &mut Frame) {
+    let Some(class) = DeviceClass::from_name(class_name) else {return};
+    let graphics = class.graphics();
+    let b = graphics.bounds();
+    let vsb = VSBox::from_points([b.min, b.max].map(|p| p.cast().cast_unit()));
+    let scale = 0.8 * (frame.width() / vsb.width()).min(frame.height() / vsb.height());
+    let center: VSPoint = vsb.center();
+    // schematic space y points up, canvas space y points down
+    let vct = VCTransform::translation(-center.x, -center.y)
+    .then_scale(scale, -scale)
+    .then_translate(euclid::Vector2D::new(frame.width() / 2.0, frame.height() / 2.0));
+    graphics.draw_persistent(vct, scale, frame);
+}
+
 #[derive(Debug, Clone)]
 pub struct RcRDevice (pub Rc<RefCell<Device>>);
 
+impl RcRDevice {
+    /// returns a new, distinct device with the same class, parameters and transform.
+    /// The new device is not part of the schematic until inserted.
+    pub fn deep_clone(&self) -> RcRDevice {
+        RcRDevice(Rc::new(RefCell::new(self.0.borrow().duplicate())))
+    }
+}
+
 impl PartialEq for RcRDevice {
     fn eq(&self, other: &Self) -> bool {
         ByAddress(self.0.clone()) == ByAddress(other.0.clone())
@@ -47,6 +88,10 @@ impl ClassManager {
         self.wm += 1;
         self.wm
     }
+    /// ensure ordinals returned in the future are greater than wm
+    pub fn reserve(&mut self, wm: usize) {
+        self.wm = self.wm.max(wm);
+    }
 }
 
 #[derive(Debug)]
@@ -54,6 +99,13 @@ struct DevicesManager {
     gnd: ClassManager,
     r: ClassManager,
     v: ClassManager,
+    pot: ClassManager,
+    m: ClassManager,
+    rail: ClassManager,
+    /// default parameters of newly created devices, keyed by device class name
+    defaults: BTreeMap<String, Vec<(String, String)>>,
+    /// recently applied parameters, most recent first, keyed by device class name
+    recent: BTreeMap<String, Vec<Vec<(String, String)>>>,
 }
 
 impl Default for DevicesManager {
@@ -62,20 +114,124 @@ impl Default for DevicesManager {
             gnd: ClassManager::new(), 
             r: ClassManager::new(), 
             v: ClassManager::new(), 
+            pot: ClassManager::new(), 
+            m: ClassManager::new(), 
+            rail: ClassManager::new(), 
+            defaults: BTreeMap::new(),
+            recent: BTreeMap::new(),
         }
     }
 }
 
-#[derive(Debug, Default)]
+impl DevicesManager {
+    /// number of recently applied parameters kept per device class
+    const RECENT_LIMIT: usize = 8;
+
+    /// returns a new ordinal for a device of class
+    pub fn incr(&mut self, class: &DeviceClass) -> usize {
+        match class {
+            DeviceClass::Gnd(_) => self.gnd.incr(),
+            DeviceClass::R(_) => self.r.incr(),
+            DeviceClass::V(_) => self.v.incr(),
+            DeviceClass::Pot(_) => self.pot.incr(),
+            DeviceClass::M(_) => self.m.incr(),
+            DeviceClass::Rail(_) => self.rail.incr(),
+        }
+    }
+    /// ensure future ordinals of class are greater than wm
+    pub fn reserve(&mut self, class: &DeviceClass, wm: usize) {
+        match class {
+            DeviceClass::Gnd(_) => self.gnd.reserve(wm),
+            DeviceClass::R(_) => self.r.reserve(wm),
+            DeviceClass::V(_) => self.v.reserve(wm),
+            DeviceClass::Pot(_) => self.pot.reserve(wm),
+            DeviceClass::M(_) => self.m.reserve(wm),
+            DeviceClass::Rail(_) => self.rail.reserve(wm),
+        }
+    }
+    /// reset ordinals of every class, defaults are kept
+    pub fn reset_ordinals(&mut self) {
+        self.gnd = ClassManager::new();
+        self.r = ClassManager::new();
+        self.v = ClassManager::new();
+        self.pot = ClassManager::new();
+        self.m = ClassManager::new();
+        self.rail = ClassManager::new();
+    }
+    /// record params as the most recently applied parameters of the class named class_name
+    pub fn push_recent(&mut self, class_name: &str, params: &[(String, String)]) {
+        let recent = self.recent.entry(class_name.to_string()).or_default();
+        recent.retain(|p| p != params);
+        recent.insert(0, params.to_vec());
+        recent.truncate(Self::RECENT_LIMIT);
+    }
+    /// returns a new device of class with the default parameters applied
+    pub fn new_device(&self, mut class: DeviceClass) -> RcRDevice {
+        if let Some(params) = self.defaults.get(class.name()) {
+            class.set_params(params);
+        }
+        RcRDevice(Rc::new(RefCell::new(Device::new_with_ord_class(0, class))))
+    }
+}
+
+/// the state of every device at a point in time. 
+/// Device identities are kept so that references to devices remain valid after restoring.
+#[derive(Debug, Clone, Default)]
+pub struct DevicesSnapshot(Vec<(RcRDevice, Device)>);
+
+#[derive(Debug)]
 pub struct Devices {
     set: HashSet<RcRDevice>, 
     manager: DevicesManager,
+    /// devices with operating point current magnitude above this limit are drawn flagged
+    current_limit: Option<f32>,
+    /// number of significant figures of operating point annotations
+    op_precision: usize,
+    /// separators of operating point annotations
+    number_format: NumberFormat,
+    /// text color of operating point annotations, contrasting the canvas background
+    op_color: Color,
+    /// if true, the ports of devices with more than 2 ports are labeled with their names
+    show_pin_names: bool,
+}
+
+impl Default for Devices {
+    fn default() -> Self {
+        Self { 
+            set: HashSet::new(), 
+            manager: DevicesManager::default(), 
+            current_limit: None, 
+            op_precision: 3,
+            number_format: NumberFormat::default(),
+            op_color: Color::WHITE,
+            show_pin_names: false,
+        }
+    }
 }
 
 impl Drawable for Devices {
     fn draw_persistent(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
         for d in &self.set {
             d.0.borrow().draw_persistent(vct, vcscale, frame);
+            d.0.borrow().draw_op(vct, vcscale, frame, self.op_precision, &self.number_format, self.op_color);
+            if self.show_pin_names {
+                d.0.borrow().draw_pin_names(vct, vcscale, frame, self.op_color);
+            }
+            if d.0.borrow().interactable.locked {
+                d.0.borrow().interactable.draw_lock(vct, vcscale, frame);
+            }
+            if d.0.borrow().disabled() {
+                d.0.borrow().draw_highlight(vct, vcscale, frame, Color::from_rgb(0.4, 0.4, 0.4));
+                continue;
+            }
+            if d.0.borrow().class().validate_params().is_err() {
+                d.0.borrow().draw_highlight(vct, vcscale, frame, Color::from_rgb(1.0, 0.0, 1.0));
+            }
+            if let (Some(limit), Some(i)) = (self.current_limit, d.0.borrow().op_current()) {
+                if i.abs() > limit {
+                    d.0.borrow().draw_highlight(vct, vcscale, frame, Color::from_rgb(1.0, 0.0, 0.0));
+                }
+            }
         }
     }
     fn draw_selected(&self, _vct: VCTransform, _vcscale: f32, _frame: &mut Frame) {
@@ -94,13 +250,37 @@ impl Devices {
             d.0.borrow_mut().op(pkvecvaluesall);
         }
     }
+    /// returns the number of significant figures of operating point annotations
+    pub fn op_precision(&self) -> usize {
+        self.op_precision
+    }
+    /// set the number of significant figures of operating point annotations
+    pub fn set_op_precision(&mut self, precision: usize) {
+        self.op_precision = precision.max(1);
+    }
+    /// returns the display conventions of operating point annotations
+    pub fn number_format(&self) -> &NumberFormat {
+        &self.number_format
+    }
+    /// set the display conventions of operating point annotations
+    pub fn set_number_format(&mut self, number_format: NumberFormat) {
+        self.number_format = number_format;
+    }
+    /// set the text color of operating point annotations
+    pub fn set_op_color(&mut self, color: Color) {
+        self.op_color = color;
+    }
+    /// set whether the ports of devices with more than 2 ports are labeled with their names
+    pub fn set_show_pin_names(&mut self, show: bool) {
+        self.show_pin_names = show;
+    }
+    /// set the current limit above which devices are flagged. None to disable.
+    pub fn set_current_limit(&mut self, limit: Option<f32>) {
+        self.current_limit = limit;
+    }
     pub fn insert(&mut self, d: RcRDevice) {
         if !self.set.contains(&d) {
-            let ord = match d.0.borrow().class() {
-                DeviceClass::Gnd(_) => self.manager.gnd.incr(),
-                DeviceClass::R(_) => self.manager.r.incr(),
-                DeviceClass::V(_) => self.manager.v.incr(),
-            };
+            let ord = self.manager.incr(d.0.borrow().class());
             d.0.borrow_mut().set_wm(ord);
             self.set.insert(d);
         }
@@ -123,22 +303,70 @@ impl Devices {
         }).collect();
     }
     pub fn new_res(&mut self) -> RcRDevice {
-        let d = Device::new_with_ord_class(0, DeviceClass::R(R::new()));
-        RcRDevice(Rc::new(RefCell::new(d)))
+        self.manager.new_device(DeviceClass::R(R::new()))
     }
     pub fn new_gnd(&mut self) -> RcRDevice {
-        let d = Device::new_with_ord_class(0, DeviceClass::Gnd(Gnd::new()));
-        RcRDevice(Rc::new(RefCell::new(d)))
+        self.manager.new_device(DeviceClass::Gnd(Gnd::new()))
     }
     pub fn new_vs(&mut self) -> RcRDevice {
-        let d = Device::new_with_ord_class(0, DeviceClass::V(V::new()));
-        RcRDevice(Rc::new(RefCell::new(d)))
+        self.manager.new_device(DeviceClass::V(V::new()))
+    }
+    /// returns a new device of the class named class_name, see `class_names()`. None if there is no such class.
+    pub fn new_named(&mut self, class_name: &str) -> Option<RcRDevice> {
+        DeviceClass::from_name(class_name).map(|class| self.manager.new_device(class))
+    }
+    pub fn new_pot(&mut self) -> RcRDevice {
+        self.manager.new_device(DeviceClass::Pot(Pot::new()))
+    }
+    /// set the default parameters of newly created devices of the class named class_name
+    pub fn set_defaults(&mut self, class_name: &str, params: Vec<(String, String)>) {
+        self.manager.defaults.insert(class_name.to_string(), params);
+    }
+    /// returns the default parameters of every device class, keyed by class name
+    pub fn defaults(&self) -> &BTreeMap<String, Vec<(String, String)>> {
+        &self.manager.defaults
+    }
+    /// record params as the most recently applied parameters of the class named class_name
+    pub fn push_recent(&mut self, class_name: &str, params: &[(String, String)]) {
+        self.manager.push_recent(class_name, params);
+    }
+    /// replace the recently applied parameters of every device class
+    pub fn set_recent(&mut self, recent: BTreeMap<String, Vec<Vec<(String, String)>>>) {
+        self.manager.recent = recent;
+    }
+    /// returns the recently applied parameters of every device class, most recent first, keyed by class name
+    pub fn recent(&self) -> &BTreeMap<String, Vec<Vec<(String, String)>>> {
+        &self.manager.recent
     }
     pub fn ports_ssp(&self) -> Vec<SSPoint> {
         self.set.iter()
         .flat_map(|d| d.0.borrow().ports_ssp())
         .collect()
     }
+    /// returns the port position and net name of every enabled power rail
+    pub fn rails(&self) -> Vec<(SSPoint, String)> {
+        self.set.iter()
+        .filter(|d| !d.0.borrow().disabled())
+        .filter_map(|d| {
+            let d = d.0.borrow();
+            Some((*d.ports_ssp().first()?, d.class().rail_name()?.to_string()))
+        })
+        .collect()
+    }
+    /// returns the ports of every enabled ground device
+    pub fn ground_ports_ssp(&self) -> Vec<SSPoint> {
+        self.set.iter()
+        .filter(|d| !d.0.borrow().disabled() && matches!(d.0.borrow().class(), DeviceClass::Gnd(_)))
+        .flat_map(|d| d.0.borrow().ports_ssp())
+        .collect()
+    }
+    /// returns the ports of every device which is not disabled, disabled devices connect nothing
+    pub fn enabled_ports_ssp(&self) -> Vec<SSPoint> {
+        self.set.iter()
+        .filter(|d| !d.0.borrow().disabled())
+        .flat_map(|d| d.0.borrow().ports_ssp())
+        .collect()
+    }
     pub fn clear_tentatives(&mut self) {
         for d in &self.set {
             d.0.borrow_mut().interactable.tentative = false;
@@ -164,6 +392,65 @@ impl Devices {
     pub fn get_set(&self) -> &HashSet<RcRDevice> {
         &self.set
     }
+    /// returns every device in a stable order: by identifier, with numbered identifiers in numeric order (R2 before R10).
+    /// Devices with identical identifiers are ordered by position.
+    pub fn sorted(&self) -> Vec<RcRDevice> {
+        let mut devices: Vec<RcRDevice> = self.set.iter().cloned().collect();
+        devices.sort_by_cached_key(|d| {
+            let d = d.0.borrow();
+            let id = d.ng_id();
+            let (prefix, ord) = designator_key(&id);
+            let ssp = d.position();
+            (prefix, ord, id, ssp.x, ssp.y)
+        });
+        devices
+    }
+    /// reassign ordinals sequentially per device class, ordered top-left to bottom-right.
+    /// Devices with user defined identifiers are left untouched.
+    pub fn renumber(&mut self) {
+        let mut devices: Vec<RcRDevice> = self.set.iter().filter(|d| !d.0.borrow().has_custom_id()).cloned().collect();
+        devices.sort_by_key(|d| {
+            let ssp = d.0.borrow().position();
+            (std::cmp::Reverse(ssp.y), ssp.x)
+        });
+        self.manager.reset_ordinals();
+        for d in devices {
+            let ord = self.manager.incr(d.0.borrow().class());
+            d.0.borrow_mut().set_wm(ord);
+        }
+    }
+    /// returns a copy of the state of every device
+    pub fn snapshot(&self) -> DevicesSnapshot {
+        DevicesSnapshot(self.set.iter().map(|d| (d.clone(), d.0.borrow().clone())).collect())
+    }
+    /// restore devices to the state in snapshot. Devices not in snapshot are removed.
+    pub fn restore(&mut self, snapshot: &DevicesSnapshot) {
+        self.set = snapshot.0.iter().map(|(rcrd, d)| {
+            *rcrd.0.borrow_mut() = d.clone();
+            rcrd.clone()
+        }).collect();
+    }
+    /// returns every device as saved to file
+    pub fn records(&self) -> Vec<DeviceRecord> {
+        self.set.iter().map(|d| d.0.borrow().record()).collect()
+    }
+    /// replace every device with devices created from records. Returns the class name of the first unknown device, if any.
+    pub fn load_records(&mut self, records: &[DeviceRecord]) -> Result<(), String> {
+        let devices = records.iter()
+        .map(|r| Device::from_record(r).ok_or_else(|| r.class.clone()))
+        .collect::<Result<Vec<Device>, String>>()?;
+        self.set.clear();
+        self.manager.reset_ordinals();
+        for d in devices {
+            self.manager.reserve(d.class(), d.wm());
+            self.set.insert(RcRDevice(Rc::new(RefCell::new(d))));
+        }
+        Ok(())
+    }
+    /// returns true if d is in the schematic
+    pub fn contains(&self, d: &RcRDevice) -> bool {
+        self.set.contains(d)
+    }
 }
 
 impl SchematicSet for Devices {
@@ -1,22 +1,67 @@
 //! device instance. Every instance of a device in the schematic is a distinct device instance.
 
 use std::hash::Hasher;
+use std::collections::BTreeMap;
+use std::borrow::Cow;
 
-use super::devicetype::{DeviceClass, r::ParamEditor};
+use super::devicetype::{DeviceClass, Graphics, Port, r::ParamEditor};
+use super::params::format_significant;
 
-use iced::{widget::canvas::{Frame, Text}, Color, Element};
+/// attribute key of the device datasheet url
+pub const DATASHEET_ATTRIBUTE: &str = "datasheet_url";
+
+/// minimum size of operating point annotations, in pixels
+const MIN_OP_TEXT_SIZE: f32 = 12.0;
+/// text size of the hover tooltip, in pixels
+const TOOLTIP_TEXT_SIZE: f32 = 14.0;
+/// distance of annotations from the port or symbol they annotate, in schematic units
+const ANNOTATION_GAP: f32 = 0.5;
+
+use iced::{widget::canvas::{Frame, Text}, alignment::{Horizontal, Vertical}, Color, Element, Size};
 
 use crate::{
-    schematic::{Drawable, interactable::Interactive, Nets},
+    config::NumberFormat,
+    schematic::{Drawable, interactable::Interactive, Nets, file::DeviceRecord},
     transforms::{
-        SSPoint, VSPoint, VCTransform, Point, SSTransform, ViewportSpace, sst_to_xxt
+        SSPoint, SSBox, VSPoint, VSVec, VSBox, VCTransform, Point, SSTransform, ViewportSpace, sst_to_xxt, SST_CCWR
     }, 
 };
 use crate::schematic::interactable::Interactable;
 use std::hash::Hash;
 
+/// returns where to draw the annotation of the port at p of a device centered at center: 
+/// the text anchor and its alignment, such that the text lies outward of the symbol and beside the wire leaving the port.
+fn port_annotation_placement(center: VSPoint, p: VSPoint) -> (VSPoint, Horizontal, Vertical) {
+    let d = p - center;
+    if d.x.abs() > d.y.abs() {
+        // port on the left or right, text above the wire
+        let (dx, h) = if d.x > 0.0 {(ANNOTATION_GAP, Horizontal::Left)} else {(-ANNOTATION_GAP, Horizontal::Right)};
+        (p + VSVec::new(dx, ANNOTATION_GAP), h, Vertical::Bottom)
+    } else {
+        // port on the top or bottom, text right of the wire. +y is up in schematic space, down on canvas
+        let (dy, v) = if d.y >= 0.0 {(ANNOTATION_GAP, Vertical::Bottom)} else {(-ANNOTATION_GAP, Vertical::Top)};
+        (p + VSVec::new(ANNOTATION_GAP, dy), Horizontal::Left, v)
+    }
+}
+
+/// returns where and how to align the name of a port at p on a symbol centered at center.
+/// The name is placed inward of the port, opposite to the operating point annotation, 
+/// so that both can be shown at once.
+fn pin_name_placement(center: VSPoint, p: VSPoint) -> (VSPoint, Horizontal, Vertical) {
+    let d = p - center;
+    if d.x.abs() > d.y.abs() {
+        // port on the left or right, text inside the symbol below the wire
+        let (dx, h) = if d.x > 0.0 {(-ANNOTATION_GAP, Horizontal::Right)} else {(ANNOTATION_GAP, Horizontal::Left)};
+        (p + VSVec::new(dx, -ANNOTATION_GAP), h, Vertical::Top)
+    } else {
+        // port on the top or bottom, text inside the symbol left of the wire
+        let (dy, v) = if d.y >= 0.0 {(-ANNOTATION_GAP, Vertical::Top)} else {(ANNOTATION_GAP, Vertical::Bottom)};
+        (p + VSVec::new(-ANNOTATION_GAP, dy), Horizontal::Right, v)
+    }
+}
+
 /// device identifier
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Identifier {
     /// prefix which determines device type in NgSpice - a few characters at most
     id_prefix: &'static str,
@@ -47,6 +92,10 @@ impl Identifier {
         }
         ret
     }
+    /// returns true if the identifier is set by the user
+    pub fn is_custom(&self) -> bool {
+        self.custom.is_some()
+    }
     /// creates a new identifier with a prefix and watermark
     pub fn new_with_prefix_ord(id_prefix: &'static str , wm: usize) -> Self {
         Identifier { id_prefix, wm, custom: None }
@@ -64,7 +113,7 @@ impl Hash for Identifier {
 }
 
 /// A device - e.g. a resistor, bjt, voltage source, ground
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Device  {
     /// id which uniquely identifies the device in netlist
     id: Identifier,
@@ -74,21 +123,47 @@ pub struct Device  {
     transform: SSTransform,
     /// the class of the device - is the device a resistor, ground, voltage source... ?
     class: DeviceClass,
+    /// indices into the class ports, in the order they appear in the netlist
+    port_order: Vec<usize>,
 
     /// vector of the connected net names in order of device ports
     nets: Vec<String>,
     /// vector of the connect net voltages in order of device ports
     op: Vec<f32>,
+    /// current through the device at the operating point, if known
+    op_current: Option<f32>,
+    /// non-electrical attributes, e.g. footprint, power rating, manufacturer part number. Ignored by simulation.
+    attributes: BTreeMap<String, String>,
+    /// if true, the device is commented out in the netlist and its ports connect nothing, e.g. for what-if analysis
+    disabled: bool,
+    /// if true, the symbol is turned 45 deg counter clockwise ahead of the device transform, see `rotate_diagonal`
+    diagonal: bool,
 }
 impl Device {
     /// wip concept
     pub fn param_editor(&mut self) -> Option<impl ParamEditor + Into<Element<()>>> {
         self.class.param_editor()
     }
+    /// returns the device identifier watermark
+    pub fn wm(&self) -> usize {
+        self.id.wm
+    }
     /// sets the device identifier watermark
     pub fn set_wm(&mut self, wm: usize) {
         self.id.wm = wm;
     }
+    /// returns the device identifier as it appears in the netlist. E.g. V1, R0
+    pub fn ng_id(&self) -> String {
+        self.id.ng_id()
+    }
+    /// returns true if the device identifier is set by the user
+    pub fn has_custom_id(&self) -> bool {
+        self.id.is_custom()
+    }
+    /// returns the position of the device in schematic space
+    pub fn position(&self) -> SSPoint {
+        SSPoint::new(self.transform.m31, self.transform.m32)
+    }
     /// returns a reference to the device class
     pub fn class(&self) -> &DeviceClass {
         &self.class
@@ -97,25 +172,124 @@ impl Device {
     pub fn class_mut(&mut self) -> &mut DeviceClass {
         &mut self.class
     }
+    /// returns a reference to the device attributes
+    pub fn attributes(&self) -> &BTreeMap<String, String> {
+        &self.attributes
+    }
+    /// returns the datasheet url of the device, if set as an attribute
+    pub fn datasheet_url(&self) -> Option<&str> {
+        self.attributes.get(DATASHEET_ATTRIBUTE).map(|s| s.as_str()).filter(|s| !s.is_empty())
+    }
+    /// returns the device attributes as whitespace separated `key=value` pairs, values containing whitespace are quoted
+    pub fn attributes_summary(&self) -> String {
+        self.attributes.iter().map(|(k, v)| {
+            if v.contains(char::is_whitespace) {format!("{}=\"{}\"", k, v)} else {format!("{}={}", k, v)}
+        }).collect::<Vec<_>>().join(" ")
+    }
+    /// sets the device attributes from whitespace separated `key=value` pairs. 
+    /// Whitespace between double quotes is kept, such that `key="a value"` sets a value containing a space. Entries without a key are ignored.
+    pub fn set_attributes(&mut self, new: &str) {
+        let mut entries = vec![];
+        let mut entry = String::new();
+        let mut quoted = false;
+        for c in new.chars() {
+            match c {
+                '"' => quoted = !quoted,
+                c if c.is_whitespace() && !quoted => entries.push(std::mem::take(&mut entry)),
+                c => entry.push(c),
+            }
+        }
+        entries.push(entry);
+        self.attributes = entries.iter().filter_map(|kv| {
+            let (k, v) = kv.split_once('=').unwrap_or((kv, ""));
+            if k.is_empty() {None} else {Some((k.to_string(), v.to_string()))}
+        }).collect();
+    }
+    /// returns a copy of the device without simulation results or custom identifier, intended for copy/paste. 
+    /// The copy is given a new identifier when inserted into the schematic.
+    pub fn duplicate(&self) -> Self {
+        let mut d = self.clone();
+        d.id.custom = None;
+        d.nets.clear();
+        d.op.clear();
+        d.op_current = None;
+        d.interactable.tentative = false;
+        d.interactable.locked = false;
+        d
+    }
+    /// returns the device as saved to file
+    pub fn record(&self) -> DeviceRecord {
+        DeviceRecord {
+            class: self.class.name().to_string(),
+            wm: self.id.wm,
+            custom_id: self.id.custom.clone(),
+            transform: self.transform,
+            params: self.class.params(),
+            attributes: self.attributes.clone(),
+            port_order: self.port_order.clone(),
+            locked: self.interactable.locked,
+            disabled: self.disabled,
+            diagonal: self.diagonal,
+        }
+    }
+    /// creates a device from its saved record. Returns none if the device class is unknown.
+    pub fn from_record(record: &DeviceRecord) -> Option<Self> {
+        let mut class = DeviceClass::from_name(&record.class)?;
+        class.set_params(&record.params);
+        let mut d = Device::new_with_ord_class(record.wm, class);
+        d.id.custom = record.custom_id.clone();
+        d.attributes = record.attributes.clone();
+        let nports = d.port_order.len();
+        if record.port_order.len() == nports && (0..nports).all(|i| record.port_order.contains(&i)) {
+            d.port_order = record.port_order.clone();
+        }
+        d.diagonal = record.diagonal && d.class.rotates_diagonally();
+        d.transform(record.transform);
+        d.interactable.locked = record.locked;
+        d.disabled = record.disabled;
+        Some(d)
+    }
     /// creates a new device with watermark and class
     pub fn new_with_ord_class(wm: usize, class: DeviceClass) -> Self {
         Device { 
             id: Identifier::new_with_prefix_ord(class.id_prefix(), wm), 
             interactable: Interactable::new(), 
             transform: SSTransform::identity(), 
+            port_order: (0..class.graphics().ports().len()).collect(),
             class,
             nets: vec![],
             op: vec![],
+            op_current: None,
+            attributes: BTreeMap::new(),
+            disabled: false,
+            diagonal: false,
+        }
+    }
+    /// returns the device ports in netlist order
+    fn ports(&self) -> Vec<&Port> {
+        let ports = self.class.graphics().ports();
+        self.port_order.iter().map(|&i| &ports[i]).collect()
+    }
+    /// swap which port is connected as node 1 and node 2 in the netlist. 
+    /// Returns false and does nothing if the device class is not symmetric.
+    pub fn swap_ports(&mut self) -> bool {
+        if !self.class.ports_swappable() {
+            return false;
         }
+        self.port_order.reverse();
+        self.nets.reverse();
+        self.op.reverse();
+        self.op_current = self.op_current.map(|i| -i);
+        true
     }
     /// returns the schematic coordiantes of the devices ports in order
     pub fn ports_ssp(&self) -> Vec<SSPoint> {
-        self.class.graphics().ports().iter().map(|p| self.transform.transform_point(p.offset)).collect()
+        self.ports().iter().map(|p| self.transform.transform_point(self.turned(p.offset))).collect()
     }
     /// returns true if any port occupies ssp
     pub fn ports_occupy_ssp(&self, ssp: SSPoint) -> bool {
         for p in self.class.graphics().ports() {
-            if self.transform.transform_point(p.offset) == ssp {
+            if self.transform.transform_point(self.turned(p.offset)) == ssp {
                 return true;
             }
         }
@@ -125,31 +299,90 @@ impl Device {
     fn compose_transform(&self, vct: VCTransform) -> VCTransform {
         sst_to_xxt::<ViewportSpace>(self.transform).then(&vct)
     }
+    /// returns the symbol as drawn ahead of the device transform, turned with its ports on the grid if the device is diagonal
+    fn symbol(&self) -> Cow<'static, Graphics> {
+        let graphics = self.class.graphics();
+        if self.diagonal {
+            Cow::Owned(graphics.diagonal(|offset| self.turned(offset), self.symbol_bounds()))
+        } else {
+            Cow::Borrowed(graphics)
+        }
+    }
+    /// returns offset, a point of the symbol, turned 45 deg if the device is diagonal. 
+    /// Turned points rarely fall on the grid, they are rounded to the nearest grid point so that ports stay connectable.
+    fn turned(&self, offset: SSPoint) -> SSPoint {
+        if !self.diagonal {
+            return offset;
+        }
+        let (x, y) = (offset.x as f32, offset.y as f32);
+        let s = std::f32::consts::FRAC_1_SQRT_2;
+        SSPoint::new(((x - y) * s).round() as i32, ((x + y) * s).round() as i32)
+    }
+    /// returns the bounds of the symbol before the device transform, enlarged to hold the turned symbol if the device is diagonal
+    fn symbol_bounds(&self) -> SSBox {
+        let b = *self.class.graphics().bounds();
+        if !self.diagonal {
+            return b;
+        }
+        let r = b.min.x.abs().max(b.max.x.abs()) + b.min.y.abs().max(b.max.y.abs());
+        let r = (r as f32 * std::f32::consts::FRAC_1_SQRT_2).ceil() as i32;
+        SSBox::new(SSPoint::new(-r, -r), SSPoint::new(r, r))
+    }
+    /// returns true if the symbol is turned 45 deg, see `rotate_diagonal`
+    pub fn diagonal(&self) -> bool {
+        self.diagonal
+    }
+    /// rotate the device 45 deg counter clockwise about its position, if its class supports diagonal orientations. 
+    /// Returns false and does nothing otherwise.
+    pub fn rotate_diagonal(&mut self) -> bool {
+        if !self.class.rotates_diagonally() {
+            return false;
+        }
+        if self.diagonal {
+            self.transform = SST_CCWR.then(&self.transform);
+        }
+        self.diagonal = !self.diagonal;
+        self.interactable.bounds = self.transform.outer_transformed_box(&self.symbol_bounds());
+        true
+    }
     /// sets the position of the device
     pub fn set_position(&mut self, ssp: SSPoint) {
         self.transform.m31 = ssp.x;
         self.transform.m32 = ssp.y;
-        self.interactable.bounds = self.transform.outer_transformed_box(self.class.graphics().bounds());
+        self.interactable.bounds = self.transform.outer_transformed_box(&self.symbol_bounds());
+    }
+    /// returns true if the device is excluded from simulation
+    pub fn disabled(&self) -> bool {
+        self.disabled
     }
-    /// returns the device's spice netlist line
+    /// set whether the device is excluded from simulation
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.disabled = disabled;
+    }
+    /// returns the device's spice netlist line, commented out if the device is disabled
     pub fn spice_line(&mut self, nets: &mut Nets) -> String {
         self.nets.clear();
-        let mut sline = self.id.ng_id();
-        sline.push(' ');
-        for p in self.class.graphics().ports() {
-            let pt = self.transform.transform_point(p.offset);
+        for pt in self.ports_ssp() {
             let net = nets.net_at(pt);
-            sline.push_str(&net);
-            sline.push(' ');
             self.nets.push(net);
         }
-        sline.push_str(&self.class.param_summary());
-        sline.push('\n');
+        let mut sline = self.class.spice_lines(&self.id.ng_id(), &self.nets);
+        if self.disabled {
+            sline = sline.lines().map(|l| format!("* {}\n", l)).collect();
+        }
+        if !self.attributes.is_empty() {
+            // attributes are emitted as a comment so they do not affect simulation
+            sline.push_str(&format!("* {} {}\n", self.id.ng_id(), self.attributes_summary()));
+        }
         sline
     }
     /// fill in the operating point for the device
     pub fn op(&mut self, pkvecvaluesall: &paprika::PkVecvaluesall) {
         self.op.clear();
+        if self.disabled {
+            self.op_current = None;
+            return;
+        }
         for n in &self.nets {
             for v in &pkvecvaluesall.vecsa {
                 if &v.name == n {
@@ -158,57 +391,226 @@ impl Device {
                 }
             }
         }
+        self.op_current = if let Some(r) = self.class.resistance() {
+            match self.op.as_slice() {
+                [v0, v1] if r != 0.0 => Some((v0 - v1) / r),
+                _ => None,
+            }
+        } else {
+            // voltage sources report their current as a branch vector
+            let branch = format!("{}#branch", self.id.ng_id());
+            pkvecvaluesall.vecsa.iter()
+            .find(|v| v.name.eq_ignore_ascii_case(&branch))
+            .map(|v| v.creal as f32)
+        };
+    }
+    /// returns the current through the device at the operating point, if known
+    pub fn op_current(&self) -> Option<f32> {
+        self.op_current
+    }
+    /// draw the operating point voltage beside each port in color with precision significant figures, formatted by number_format. 
+    /// The text is placed outward of the symbol, see `port_annotation_placement`. 
+    /// Text is never drawn smaller than `MIN_OP_TEXT_SIZE` so that results remain legible when zoomed out.
+    pub fn draw_op(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame, precision: usize, number_format: &NumberFormat, color: Color) {
+        let bounds: VSBox = self.interactable.bounds.cast().cast_unit();
+        let center = bounds.center();
+        let ports = self.ports_ssp();
+        for (i, v) in self.op.iter().enumerate() {
+            let (anchor, horizontal_alignment, vertical_alignment) = port_annotation_placement(center, ports[i].cast().cast_unit());
+            let b = Text {
+                content: number_format.localize(&format_significant(*v, precision)),
+                position: Point::from(vct.transform_point(anchor)).into(),
+                color,
+                size: vcscale.max(MIN_OP_TEXT_SIZE),
+                horizontal_alignment,
+                vertical_alignment,
+                ..Default::default()
+            };
+            frame.fill_text(b);
+        }
+    }
+    /// draw the name of each port beside it in color, transformed with the device, see `pin_name_placement`. 
+    /// Only devices with more than 2 ports are labeled, the ports of 2 terminal devices are told apart by their symbol.
+    pub fn draw_pin_names(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame, color: Color) {
+        let ports = self.ports();
+        if ports.len() <= 2 {
+            return;
+        }
+        let center: VSPoint = self.interactable.bounds.cast().cast_unit().center();
+        for (port, p) in ports.iter().zip(self.ports_ssp()) {
+            let (anchor, horizontal_alignment, vertical_alignment) = pin_name_placement(center, p.cast().cast_unit());
+            frame.fill_text(Text {
+                content: port.name.clone(),
+                position: Point::from(vct.transform_point(anchor)).into(),
+                color,
+                size: vcscale.max(MIN_OP_TEXT_SIZE),
+                horizontal_alignment,
+                vertical_alignment,
+                ..Default::default()
+            });
+        }
+    }
+    /// returns the lines of the hover tooltip: identifier and class, the parameter summary and every parameter
+    pub fn tooltip_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("{} {}", self.ng_id(), self.class.name()), self.class.param_summary()];
+        lines.extend(self.class.params().into_iter().map(|(name, value)| format!("{} = {}", name, value)));
+        lines.retain(|l| !l.is_empty());
+        lines
+    }
+    /// draw the hover tooltip on a dark background just below and right of the cursor at vsp. 
+    /// Text is drawn at a fixed size so that it is legible at any zoom.
+    pub fn draw_tooltip(&self, vsp: VSPoint, vct: VCTransform, frame: &mut Frame) {
+        let lines = self.tooltip_lines();
+        let csp = vct.transform_point(vsp);
+        let origin = iced::Point::new(csp.x + TOOLTIP_TEXT_SIZE, csp.y + TOOLTIP_TEXT_SIZE);
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as f32 * TOOLTIP_TEXT_SIZE * 0.6;
+        let height = lines.len() as f32 * TOOLTIP_TEXT_SIZE * 1.2;
+        let pad = TOOLTIP_TEXT_SIZE * 0.25;
+        frame.fill_rectangle(
+            iced::Point::new(origin.x - pad, origin.y - pad), 
+            Size::new(width + 2.0 * pad, height + 2.0 * pad), 
+            Color::from_rgba(0.1, 0.1, 0.1, 0.9),
+        );
+        for (i, l) in lines.into_iter().enumerate() {
+            frame.fill_text(Text {
+                content: l,
+                position: iced::Point::new(origin.x, origin.y + i as f32 * TOOLTIP_TEXT_SIZE * 1.2),
+                color: Color::WHITE,
+                size: TOOLTIP_TEXT_SIZE,
+                ..Default::default()
+            });
+        }
+    }
+    /// draw the device symbol in color, e.g. to flag the device
+    pub fn draw_highlight(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame, color: Color) {
+        let vct_c = self.compose_transform(vct);
+        self.symbol().draw_highlight(vct_c, vcscale, frame, color);
     }
 }
 
 impl Drawable for Device {
+    /// draws the symbol with the identifier above and the parameter summary below its vertical center, 
+    /// beside the symbol on the right, or on the left if a port leaves the symbol to the right
     fn draw_persistent(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
         let vct_c = self.compose_transform(vct);
-        self.class.graphics().draw_persistent(vct_c, vcscale, frame);
+        self.symbol().draw_persistent(vct_c, vcscale, frame);
         
+        let bounds: VSBox = self.interactable.bounds.cast().cast_unit();
+        let center = bounds.center();
+        let port_right = self.ports_ssp().into_iter().any(|p| {
+            let d = p.cast().cast_unit() - center;
+            d.x > d.y.abs()
+        });
+        let (x, horizontal_alignment) = if port_right {
+            (bounds.min.x - ANNOTATION_GAP, Horizontal::Right)
+        } else {
+            (bounds.max.x + ANNOTATION_GAP, Horizontal::Left)
+        };
         let a = Text {
             content: self.id.ng_id(),
-            position: Point::from(vct_c.transform_point(VSPoint::new(1.0, 1.0))).into(),
+            position: Point::from(vct.transform_point(VSPoint::new(x, center.y))).into(),
             color: Color::from_rgba(1.0, 0.5, 1.0, 1.0),
             size: vcscale,
+            horizontal_alignment,
+            vertical_alignment: Vertical::Bottom,
             ..Default::default()
         };
         frame.fill_text(a);
 
         let b = Text {
             content: self.class.param_summary(),
-            position: Point::from(vct_c.transform_point(VSPoint::new(1.0, 0.0))).into(),
+            position: Point::from(vct.transform_point(VSPoint::new(x, center.y))).into(),
             color: Color::from_rgba(0.5, 1.0, 1.0, 1.0),
             size: vcscale,
+            horizontal_alignment,
+            vertical_alignment: Vertical::Top,
             ..Default::default()
         };
         frame.fill_text(b);
-
-        let ports = self.class.graphics().ports();
-        for (i, v) in self.op.iter().enumerate() {
-            let b = Text {
-                content: v.to_string(),
-                position: Point::from(vct_c.transform_point(ports[i].offset.cast().cast_unit())).into(),
-                color: Color::from_rgba(1.0, 1.0, 1.0, 1.0),
-                size: vcscale,
-                ..Default::default()
-            };
-            frame.fill_text(b);
-        }
     }
     fn draw_selected(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
         let vct_c = self.compose_transform(vct);
-        self.class.graphics().draw_selected(vct_c, vcscale, frame);
+        self.symbol().draw_selected(vct_c, vcscale, frame);
     }
     fn draw_preview(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
         let vct_c = self.compose_transform(vct);
-        self.class.graphics().draw_preview(vct_c, vcscale, frame);
+        self.symbol().draw_preview(vct_c, vcscale, frame);
     }
 }
 
 impl Interactive for Device {
     fn transform(&mut self, sst: SSTransform) {
         self.transform = self.transform.then(&sst);
-        self.interactable.bounds = self.transform.outer_transformed_box(self.class.graphics().bounds());
+        self.interactable.bounds = self.transform.outer_transformed_box(&self.symbol_bounds());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn port_annotations_point_away_from_the_symbol() {
+        let center = VSPoint::new(10.0, 10.0);
+        // top port: above the port, right of the wire leaving it upwards
+        let (anchor, h, v) = port_annotation_placement(center, VSPoint::new(10.0, 13.0));
+        assert_eq!((anchor, h, v), (VSPoint::new(10.5, 13.5), Horizontal::Left, Vertical::Bottom));
+        // bottom port: below the port
+        let (anchor, _, v) = port_annotation_placement(center, VSPoint::new(10.0, 7.0));
+        assert_eq!((anchor, v), (VSPoint::new(10.5, 6.5), Vertical::Top));
+        // left port: text ends left of the port
+        let (anchor, h, _) = port_annotation_placement(center, VSPoint::new(8.0, 10.0));
+        assert_eq!((anchor, h), (VSPoint::new(7.5, 10.5), Horizontal::Right));
+    }
+
+    #[test]
+    fn pin_names_point_into_the_symbol() {
+        let center = VSPoint::new(10.0, 10.0);
+        // top port: below the port, left of the wire
+        let (anchor, h, v) = pin_name_placement(center, VSPoint::new(10.0, 13.0));
+        assert_eq!((anchor, h, v), (VSPoint::new(9.5, 12.5), Horizontal::Right, Vertical::Top));
+        // right port: text ends left of the port, below the wire
+        let (anchor, h, v) = pin_name_placement(center, VSPoint::new(13.0, 10.0));
+        assert_eq!((anchor, h, v), (VSPoint::new(12.5, 9.5), Horizontal::Right, Vertical::Top));
+        // left port: text starts right of the port
+        let (anchor, h, _) = pin_name_placement(center, VSPoint::new(8.0, 10.0));
+        assert_eq!((anchor, h), (VSPoint::new(8.5, 9.5), Horizontal::Left));
+    }
+
+    #[test]
+    fn quoted_attribute_values_keep_spaces() {
+        let mut d = Device::new_with_ord_class(1, DeviceClass::R(super::super::devicetype::r::R::new()));
+        d.set_attributes(" footprint=0805  note=\"hand  solder\" lone ");
+        assert_eq!(d.attributes().get("note").map(String::as_str), Some("hand  solder"));
+        assert_eq!(d.attributes().get("footprint").map(String::as_str), Some("0805"));
+        assert_eq!(d.attributes().get("lone").map(String::as_str), Some(""));
+        assert_eq!(d.attributes().len(), 3);
+
+        // the summary parses back to the same attributes
+        let summary = d.attributes_summary();
+        assert_eq!(summary, "footprint=0805 lone= note=\"hand  solder\"");
+        let attributes = d.attributes().clone();
+        d.set_attributes(&summary);
+        assert_eq!(d.attributes(), &attributes);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn duplicates_drop_custom_identifiers() {
+        let mut d = Device::new_with_ord_class(1, DeviceClass::R(super::super::devicetype::r::R::new()));
+        d.id.custom = Some(String::from("load"));
+        assert_eq!(d.ng_id(), "Rload");
+        let dup = d.duplicate();
+        assert!(!dup.has_custom_id());
+        assert_eq!(dup.ng_id(), "R1");
+    }
+
+    #[test]
+    fn diagonal_symbols_meet_their_ports() {
+        let mut d = Device::new_with_ord_class(1, DeviceClass::R(super::super::devicetype::r::R::new()));
+        assert!(d.rotate_diagonal());
+        let symbol = d.symbol();
+        let ports: Vec<SSPoint> = symbol.ports().iter().map(|p| p.offset).collect();
+        assert_eq!(ports, d.ports_ssp());
+        assert!(ports.iter().all(|p| p.x != 0 && p.y != 0));
+    }
+}
@@ -4,8 +4,8 @@ use iced::{Size, widget::canvas::{self, stroke, LineCap, path::Builder, LineDash
 
 use crate::{
     transforms::{
-        SSPoint, VSBox, VSPoint, VCTransform, Point, SSBox, VSVec
-    }, schematic::Drawable,
+        SSPoint, VSBox, VSPoint, VCTransform, Point, SSBox, VSVec, ViewportSpace
+    }, schematic::{Drawable, interactable::VisualState},
 };
 
 use self::r::ParamEditor;
@@ -13,6 +13,9 @@ use self::r::ParamEditor;
 pub mod v;
 pub mod r;
 pub mod gnd;
+pub mod pot;
+pub mod m;
+pub mod rail;
 
 /// ports for devices, where wires may be connected
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
@@ -45,7 +48,7 @@ impl Drawable for Port {
     fn draw_selected(&self, vct: crate::transforms::VCTransform, vcscale: f32, frame: &mut iced::widget::canvas::Frame) {
         let stroke = Stroke {
             width: (STROKE_WIDTH * vcscale).max(STROKE_WIDTH * 1.),
-            style: stroke::Style::Solid(Color::from_rgb(1.0, 1.0, 0.0)),
+            style: stroke::Style::Solid(VisualState::Selected.color()),
             line_cap: LineCap::Square,
             ..Stroke::default()
         };
@@ -64,7 +67,7 @@ impl Drawable for Port {
     fn draw_preview(&self, vct: crate::transforms::VCTransform, vcscale: f32, frame: &mut iced::widget::canvas::Frame) {
         let stroke = Stroke {
             width: (STROKE_WIDTH * vcscale).max(STROKE_WIDTH * 1.),
-            style: stroke::Style::Solid(Color::from_rgb(1.0, 1.0, 0.5)),
+            style: stroke::Style::Solid(VisualState::Tentative.color()),
             line_cap: LineCap::Square,
             ..Stroke::default()
         };
@@ -84,7 +87,7 @@ impl Drawable for Port {
 const STROKE_WIDTH: f32 = 0.1;
 
 /// graphical representation for devices
-#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct Graphics {
     /// line is traced from point to point for each inner vector.
     pts: Vec<Vec<VSPoint>>,
@@ -94,14 +97,54 @@ pub struct Graphics {
     ports: Vec<Port>,
     /// device bounds
     bounds: SSBox,
+    /// display scale of the symbol lines and circles. Ports and bounds are not scaled so that ports stay on the grid. 
+    /// The symbol is scaled about the center of its ports, which keeps single port symbols (e.g. ground) attached to their port.
+    #[serde(default = "Graphics::default_scale")]
+    scale: f32,
 }
 impl Graphics {
+    fn default_scale() -> f32 {
+        1.0
+    }
+    /// returns the point about which the symbol is scaled: the center of the ports
+    fn scale_anchor(&self) -> VSPoint {
+        if self.ports.is_empty() {
+            return VSPoint::origin();
+        }
+        let sum = self.ports.iter().fold(VSVec::zero(), |acc, p| acc + p.offset.cast::<f32>().cast_unit().to_vector());
+        (sum / self.ports.len() as f32).to_point()
+    }
+    /// returns vsp scaled by the display scale of the symbol
+    fn scaled(&self, vsp: VSPoint) -> VSPoint {
+        let anchor = self.scale_anchor();
+        anchor + (vsp - anchor) * self.scale
+    }
     pub fn bounds(&self) -> &SSBox {
         &self.bounds
     }
     pub fn ports(&self) -> &[Port] {
         &self.ports
     }
+    /// returns the symbol turned 45 deg counter clockwise with bounds, and each port moved to the grid point turn(offset). 
+    /// A lead from the end of each turned port lead to its moved port keeps the symbol attached to its ports.
+    pub fn diagonal(&self, turn: impl Fn(SSPoint) -> SSPoint, bounds: SSBox) -> Graphics {
+        let rotation = euclid::Transform2D::<f32, ViewportSpace, ViewportSpace>::rotation(euclid::Angle::degrees(45.0));
+        let ports: Vec<Port> = self.ports.iter().map(|p| Port { name: p.name.clone(), offset: turn(p.offset) }).collect();
+        let mut pts: Vec<Vec<VSPoint>> = self.pts.iter()
+            .map(|line| line.iter().map(|v| rotation.transform_point(self.scaled(*v))).collect())
+            .collect();
+        pts.extend(self.ports.iter().zip(&ports).map(|(p, q)| vec![
+            rotation.transform_point(self.scaled(p.offset.cast().cast_unit())), 
+            q.offset.cast().cast_unit(),
+        ]));
+        Graphics {
+            pts,
+            circles: self.circles.iter().map(|(c, r)| (rotation.transform_point(self.scaled(*c)), r * self.scale)).collect(),
+            ports,
+            bounds,
+            scale: 1.0,
+        }
+    }
     pub fn stroke_bounds(&self, vct_composite: VCTransform, frame: &mut Frame, stroke: Stroke) {
         let mut path_builder = Builder::new();
         let vsb = self.bounds.cast().cast_unit();
@@ -117,17 +160,29 @@ impl Graphics {
             // path_builder.move_to(Point::from(vct_composite.transform_point(v1[0])).into());
             let mut path_builder = Builder::new();
             for v0 in v1 {
-                path_builder.line_to(Point::from(vct_composite.transform_point(*v0)).into());
+                path_builder.line_to(Point::from(vct_composite.transform_point(self.scaled(*v0))).into());
             }
             frame.stroke(&path_builder.build(), stroke.clone());
         }
         let mut path_builder = Builder::new();
         for (p, r) in &self.circles {
-            path_builder.circle(Point::from(vct_composite.transform_point(*p)).into(), *r * vcscale);
+            path_builder.circle(Point::from(vct_composite.transform_point(self.scaled(*p))).into(), *r * self.scale * vcscale);
         }
         frame.stroke(&path_builder.build(), stroke.clone());
     }
 }
+impl Graphics {
+    /// draw the symbol in a solid color over the persistent drawing, e.g. to flag the device
+    pub fn draw_highlight(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame, color: Color) {
+        let stroke = Stroke {
+            width: (STROKE_WIDTH * vcscale).max(STROKE_WIDTH * 2.0),
+            style: stroke::Style::Solid(color),
+            line_cap: LineCap::Square,
+            ..Stroke::default()
+        };
+        self.stroke_symbol(vct, vcscale, frame, stroke);
+    }
+}
 impl Drawable for Graphics {
     fn draw_persistent(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
         let stroke = Stroke {
@@ -144,7 +199,7 @@ impl Drawable for Graphics {
     fn draw_selected(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
         let stroke = Stroke {
             width: (STROKE_WIDTH * vcscale).max(STROKE_WIDTH * 2.) / 2.0,
-            style: stroke::Style::Solid(Color::from_rgb(1.0, 0.8, 0.0)),
+            style: stroke::Style::Solid(VisualState::Selected.color()),
             line_cap: LineCap::Round,
             ..Stroke::default()
         };
@@ -157,7 +212,7 @@ impl Drawable for Graphics {
     fn draw_preview(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
         let stroke = Stroke {
             width: (STROKE_WIDTH * vcscale).max(STROKE_WIDTH * 1.) / 2.0,
-            style: stroke::Style::Solid(Color::from_rgb(1.0, 1.0, 0.5)),
+            style: stroke::Style::Solid(VisualState::Tentative.color()),
             line_cap: LineCap::Butt,
             line_dash: LineDash{segments: &[3. * (STROKE_WIDTH * vcscale).max(STROKE_WIDTH * 2.0)], offset: 0},
             ..Stroke::default()
@@ -175,13 +230,21 @@ pub trait DeviceType  {
 }
 
 /// DeviceClass enumerates the various classes of devices. E.g. ground, resistor, voltage source... etc
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum DeviceClass {
     Gnd(gnd::Gnd),
     R(r::R),
     V(v::V),
+    Pot(pot::Pot),
+    M(m::M),
+    Rail(rail::Rail),
 }
 impl DeviceClass {
+    /// names of the device classes which can be placed, see `name()`
+    pub const NAMES: [&'static str; 9] = [
+        "Resistor", "Potentiometer", "Voltage Source", "Ground", "Power Rail", "NMOS", "PMOS", "NMOS 3 terminal", "PMOS 3 terminal",
+    ];
+
     /// todo wip concept
     pub fn param_editor(&mut self) -> Option<impl ParamEditor + Into<Element<()>>> {
         match self {
@@ -194,19 +257,55 @@ impl DeviceClass {
             DeviceClass::V(v) => {
                 None
             },
+            DeviceClass::Pot(_) => {
+                None
+            },
+            DeviceClass::M(_) => {
+                None
+            },
+            DeviceClass::Rail(_) => {
+                None
+            },
         }
     }
-    /// sets the raw parameter of the device
-    pub fn set(&mut self, new: String) {
+    /// returns the parameters of the device as (name, value) pairs, one for each field in the parameter editor
+    pub fn params(&self) -> Vec<(String, String)> {
+        match self {
+            DeviceClass::Gnd(x) => x.params.params(),
+            DeviceClass::R(x) => x.params.params(),
+            DeviceClass::V(x) => x.params.params(),
+            DeviceClass::Pot(x) => x.params.params(),
+            DeviceClass::M(x) => x.params.params(),
+            DeviceClass::Rail(x) => x.params.params(),
+        }
+    }
+    /// sets the parameters of the device from (name, value) pairs
+    pub fn set_params(&mut self, params: &[(String, String)]) {
         match self {
-            DeviceClass::R(x) => match &mut x.params {
-                r::ParamR::Raw(y) => y.set(new),
-                r::ParamR::Value(_) => {},
-            },
             DeviceClass::Gnd(_) => {},
-            DeviceClass::V(x) => match &mut x.params {
-                v::ParamV::Raw(y) => y.set(new),
-            },
+            DeviceClass::R(x) => x.params.set_params(params),
+            DeviceClass::V(x) => x.params.set_params(params),
+            DeviceClass::Pot(x) => x.params.set_params(params),
+            DeviceClass::M(x) => x.params.set_params(params),
+            DeviceClass::Rail(x) => x.params.set_params(params),
+        }
+    }
+    /// returns an error describing the problem if the device parameters would not netlist to a valid device
+    pub fn validate_params(&self) -> Result<(), String> {
+        match self {
+            DeviceClass::Gnd(x) => x.params.validate(),
+            DeviceClass::R(x) => x.params.validate(),
+            DeviceClass::V(x) => x.params.validate(),
+            DeviceClass::Pot(x) => x.params.validate(),
+            DeviceClass::M(x) => x.params.validate(),
+            DeviceClass::Rail(x) => x.params.validate(),
+        }
+    }
+    /// returns true for passive devices with a value of zero, e.g. a 0 ohm resistor
+    pub fn is_zero_value(&self) -> bool {
+        match self {
+            DeviceClass::R(x) => x.params.is_zero(),
+            _ => false,
         }
     }
     /// returns a reference to the device graphics
@@ -215,6 +314,9 @@ impl DeviceClass {
             DeviceClass::Gnd(x) => x.graphics,
             DeviceClass::R(x) => x.graphics,
             DeviceClass::V(x) => x.graphics,
+            DeviceClass::Pot(x) => x.graphics,
+            DeviceClass::M(x) => x.graphics,
+            DeviceClass::Rail(x) => x.graphics,
         }
     }
     /// returns a summary of the device parameter for display on canvas
@@ -229,6 +331,127 @@ impl DeviceClass {
             DeviceClass::V(x) => {
                 x.params.summary()
             },
+            DeviceClass::Pot(x) => {
+                x.params.summary()
+            },
+            DeviceClass::M(x) => {
+                x.params.summary()
+            },
+            DeviceClass::Rail(x) => {
+                x.params.summary()
+            },
+        }
+    }
+    /// returns the netlist lines of the device with identifier id, connected to nets in port order. 
+    /// Most devices are a single line, some are made up of several spice elements. Power rails only name their net and have none.
+    pub fn spice_lines(&self, id: &str, nets: &[String]) -> String {
+        match self {
+            DeviceClass::Pot(x) => x.params.spice_lines(id, nets),
+            DeviceClass::V(x) => x.params.spice_lines(id, nets),
+            DeviceClass::M(x) => x.spice_lines(id, nets),
+            DeviceClass::Rail(_) => String::new(),
+            _ => {
+                let mut sline = String::from(id);
+                for net in nets {
+                    sline.push(' ');
+                    sline.push_str(net);
+                }
+                format!("{} {}\n", sline, self.param_summary())
+            },
+        }
+    }
+    /// returns the model name and .model card the device refers to, if any. 
+    /// Devices sharing a model name share a single card in the netlist.
+    pub fn model_card(&self) -> Option<(String, String)> {
+        match self {
+            DeviceClass::M(x) => x.model_card(),
+            _ => None,
+        }
+    }
+    /// returns the name of the model the device refers to, if any
+    pub fn model_ref(&self) -> Option<&str> {
+        match self {
+            DeviceClass::M(x) => Some(&x.params.model),
+            _ => None,
+        }
+    }
+    /// returns the name of the device class for display, e.g. in the bill of materials
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeviceClass::Gnd(_) => "Ground",
+            DeviceClass::R(_) => "Resistor",
+            DeviceClass::V(_) => "Voltage Source",
+            DeviceClass::Pot(_) => "Potentiometer",
+            DeviceClass::Rail(_) => "Power Rail",
+            DeviceClass::M(x) => match (x.polarity, x.four_terminal) {
+                (m::Polarity::N, true) => "NMOS",
+                (m::Polarity::P, true) => "PMOS",
+                (m::Polarity::N, false) => "NMOS 3 terminal",
+                (m::Polarity::P, false) => "PMOS 3 terminal",
+            },
+        }
+    }
+    /// returns a new device of the class with the given name, see `name()`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Ground" => Some(DeviceClass::Gnd(gnd::Gnd::new())),
+            "Resistor" => Some(DeviceClass::R(r::R::new())),
+            "Voltage Source" => Some(DeviceClass::V(v::V::new())),
+            "Potentiometer" => Some(DeviceClass::Pot(pot::Pot::new())),
+            "Power Rail" => Some(DeviceClass::Rail(rail::Rail::new())),
+            "NMOS" => Some(DeviceClass::M(m::M::new(m::Polarity::N, true))),
+            "PMOS" => Some(DeviceClass::M(m::M::new(m::Polarity::P, true))),
+            "NMOS 3 terminal" => Some(DeviceClass::M(m::M::new(m::Polarity::N, false))),
+            "PMOS 3 terminal" => Some(DeviceClass::M(m::M::new(m::Polarity::P, false))),
+            _ => None,
+        }
+    }
+    /// returns true if the device class is a physical part which belongs in the bill of materials
+    pub fn is_part(&self) -> bool {
+        match self {
+            DeviceClass::Gnd(_) => false,
+            DeviceClass::R(_) => true,
+            DeviceClass::V(_) => false,
+            DeviceClass::Pot(_) => true,
+            DeviceClass::M(_) => true,
+            DeviceClass::Rail(_) => false,
+        }
+    }
+    /// returns the resistance of the device, if it is a resistor with a numeric value
+    pub fn resistance(&self) -> Option<f32> {
+        match self {
+            DeviceClass::R(x) => super::params::parse_spice_value(&x.params.summary()),
+            _ => None,
+        }
+    }
+    /// returns true if the device is symmetric, i.e. its ports may be swapped without changing the circuit
+    pub fn ports_swappable(&self) -> bool {
+        match self {
+            DeviceClass::Gnd(_) => false,
+            DeviceClass::R(_) => true,
+            DeviceClass::V(_) => false,
+            DeviceClass::Pot(_) => false,
+            DeviceClass::M(_) => false,
+            DeviceClass::Rail(_) => false,
+        }
+    }
+    /// returns true if the symbol may be turned 45 deg, see `Device::rotate_diagonal`. 
+    /// Turned ports are rounded to the grid, which only looks right for symbols with a port at each end of a straight lead.
+    pub fn rotates_diagonally(&self) -> bool {
+        match self {
+            DeviceClass::Gnd(_) => false,
+            DeviceClass::R(_) => true,
+            DeviceClass::V(_) => true,
+            DeviceClass::Pot(_) => false,
+            DeviceClass::M(_) => false,
+            DeviceClass::Rail(_) => false,
+        }
+    }
+    /// returns the name of the global net of a power rail, none for other devices
+    pub fn rail_name(&self) -> Option<&str> {
+        match self {
+            DeviceClass::Rail(x) => Some(&x.params.name),
+            _ => None,
         }
     }
     /// returns the id prefix of the device class
@@ -237,6 +460,108 @@ impl DeviceClass {
             DeviceClass::Gnd(_) => gnd::ID_PREFIX,
             DeviceClass::R(_) => r::ID_PREFIX,
             DeviceClass::V(_) => v::ID_PREFIX,
+            DeviceClass::Pot(_) => pot::ID_PREFIX,
+            DeviceClass::M(_) => m::ID_PREFIX,
+            DeviceClass::Rail(_) => rail::ID_PREFIX,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbol_scales_about_port_center() {
+        let json = r#"{"pts": [], "circles": [], "ports": [{"name": "gnd", "offset": [0, 2]}], "bounds": [[-1, 2], [1, -2]]}"#;
+        let mut graphics: Graphics = serde_json::from_str(json).unwrap();
+        assert_eq!(graphics.scale, 1.0);
+        assert_eq!(graphics.scaled(VSPoint::new(1., -2.)), VSPoint::new(1., -2.));
+        graphics.scale = 0.5;
+        assert_eq!(graphics.scaled(VSPoint::new(0., 2.)), VSPoint::new(0., 2.));
+        assert_eq!(graphics.scaled(VSPoint::new(1., -2.)), VSPoint::new(0.5, 0.));
+    }
+
+    fn class_with(name: &str, params: &[(&str, &str)]) -> DeviceClass {
+        let mut class = DeviceClass::from_name(name).unwrap();
+        let params: Vec<(String, String)> = params.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect();
+        class.set_params(&params);
+        class
+    }
+
+    #[test]
+    fn param_fields_map_back_to_their_parameters() {
+        for name in DeviceClass::NAMES {
+            let mut class = DeviceClass::from_name(name).unwrap();
+            let params = class.params();
+            class.set_params(&params);
+            assert_eq!(class.params(), params, "{}", name);
+            // editing one field leaves the others alone
+            let Some((first, _)) = params.first() else {continue};
+            class.set_params(&[(first.clone(), String::from("{x}"))]);
+            let edited = class.params();
+            assert_eq!(edited[0], (first.clone(), String::from("{x}")), "{}", name);
+            assert_eq!(edited[1..], params[1..], "{}", name);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn ground_params_are_valid() {
+        assert!(class_with("Ground", &[]).validate_params().is_ok());
+    }
+
+    #[test]
+    fn resistor_params_must_start_with_a_value() {
+        assert!(class_with("Resistor", &[]).validate_params().is_ok());
+        assert!(class_with("Resistor", &[("value", "4.7k tc1=0.01")]).validate_params().is_ok());
+        assert!(class_with("Resistor", &[("value", "{2*R}")]).validate_params().is_ok());
+        assert!(class_with("Resistor", &[("value", "  ")]).validate_params().is_err());
+        assert!(class_with("Resistor", &[("value", "abc")]).validate_params().is_err());
+    }
+
+    #[test]
+    fn mosfet_netlists_model_and_ties_bulk_of_3_terminal_devices() {
+        let nets: Vec<String> = ["d", "g", "s", "b"].iter().map(|n| n.to_string()).collect();
+        let nmos = class_with("NMOS", &[("model", "mynmos")]);
+        assert_eq!(nmos.spice_lines("M1", &nets), "M1 d g s b mynmos\n");
+        assert_eq!(nmos.model_card(), Some((String::from("mynmos"), String::from(".model mynmos nmos (level=1 vto=0.7 kp=110u)\n"))));
+        assert_eq!(nmos.graphics().ports().len(), 4);
+
+        let pmos = class_with("PMOS 3 terminal", &[]);
+        assert_eq!(pmos.name(), "PMOS 3 terminal");
+        assert_eq!(pmos.graphics().ports().len(), 3);
+        assert_eq!(pmos.spice_lines("M2", &nets[..3]), "M2 d g s s pmos1\n");
+        assert!(pmos.model_card().unwrap().1.contains(" pmos "));
+
+        assert_eq!(class_with("NMOS", &[("model_params", "")]).model_card(), None);
+        assert_eq!(class_with("NMOS", &[("model", "ext")]).model_ref(), Some("ext"));
+        assert!(class_with("NMOS", &[("model", "")]).validate_params().is_err());
+        assert!(class_with("NMOS", &[("model_params", "(vto=1)")]).validate_params().is_err());
+    }
+
+    #[test]
+    fn zero_ohm_resistors_are_zero_value() {
+        assert!(class_with("Resistor", &[("value", "0")]).is_zero_value());
+        assert!(class_with("Resistor", &[("value", "0k tc1=0.01")]).is_zero_value());
+        assert!(!class_with("Resistor", &[("value", "1m")]).is_zero_value());
+        assert!(!class_with("Resistor", &[("value", "{r0}")]).is_zero_value());
+        assert!(!class_with("Voltage Source", &[("value", "0")]).is_zero_value());
+    }
+
+    #[test]
+    fn voltage_source_params_must_be_balanced() {
+        assert!(class_with("Voltage Source", &[]).validate_params().is_ok());
+        assert!(class_with("Voltage Source", &[("value", "sin(0 1 1k)")]).validate_params().is_ok());
+        assert!(class_with("Voltage Source", &[("value", "")]).validate_params().is_err());
+        assert!(class_with("Voltage Source", &[("value", "sin(0 1 1k")]).validate_params().is_err());
+        assert!(class_with("Voltage Source", &[("value", "dc 1)")]).validate_params().is_err());
+    }
+
+    #[test]
+    fn potentiometer_resistance_must_be_positive() {
+        assert!(class_with("Potentiometer", &[]).validate_params().is_ok());
+        assert!(class_with("Potentiometer", &[("resistance", "{rpot}")]).validate_params().is_ok());
+        assert!(class_with("Potentiometer", &[("resistance", "0")]).validate_params().is_err());
+        assert!(class_with("Potentiometer", &[("resistance", "-1k")]).validate_params().is_err());
+    }
+}
@@ -23,10 +23,11 @@ lazy_static! {
             Port {name: "gnd".to_string(), offset: SSPoint::new(0, 2)}
         ], 
         bounds: SSBox::new(SSPoint::new(-1, 2), SSPoint::new(1, -2)), 
+        scale: 1.0,
     };
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ParamGnd  {
     None,
 }
@@ -39,9 +40,17 @@ impl ParamGnd {
     pub fn summary(&self) -> String {
         String::from("0 0")
     }
+    /// returns the parameters as (name, value) pairs for editing - ground has none
+    pub fn params(&self) -> Vec<(String, String)> {
+        vec![]
+    }
+    /// ground has no parameters, always valid
+    pub fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Gnd {
     pub params: ParamGnd,
     pub graphics: &'static Graphics,
@@ -0,0 +1,152 @@
+use crate::transforms::{SSPoint, VSPoint, SSBox};
+use super::{Graphics, Port};
+use lazy_static::lazy_static;
+
+pub const ID_PREFIX: &str = "M";
+
+/// symbol lines shared by every mosfet symbol: gate, channel, drain and source leads
+fn common_pts() -> Vec<Vec<VSPoint>> {
+    vec![
+        // gate
+        vec![VSPoint::new(-2., 0.), VSPoint::new(-1., 0.)],
+        vec![VSPoint::new(-1., 1.5), VSPoint::new(-1., -1.5)],
+        // enhancement mode channel
+        vec![VSPoint::new(0., 1.5), VSPoint::new(0., 0.5)],
+        vec![VSPoint::new(0., 0.25), VSPoint::new(0., -0.25)],
+        vec![VSPoint::new(0., -0.5), VSPoint::new(0., -1.5)],
+        // drain
+        vec![VSPoint::new(0., 1.), VSPoint::new(1., 1.), VSPoint::new(1., 3.)],
+        // source
+        vec![VSPoint::new(0., -1.), VSPoint::new(1., -1.), VSPoint::new(1., -3.)],
+    ]
+}
+
+/// returns the symbol of a mosfet. The bulk arrow points into the channel for n-channel devices.
+/// 4 terminal devices have a bulk port, 3 terminal devices have the bulk tied to the source.
+fn graphics(polarity: Polarity, four_terminal: bool) -> Graphics {
+    let mut pts = common_pts();
+    pts.push(match polarity {
+        Polarity::N => vec![VSPoint::new(0.75, 0.4), VSPoint::new(0.25, 0.), VSPoint::new(0.75, -0.4)],
+        Polarity::P => vec![VSPoint::new(0.25, 0.4), VSPoint::new(0.75, 0.), VSPoint::new(0.25, -0.4)],
+    });
+    let mut ports = vec![
+        Port {name: "d".to_string(), offset: SSPoint::new(1, 3)},
+        Port {name: "g".to_string(), offset: SSPoint::new(-2, 0)},
+        Port {name: "s".to_string(), offset: SSPoint::new(1, -3)},
+    ];
+    if four_terminal {
+        pts.push(vec![VSPoint::new(0., 0.), VSPoint::new(2., 0.)]);
+        ports.push(Port {name: "b".to_string(), offset: SSPoint::new(2, 0)});
+    } else {
+        pts.push(vec![VSPoint::new(0., 0.), VSPoint::new(1., 0.), VSPoint::new(1., -1.)]);
+    }
+    Graphics {
+        pts,
+        circles: vec![],
+        ports,
+        bounds: SSBox::new(SSPoint::new(-2, 3), SSPoint::new(2, -3)),
+        scale: 1.0,
+    }
+}
+
+lazy_static! {
+    static ref NMOS_GRAPHICS: Graphics = graphics(Polarity::N, true);
+    static ref PMOS_GRAPHICS: Graphics = graphics(Polarity::P, true);
+    static ref NMOS3_GRAPHICS: Graphics = graphics(Polarity::N, false);
+    static ref PMOS3_GRAPHICS: Graphics = graphics(Polarity::P, false);
+}
+
+/// channel type of a mosfet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Polarity {
+    N,
+    P,
+}
+
+/// mosfet parameters: the model name and the parameters of its .model card
+#[derive(Debug, Clone)]
+pub struct ParamM {
+    /// name of the model, shared by every mosfet with the same model card
+    pub model: String,
+    /// parameters of the .model card, e.g. level=1 vto=0.7 kp=110u.
+    /// Empty if the model is defined by a .model or .include control statement instead.
+    pub model_params: String,
+}
+impl ParamM {
+    fn new(polarity: Polarity) -> Self {
+        match polarity {
+            Polarity::N => ParamM { model: String::from("nmos1"), model_params: String::from("level=1 vto=0.7 kp=110u") },
+            Polarity::P => ParamM { model: String::from("pmos1"), model_params: String::from("level=1 vto=-0.7 kp=50u") },
+        }
+    }
+    pub fn summary(&self) -> String {
+        self.model.clone()
+    }
+    /// returns the parameters as (name, value) pairs for editing
+    pub fn params(&self) -> Vec<(String, String)> {
+        vec![
+            (String::from("model"), self.model.clone()),
+            (String::from("model_params"), self.model_params.clone()),
+        ]
+    }
+    /// sets the parameters from (name, value) pairs. Unknown names are ignored.
+    pub fn set_params(&mut self, params: &[(String, String)]) {
+        for (name, value) in params {
+            match name.as_str() {
+                "model" => self.model = value.trim().to_string(),
+                "model_params" => self.model_params = value.trim().to_string(),
+                _ => {},
+            }
+        }
+    }
+    /// returns an error describing the problem if the parameters would not netlist to a valid mosfet
+    pub fn validate(&self) -> Result<(), String> {
+        if self.model.is_empty() {
+            return Err(String::from("model is empty"));
+        }
+        if self.model.contains(char::is_whitespace) {
+            return Err(format!("model {} contains whitespace", self.model));
+        }
+        if self.model_params.contains(['(', ')']) {
+            return Err(String::from("model_params must not contain parentheses"));
+        }
+        Ok(())
+    }
+}
+
+/// mosfet device class
+#[derive(Debug, Clone)]
+pub struct M {
+    pub params: ParamM,
+    pub polarity: Polarity,
+    /// if false, the bulk is tied to the source and the device has no bulk port
+    pub four_terminal: bool,
+    pub graphics: &'static Graphics,
+}
+impl M {
+    pub fn new(polarity: Polarity, four_terminal: bool) -> M {
+        let graphics: &'static Graphics = match (polarity, four_terminal) {
+            (Polarity::N, true) => &NMOS_GRAPHICS,
+            (Polarity::P, true) => &PMOS_GRAPHICS,
+            (Polarity::N, false) => &NMOS3_GRAPHICS,
+            (Polarity::P, false) => &PMOS3_GRAPHICS,
+        };
+        M {params: ParamM::new(polarity), polarity, four_terminal, graphics}
+    }
+    /// returns the netlist line of the mosfet. nets are the nets connected to the drain, gate, source and, if 4 terminal, bulk ports.
+    pub fn spice_lines(&self, id: &str, nets: &[String]) -> String {
+        let bulk = if self.four_terminal {&nets[3]} else {&nets[2]};
+        format!("{} {} {} {} {} {}\n", id, nets[0], nets[1], nets[2], bulk, self.params.model)
+    }
+    /// returns the model name and the .model card of the mosfet, none if the model is defined elsewhere
+    pub fn model_card(&self) -> Option<(String, String)> {
+        if self.params.model_params.is_empty() {
+            return None;
+        }
+        let kind = match self.polarity {
+            Polarity::N => "nmos",
+            Polarity::P => "pmos",
+        };
+        Some((self.params.model.clone(), format!(".model {} {} ({})\n", self.params.model, kind, self.params.model_params)))
+    }
+}
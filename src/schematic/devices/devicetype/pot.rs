@@ -0,0 +1,124 @@
+use crate::transforms::{SSPoint, VSPoint, SSBox};
+use super::{Graphics, Port};
+use super::super::params;
+use lazy_static::lazy_static;
+
+pub const ID_PREFIX: &str = "RPOT";
+
+lazy_static! {
+    static ref DEFAULT_GRAPHICS: Graphics = Graphics {
+        pts: vec![
+            vec![
+                VSPoint::new(0., 3.),
+                VSPoint::new(0., -3.),
+            ],
+            vec![
+                VSPoint::new(-1., 2.),
+                VSPoint::new(-1., -2.),
+                VSPoint::new(1., -2.),
+                VSPoint::new(1., 2.),
+                VSPoint::new(-1., 2.),
+            ],
+            // wiper arrow
+            vec![
+                VSPoint::new(3., 0.),
+                VSPoint::new(1., 0.),
+            ],
+            vec![
+                VSPoint::new(1.75, 0.5),
+                VSPoint::new(1., 0.),
+                VSPoint::new(1.75, -0.5),
+            ],
+        ],
+        circles: vec![],
+        ports: vec![
+            Port {name: "+".to_string(), offset: SSPoint::new(0, 3)},
+            Port {name: "-".to_string(), offset: SSPoint::new(0, -3)},
+            Port {name: "w".to_string(), offset: SSPoint::new(3, 0)},
+        ],
+        bounds: SSBox::new(SSPoint::new(-2, 3), SSPoint::new(3, -3)),
+        scale: 1.0,
+    };
+}
+
+/// least resistance of either side of the wiper, spice rejects zero ohm resistors
+const MIN_RESISTANCE: f32 = 1e-3;
+
+/// potentiometer parameters: the total resistance and the wiper position
+#[derive(Debug, Clone)]
+pub struct ParamPot {
+    /// resistance between the + and - ports
+    pub resistance: params::ParamValue,
+    /// wiper position from the - port (0) to the + port (1)
+    pub position: f32,
+}
+impl Default for ParamPot {
+    fn default() -> Self {
+        ParamPot { resistance: params::ParamValue::Number(10e3), position: 0.5 }
+    }
+}
+impl ParamPot {
+    pub fn summary(&self) -> String {
+        format!("{} {}", self.resistance.spice(), self.position)
+    }
+    /// returns the parameters as (name, value) pairs for editing
+    pub fn params(&self) -> Vec<(String, String)> {
+        vec![
+            (String::from("resistance"), self.resistance.spice()),
+            (String::from("position"), self.position.to_string()),
+        ]
+    }
+    /// sets the parameters from (name, value) pairs. Unknown names and invalid values are ignored.
+    pub fn set_params(&mut self, params: &[(String, String)]) {
+        for (name, value) in params {
+            match name.as_str() {
+                "resistance" => if let Some(pv) = params::ParamValue::parse(value) {self.resistance = pv},
+                "position" => if let Ok(p) = value.trim().parse::<f32>() {self.position = p.clamp(0.0, 1.0)},
+                _ => {},
+            }
+        }
+    }
+    /// returns an error describing the problem if the parameters would not netlist to valid resistors
+    pub fn validate(&self) -> Result<(), String> {
+        match self.resistance {
+            params::ParamValue::Number(r) if r <= 0.0 => Err(format!("resistance {} is not positive", r)),
+            _ => Ok(()),
+        }
+    }
+    /// returns the resistance between the wiper and the + port, and between the wiper and the - port. 
+    /// Neither is less than MIN_RESISTANCE, also with the wiper at either end.
+    fn split(&self) -> (String, String) {
+        match &self.resistance {
+            params::ParamValue::Number(r) => (
+                params::format_spice_value((r * (1.0 - self.position)).max(MIN_RESISTANCE)),
+                params::format_spice_value((r * self.position).max(MIN_RESISTANCE)),
+            ),
+            params::ParamValue::Expr(e) => {
+                let e = &e[1..e.len() - 1];
+                let leg = |fraction: f32| format!("{{max(({})*{}, {})}}", e, fraction, MIN_RESISTANCE);
+                (leg(1.0 - self.position), leg(self.position))
+            },
+        }
+    }
+    /// returns the netlist lines of the potentiometer: one resistor from + to the wiper and one from the wiper to -.
+    /// nets are the nets connected to the +, - and wiper ports.
+    pub fn spice_lines(&self, id: &str, nets: &[String]) -> String {
+        let (upper, lower) = self.split();
+        format!(
+            "{id}a {} {} {upper}\n{id}b {} {} {lower}\n",
+            nets[0], nets[2], nets[2], nets[1],
+        )
+    }
+}
+
+/// potentiometer device class
+#[derive(Debug, Clone)]
+pub struct Pot {
+    pub params: ParamPot,
+    pub graphics: &'static Graphics,
+}
+impl Pot {
+    pub fn new() -> Pot {
+        Pot {params: ParamPot::default(), graphics: &DEFAULT_GRAPHICS}
+    }
+}
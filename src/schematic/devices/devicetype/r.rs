@@ -27,12 +27,13 @@ lazy_static! {
             Port {name: "-".to_string(), offset: SSPoint::new(0, -3)},
         ], 
         bounds: SSBox::new(SSPoint::new(-2, 3), SSPoint::new(2, -3)), 
+        scale: 1.0,
     };
 }
 
 
 /// Enumerates the different ways to specifify parameters for a resistor
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ParamR  {
     /// specify the spice line directly (after id and port connections)
     Raw(params::Raw),
@@ -48,13 +49,50 @@ impl ParamR {
     pub fn summary(&self) -> String {
         match self {
             ParamR::Value(v) => {
-                std::format!("{}", v.value)
+                v.value.spice()
             },
             ParamR::Raw(s) => {
                 s.raw.clone()
             },
         }
     }
+    /// returns the parameters as (name, value) pairs for editing
+    pub fn params(&self) -> Vec<(String, String)> {
+        vec![(String::from("value"), self.summary())]
+    }
+    /// sets the parameters from (name, value) pairs. Unknown names are ignored.
+    /// A value which is a single number or expression is kept as such, anything else as raw spice line.
+    pub fn set_params(&mut self, params: &[(String, String)]) {
+        for (name, value) in params {
+            if name != "value" {
+                continue;
+            }
+            *self = match params::ParamValue::parse(value) {
+                Some(pv) if matches!(pv, params::ParamValue::Expr(_)) || value.split_whitespace().count() == 1 => {
+                    ParamR::Value(params::SingleValue::new(pv))
+                },
+                _ => ParamR::Raw(params::Raw::new(value.clone())),
+            };
+        }
+    }
+    /// returns an error describing the problem if the parameters would not netlist to a valid resistor
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ParamR::Value(_) => Ok(()),
+            ParamR::Raw(s) => {
+                let value = s.raw.split_whitespace().next().ok_or("value is empty")?;
+                params::ParamValue::parse(value).map(|_| ()).ok_or(format!("value {} is not a number or expression", value))
+            },
+        }
+    }
+    /// returns true if the resistance is a number equal to zero
+    pub fn is_zero(&self) -> bool {
+        let value = match self {
+            ParamR::Value(v) => Some(v.value.clone()),
+            ParamR::Raw(s) => s.raw.split_whitespace().next().and_then(params::ParamValue::parse),
+        };
+        value == Some(params::ParamValue::Number(0.0))
+    }
     pub fn param_editor(&mut self) -> Option<impl ParamEditor + Into<Element<()>>> {
         None::<param_editor::RawParamEditor>
         // match self {
@@ -67,7 +105,7 @@ impl ParamR {
 }
 
 /// resistor device class
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct R {
     /// parameters of the resistor
     pub params: ParamR,
@@ -162,4 +200,24 @@ mod param_editor {
             component(parameditor)
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParamR, params::ParamValue};
+
+    #[test]
+    fn single_values_are_kept_as_numbers_or_expressions() {
+        let mut params = ParamR::default();
+        params.set_params(&[(String::from("value"), String::from("4.7K"))]);
+        assert!(matches!(&params, ParamR::Value(v) if v.value == ParamValue::Number(4.7e3)));
+        assert_eq!(params.summary(), "4.7k");
+        params.set_params(&[(String::from("value"), String::from("{2*R}"))]);
+        assert!(matches!(&params, ParamR::Value(v) if v.value == ParamValue::Expr(String::from("{2*R}"))));
+        params.set_params(&[(String::from("value"), String::from("1k tc1=0.01"))]);
+        assert!(matches!(&params, ParamR::Raw(_)));
+        assert_eq!(params.summary(), "1k tc1=0.01");
+        params.set_params(&[(String::from("value"), String::from("0"))]);
+        assert!(params.is_zero());
+    }
+}
@@ -0,0 +1,95 @@
+//! power rail symbol
+//! a named global net, e.g. VCC. Every net touching a rail of the same name is the same node in the netlist, without wires between them.
+
+use crate::transforms::{SSPoint, VSPoint, SSBox};
+use super::{Graphics, Port};
+use lazy_static::lazy_static;
+
+pub const ID_PREFIX: &str = "PWR";
+
+lazy_static! {
+    static ref DEFAULT_GRAPHICS: Graphics = Graphics { 
+        pts: vec![
+            vec![
+                VSPoint::new(0., -2.),
+                VSPoint::new(0., 1.)
+            ],
+            vec![
+                VSPoint::new(-1., 1.),
+                VSPoint::new(1., 1.),
+            ],
+        ],
+        circles: vec![],
+        ports: vec![
+            Port {name: "rail".to_string(), offset: SSPoint::new(0, -2)}
+        ], 
+        bounds: SSBox::new(SSPoint::new(-1, -2), SSPoint::new(1, 2)), 
+        scale: 1.0,
+    };
+}
+
+/// name of the global net of a power rail
+#[derive(Debug, Clone)]
+pub struct ParamRail {
+    pub name: String,
+}
+impl Default for ParamRail {
+    fn default() -> Self {
+        ParamRail { name: String::from("VCC") }
+    }
+}
+impl ParamRail {
+    pub fn summary(&self) -> String {
+        self.name.clone()
+    }
+    /// returns the parameters as (name, value) pairs for editing
+    pub fn params(&self) -> Vec<(String, String)> {
+        vec![(String::from("name"), self.name.clone())]
+    }
+    /// sets the parameters from (name, value) pairs. Unknown names are ignored.
+    pub fn set_params(&mut self, params: &[(String, String)]) {
+        for (name, value) in params {
+            if name == "name" {
+                self.name = value.trim().to_string();
+            }
+        }
+    }
+    /// returns an error describing the problem if the rail name is not a valid spice node name
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err(String::from("name is empty"));
+        }
+        if self.name.contains(|c: char| c.is_whitespace() || "()=,".contains(c)) {
+            return Err(format!("{} is not a valid net name", self.name));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rail {
+    pub params: ParamRail,
+    pub graphics: &'static Graphics,
+}
+impl Rail {
+    pub fn new() -> Rail {
+        Rail {params: ParamRail::default(), graphics: &DEFAULT_GRAPHICS}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rail_names_must_be_node_names() {
+        let mut params = ParamRail::default();
+        assert!(params.validate().is_ok());
+        params.set_params(&[(String::from("name"), String::from(" VDD "))]);
+        assert_eq!(params.summary(), "VDD");
+        params.set_params(&[(String::from("name"), String::from("V DD"))]);
+        assert!(params.validate().is_err());
+        params.set_params(&[(String::from("name"), String::new())]);
+        assert!(params.validate().is_err());
+    }
+}
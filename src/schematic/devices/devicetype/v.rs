@@ -8,26 +8,115 @@ lazy_static! {
     static ref DEFAULT_GRAPHICS: Graphics = serde_json::from_slice(&std::fs::read("src/schematic/devices/devicetype/v.json").unwrap()).unwrap();
 }
 
-#[derive(Debug)]
-pub enum ParamV  {
-    Raw(params::Raw),
+/// voltage source parameters: the dc value or source specification, and the optional ac analysis magnitude and phase
+#[derive(Debug, Clone)]
+pub struct ParamV {
+    /// dc value, or a source specification netlisted as is, e.g. `sin(0 1 1k)`
+    pub value: params::Raw,
+    /// ac analysis magnitude, blank if the source has no ac component
+    pub ac_magnitude: params::Raw,
+    /// ac analysis phase in degrees, blank for 0
+    pub ac_phase: params::Raw,
 }
 impl Default for ParamV {
     fn default() -> Self {
-        ParamV::Raw(params::Raw::new(String::from("3.3")))
+        ParamV {
+            value: params::Raw::new(String::from("3.3")),
+            ac_magnitude: params::Raw::new(String::new()),
+            ac_phase: params::Raw::new(String::new()),
+        }
     }
 }
 impl ParamV {
+    /// returns the value, followed by the ac magnitude and phase if the source has an ac component
     pub fn summary(&self) -> String {
-        match self {
-            ParamV::Raw(s) => {
-                s.raw.clone()
-            },
+        let value = self.value.raw.trim();
+        match self.ac() {
+            Some((magnitude, phase)) => format!("{} AC {} {}°", value, magnitude, phase),
+            None => value.to_string(),
+        }
+    }
+    /// returns the ac magnitude and phase, None if the source has no ac component
+    fn ac(&self) -> Option<(&str, &str)> {
+        let magnitude = self.ac_magnitude.raw.trim();
+        if magnitude.is_empty() {
+            return None;
+        }
+        let phase = self.ac_phase.raw.trim();
+        Some((magnitude, if phase.is_empty() {"0"} else {phase}))
+    }
+    /// returns the parameters as (name, value) pairs for editing
+    pub fn params(&self) -> Vec<(String, String)> {
+        vec![
+            (String::from("value"), self.value.raw.clone()),
+            (String::from("ac_magnitude"), self.ac_magnitude.raw.clone()),
+            (String::from("ac_phase"), self.ac_phase.raw.clone()),
+        ]
+    }
+    /// returns the netlist line of the source with identifier id, connected to nets. 
+    /// Sources without an ac component netlist their value as is, otherwise a numeric value is marked as the dc value, 
+    /// e.g. `V1 n1 n2 DC 0 AC 1 0`.
+    pub fn spice_lines(&self, id: &str, nets: &[String]) -> String {
+        let value = self.value.raw.trim();
+        let spec = match self.ac() {
+            Some((magnitude, phase)) if params::parse_spice_value(value).is_some() => format!("DC {} AC {} {}", value, magnitude, phase),
+            Some((magnitude, phase)) => format!("{} AC {} {}", value, magnitude, phase),
+            None => value.to_string(),
+        };
+        format!("{} {} {}\n", id, nets.join(" "), spec)
+    }
+    /// returns an error describing the problem if the parameters would not netlist to a valid voltage source. 
+    /// Source specifications take many forms, e.g. `dc 1 ac 1` or `sin(0 1 1k)`, only their general shape is checked.
+    pub fn validate(&self) -> Result<(), String> {
+        let raw = &self.value.raw;
+        if raw.trim().is_empty() {
+            return Err(String::from("value is empty"));
+        }
+        let mut depth = 0;
+        for c in raw.chars() {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => return Err(String::from("unbalanced parentheses")),
+                ')' => depth -= 1,
+                _ => {},
+            }
+        }
+        if depth != 0 {
+            return Err(String::from("unbalanced parentheses"));
+        }
+        let magnitude = self.ac_magnitude.raw.trim();
+        let phase = self.ac_phase.raw.trim();
+        if magnitude.is_empty() {
+            if !phase.is_empty() {
+                return Err(String::from("ac phase without ac magnitude"));
+            }
+            return Ok(());
+        }
+        if raw.split_whitespace().any(|w| w.eq_ignore_ascii_case("ac")) {
+            return Err(String::from("ac is set in both value and ac_magnitude"));
+        }
+        if params::parse_spice_value(magnitude).is_none() {
+            return Err(format!("ac magnitude {} is not a number", magnitude));
+        }
+        if !phase.is_empty() && params::parse_spice_value(phase).is_none() {
+            return Err(format!("ac phase {} is not a number", phase));
+        }
+        Ok(())
+    }
+    /// sets the parameters from (name, value) pairs. Unknown names are ignored.
+    pub fn set_params(&mut self, params: &[(String, String)]) {
+        for (name, value) in params {
+            match name.as_str() {
+                "value" => self.value.set(value.clone()),
+                "ac_magnitude" => self.ac_magnitude.set(value.clone()),
+                "ac_phase" => self.ac_phase.set(value.clone()),
+                _ => {},
+            }
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct V {
     pub params: ParamV,
     pub graphics: &'static Graphics,
@@ -47,6 +136,36 @@ mod tests {
         std::fs::write("src/schematic/devices/devicetype/v.json", serde_json::to_string_pretty(&out).unwrap().as_bytes()).expect("Unable to write file");
     }
 
+    #[test]
+    fn ac_sources_mark_their_dc_value() {
+        let nets = [String::from("n1"), String::from("n2")];
+        let mut params = super::ParamV::default();
+        assert_eq!(params.spice_lines("V1", &nets), "V1 n1 n2 3.3\n");
+        params.set_params(&[(String::from("value"), String::from("0")), (String::from("ac_magnitude"), String::from("1"))]);
+        assert!(params.validate().is_ok());
+        assert_eq!(params.spice_lines("V1", &nets), "V1 n1 n2 DC 0 AC 1 0\n");
+        params.set_params(&[(String::from("value"), String::from("sin(0 1 1k)")), (String::from("ac_phase"), String::from("90"))]);
+        assert_eq!(params.spice_lines("V1", &nets), "V1 n1 n2 sin(0 1 1k) AC 1 90\n");
+        assert_eq!(params.summary(), "sin(0 1 1k) AC 1 90°");
+
+        params.set_params(&[(String::from("value"), String::from("dc 0 ac 1"))]);
+        assert!(params.validate().is_err());
+        params.set_params(&[(String::from("value"), String::from("0")), (String::from("ac_magnitude"), String::from(""))]);
+        assert!(params.validate().is_err(), "phase without magnitude");
+    }
+
+    #[test]
+    fn value_is_trimmed_with_and_without_ac() {
+        let nets = [String::from("n1"), String::from("n2")];
+        let mut params = super::ParamV::default();
+        params.set_params(&[(String::from("value"), String::from(" 5 ")), (String::from("ac_magnitude"), String::from(""))]);
+        assert_eq!(params.spice_lines("V1", &nets), "V1 n1 n2 5\n");
+        assert_eq!(params.summary(), "5");
+        params.set_params(&[(String::from("value"), String::from(" 5 ")), (String::from("ac_magnitude"), String::from("1"))]);
+        assert_eq!(params.spice_lines("V1", &nets), "V1 n1 n2 DC 5 AC 1 0\n");
+        assert_eq!(params.summary(), "5 AC 1 0°");
+    }
+
     fn parse() {
         let a = std::fs::read("src/schematic/devices/devicetype/v.json").unwrap();
         let b: super::Graphics = serde_json::from_slice(&a).unwrap();
@@ -3,7 +3,7 @@
 //! a device should be able to choose between all compatible parameter specifier
 
 /// this struct to edit device parameters by specifying the spice netlist line (after port connects) directly
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Raw  {
     pub raw: String,
 }
@@ -17,12 +17,161 @@ impl Raw {
 }
 
 /// this struct to edit device paramters by specying a single characteristic value (resistance, capacitance, inductance)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SingleValue  {
-    pub value: f32,
+    pub value: ParamValue,
 }
 impl SingleValue {
-    fn new(value: f32) -> Self {
+    pub fn new(value: ParamValue) -> Self {
         SingleValue { value }
     }
-}
\ No newline at end of file
+}
+
+/// a single parameter value: either a number, or an expression evaluated by ngspice, e.g. `{2*R}`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamValue {
+    Number(f32),
+    /// expression including its enclosing braces or quotes
+    Expr(String),
+}
+impl ParamValue {
+    /// parses a spice number, see `parse_spice_value`, or an expression enclosed in braces or single quotes.
+    /// Returns none for anything else.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if (s.starts_with('{') && s.ends_with('}') || s.starts_with('\'') && s.ends_with('\'')) && s.len() > 2 {
+            return Some(ParamValue::Expr(s.to_string()));
+        }
+        parse_spice_value(s).map(ParamValue::Number)
+    }
+    /// returns the value as it appears in the netlist
+    pub fn spice(&self) -> String {
+        match self {
+            ParamValue::Number(v) => format_spice_value(*v),
+            ParamValue::Expr(e) => e.clone(),
+        }
+    }
+}
+
+/// formats v as a spice number with scale suffix, e.g. `10k`, `2.2u`, `1meg`. See `parse_spice_value`.
+pub fn format_spice_value(v: f32) -> String {
+    const SCALES: [(i32, &str); 10] = [(12, "t"), (9, "g"), (6, "meg"), (3, "k"), (0, ""), (-3, "m"), (-6, "u"), (-9, "n"), (-12, "p"), (-15, "f")];
+    if v == 0.0 || !v.is_finite() {
+        return v.to_string();
+    }
+    let exp = v.abs().log10().floor() as i32;
+    let Some((scale, suffix)) = SCALES.iter().find(|(scale, _)| exp >= *scale) else {return v.to_string()};
+    let mantissa = v as f64 / 10f64.powi(*scale);
+    // f32 carries about 7 significant figures, the mantissa has up to 3 integer digits
+    let s = format!("{:.*}", (6 - (exp - scale)).max(0) as usize, mantissa);
+    let s = if s.contains('.') {s.trim_end_matches('0').trim_end_matches('.')} else {&s};
+    format!("{}{}", s, suffix)
+}
+
+/// parses a spice number with optional scale suffix, e.g. `10k`, `2.2u`, `1meg`. Trailing units are ignored, e.g. `10kohm`.
+/// Returns none if the string does not start with a number.
+pub fn parse_spice_value(s: &str) -> Option<f32> {
+    let s = s.trim().to_ascii_lowercase();
+    if !s.starts_with(|c: char| c.is_ascii_digit() || c == '.' || c == '-' || c == '+') {
+        return None;
+    }
+    // longest prefix which is a number, the rest is the scale suffix and units
+    let (value, suffix) = (1..=s.len()).rev()
+        .filter(|i| s.is_char_boundary(*i))
+        .find_map(|i| s[..i].parse::<f64>().ok().filter(|v| v.is_finite()).map(|v| (v, &s[i..])))?;
+    let scale = if suffix.starts_with("meg") {
+        1e6
+    } else if suffix.starts_with("mil") {
+        25.4e-6
+    } else {
+        match suffix.chars().next() {
+            Some('t') => 1e12,
+            Some('g') => 1e9,
+            Some('k') => 1e3,
+            Some('m') => 1e-3,
+            Some('u') => 1e-6,
+            Some('n') => 1e-9,
+            Some('p') => 1e-12,
+            Some('f') => 1e-15,
+            _ => 1.0,
+        }
+    };
+    Some((value * scale) as f32)
+}
+
+/// returns true if a and b are the same value: equal numbers however written, e.g. `10k`, `10K` and `10000`, 
+/// or else the same text regardless of case and surrounding whitespace
+pub fn same_value(a: &str, b: &str) -> bool {
+    let (a, b) = (a.trim(), b.trim());
+    let single = |s: &str| s.split_whitespace().count() == 1;
+    match (parse_spice_value(a), parse_spice_value(b)) {
+        (Some(x), Some(y)) if single(a) && single(b) => x == y || (x - y).abs() <= 1e-6 * x.abs().max(y.abs()),
+        _ => a.eq_ignore_ascii_case(b),
+    }
+}
+
+/// formats v with sig significant figures. Very large or small magnitudes are formatted in scientific notation.
+pub fn format_significant(v: f32, sig: usize) -> String {
+    let sig = sig.max(1);
+    if v == 0.0 || !v.is_finite() {
+        return v.to_string();
+    }
+    let exp = v.abs().log10().floor() as i32;
+    if !(-3..6).contains(&exp) {
+        format!("{:.*e}", sig - 1, v)
+    } else {
+        format!("{:.*}", (sig as i32 - 1 - exp).max(0) as usize, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_spice_value, format_spice_value, format_significant, ParamValue};
+
+    #[test]
+    fn spice_values() {
+        assert_eq!(parse_spice_value("1000"), Some(1000.0));
+        assert_eq!(parse_spice_value("10k"), Some(10e3));
+        assert_eq!(parse_spice_value("1meg"), Some(1e6));
+        assert_eq!(parse_spice_value("2.2u"), Some(2.2e-6));
+        assert_eq!(parse_spice_value("1e3"), Some(1e3));
+        assert_eq!(parse_spice_value(" -3.3V "), Some(-3.3));
+        assert_eq!(parse_spice_value("10kohm"), Some(10e3));
+        assert_eq!(parse_spice_value("dc 1"), None);
+        assert_eq!(parse_spice_value("nan"), None);
+    }
+
+    #[test]
+    fn spice_values_are_formatted_with_scale_suffix() {
+        for (v, s) in [(4.7e3, "4.7k"), (2.2e-6, "2.2u"), (1e6, "1meg"), (-3.3, "-3.3"), (100e-3, "100m"), (1000.0, "1k"), (0.0, "0"), (123456.7, "123.4567k")] {
+            assert_eq!(format_spice_value(v), s);
+            assert_eq!(parse_spice_value(s), Some(v), "{}", s);
+        }
+    }
+
+    #[test]
+    fn param_values() {
+        assert_eq!(ParamValue::parse("10k"), Some(ParamValue::Number(10e3)));
+        assert_eq!(ParamValue::parse("-3.3"), Some(ParamValue::Number(-3.3)));
+        assert_eq!(ParamValue::parse("-1meg"), Some(ParamValue::Number(-1e6)));
+        assert_eq!(ParamValue::parse(" {2*R} "), Some(ParamValue::Expr(String::from("{2*R}"))));
+        assert_eq!(ParamValue::parse("'vdd/2'"), Some(ParamValue::Expr(String::from("'vdd/2'"))));
+        assert_eq!(ParamValue::parse("{}"), None);
+        assert_eq!(ParamValue::parse("{2*R"), None);
+        assert_eq!(ParamValue::parse("abc"), None);
+
+        assert_eq!(ParamValue::Number(-3.3).spice(), "-3.3");
+        assert_eq!(ParamValue::Number(10e3).spice(), "10k");
+        assert_eq!(ParamValue::Expr(String::from("{2*R}")).spice(), "{2*R}");
+    }
+
+    #[test]
+    fn significant_figures() {
+        assert_eq!(format_significant(1.23456, 3), "1.23");
+        assert_eq!(format_significant(-12.3456, 3), "-12.3");
+        assert_eq!(format_significant(4999.9, 3), "5000");
+        assert_eq!(format_significant(0.0123456, 2), "0.012");
+        assert_eq!(format_significant(1.5e-6, 3), "1.50e-6");
+        assert_eq!(format_significant(0.0, 3), "0");
+    }
+}
@@ -0,0 +1,104 @@
+//! saving and loading schematics
+//! schematics are saved as json, listing every device and wire segment. Net names are not saved, they are regenerated on load.
+
+use std::{fs, io, collections::BTreeMap};
+
+use crate::transforms::{SSPoint, SSTransform, VSPoint};
+use super::{SimConfig, probe::Probe, nets::NetAssignments, ArrowAnnotation};
+
+/// current version of the file format
+pub const VERSION: u32 = 1;
+
+/// a device as saved to file
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct DeviceRecord {
+    /// device class name, e.g. Resistor
+    pub class: String,
+    /// device identifier ordinal
+    pub wm: usize,
+    /// user defined identifier, if any
+    pub custom_id: Option<String>,
+    /// position and orientation of the device
+    pub transform: SSTransform,
+    /// device parameters as (name, value) pairs
+    pub params: Vec<(String, String)>,
+    /// non-electrical attributes
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+    /// netlist order of the class ports, empty for the default order
+    #[serde(default)]
+    pub port_order: Vec<usize>,
+    #[serde(default)]
+    pub locked: bool,
+    /// disabled devices are commented out in the netlist
+    #[serde(default)]
+    pub disabled: bool,
+    /// turned 45 deg ahead of transform
+    #[serde(default)]
+    pub diagonal: bool,
+}
+
+/// a wire segment as saved to file
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct WireRecord {
+    pub src: SSPoint,
+    pub dst: SSPoint,
+    #[serde(default)]
+    pub locked: bool,
+}
+
+/// the view of the schematic when it was saved, independent of the canvas size
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ViewRecord {
+    /// point of the schematic at the center of the canvas
+    pub center: VSPoint,
+    /// zoom, in canvas pixels per schematic unit
+    pub scale: f32,
+}
+
+/// the contents of a saved schematic
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SchematicFile {
+    pub version: u32,
+    pub devices: Vec<DeviceRecord>,
+    pub wires: Vec<WireRecord>,
+    /// wire ends anchored to the device port they were started from
+    #[serde(default)]
+    pub anchors: Vec<SSPoint>,
+    /// analysis setup
+    #[serde(default)]
+    pub sim: SimConfig,
+    /// voltage probes
+    #[serde(default)]
+    pub probes: Vec<Probe>,
+    /// nets assigned to net classes
+    #[serde(default)]
+    pub net_classes: NetAssignments<String>,
+    /// initial voltages of nets for transient analysis
+    #[serde(default)]
+    pub initial_conditions: NetAssignments<f32>,
+    /// point the grid is anchored at
+    #[serde(default)]
+    pub grid_origin: SSPoint,
+    /// arrow annotations
+    #[serde(default)]
+    pub arrows: Vec<ArrowAnnotation>,
+    /// pan and zoom of the view, restored on open
+    #[serde(default)]
+    pub view: Option<ViewRecord>,
+}
+
+impl SchematicFile {
+    /// read a schematic file from path
+    pub fn read(path: &str) -> io::Result<Self> {
+        let file: SchematicFile = serde_json::from_slice(&fs::read(path)?)?;
+        if file.version > VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsupported file version {}", file.version)));
+        }
+        Ok(file)
+    }
+    /// write the schematic file to path
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?.as_bytes())
+    }
+}
@@ -0,0 +1,58 @@
+//! undo/redo history of the schematic
+//! every entry is a snapshot of the schematic taken just before an edit
+
+use crate::transforms::SSPoint;
+
+use super::{nets::{Nets, NetAssignments}, devices::DevicesSnapshot, BaseElement, ArrowAnnotation};
+
+/// the schematic at a point in history
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// nets graph
+    pub nets: Nets,
+    /// every device alongside a copy of its state
+    pub devices: DevicesSnapshot,
+    /// arrow annotations
+    pub arrows: Vec<ArrowAnnotation>,
+    /// nets assigned to net classes
+    pub net_classes: NetAssignments<String>,
+    /// initial voltages of nets
+    pub initial_conditions: NetAssignments<f32>,
+    /// point the grid is anchored at
+    pub grid_origin: SSPoint,
+    /// selected elements, keyed by device identity and net segment end points
+    pub selected: Vec<BaseElement>,
+}
+
+/// stacks of undo and redo snapshots
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl History {
+    /// maximum number of undo steps kept
+    const MAX_DEPTH: usize = 100;
+
+    /// record the state before an edit. Clears the redo stack.
+    pub fn push(&mut self, snapshot: Snapshot) {
+        self.undo.push(snapshot);
+        if self.undo.len() > History::MAX_DEPTH {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+    /// returns the snapshot to revert to, if any. `current` is kept so that the undo can be redone.
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.undo.pop()?;
+        self.redo.push(current);
+        Some(snapshot)
+    }
+    /// returns the snapshot to reapply, if any. `current` is kept so that the redo can be undone.
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.redo.pop()?;
+        self.undo.push(current);
+        Some(snapshot)
+    }
+}
@@ -1,31 +1,111 @@
 //! common functionality for interactive schematic elements
 
-use crate::transforms::{SSBox, SchematicSpace, SSPoint, SSTransform};
+use crate::transforms::{SSBox, SchematicSpace, SSCoord, SSPoint, SSTransform, VCTransform, Point};
+use iced::{widget::canvas::{Frame, Stroke, stroke, path::{Builder, Arc}}, Color, Size};
 
 pub trait Interactive {
     fn transform(&mut self, sst: SSTransform);
 }
 
+/// the highlight state of an interactable. Decides how it is drawn when several flags apply, in order of precedence: 
+/// locked overrides selected, which overrides tentative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualState {
+    /// selected, but locked against moving and deleting
+    Locked,
+    Selected,
+    /// under the cursor or selection box, not yet selected
+    Tentative,
+}
+
+impl VisualState {
+    /// returns the highlight color of the state
+    pub fn color(self) -> Color {
+        match self {
+            VisualState::Locked => Color::from_rgb(0.6, 0.6, 0.6),
+            VisualState::Selected => Color::from_rgb(1.0, 0.8, 0.0),
+            VisualState::Tentative => Color::from_rgb(1.0, 1.0, 0.5),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Interactable {
     /// the bounds of the interactable. e.g. mouse hover over this area should highlight the interactable.
     pub bounds: SSBox,
     /// tentative flag. If true, marks this interactable as under tentative selection. i.e. mouse hovering over but not yet selected.
     pub tentative: bool,
+    /// locked flag. If true, the interactable cannot be moved or deleted, and is skipped by area selection.
+    pub locked: bool,
 }
 
 impl Interactable {
     pub fn new() -> Self {
-        Interactable { bounds: SSBox::default(), tentative: false }
+        Interactable { bounds: SSBox::default(), tentative: false, locked: false }
     }
     /// sets tentative flag based on Schematic Space Box argument. Set to true if argument intersects with bounds.
     pub fn tentative_by_ssb(&mut self, ssb: &SSBox) {
-        self.tentative = self.bounds.intersects(ssb);
+        self.tentative = !self.locked && self.intersects_ssb(ssb);
+    }
+    /// returns true if bounds intersects ssb, including where they only touch at an edge or corner. 
+    /// Both boxes may have zero width or height, e.g. the bounds of a straight wire.
+    pub fn intersects_ssb(&self, ssb: &SSBox) -> bool {
+        self.bounds.min.x <= ssb.max.x && ssb.min.x <= self.bounds.max.x 
+        && self.bounds.min.y <= ssb.max.y && ssb.min.y <= self.bounds.max.y
+    }
+    /// returns the visual state of the interactable, given whether it is selected. None if it is drawn normally.
+    pub fn visual_state(&self, selected: bool) -> Option<VisualState> {
+        match (selected, self.locked, self.tentative) {
+            (true, true, _) => Some(VisualState::Locked),
+            (true, false, _) => Some(VisualState::Selected),
+            (false, _, true) => Some(VisualState::Tentative),
+            (false, _, false) => None,
+        }
     }
     /// returns true if Schematic Space Point intersects with bounds.
     pub fn contains_ssp(&self, ssp: SSPoint) -> bool {
         let mut ssb = self.bounds;
-        ssb.set_size(ssb.size() + euclid::Size2D::<i16, SchematicSpace>::new(1, 1));
+        ssb.set_size(ssb.size() + euclid::Size2D::<SSCoord, SchematicSpace>::new(1, 1));
         ssb.contains(ssp)
     }
-}
\ No newline at end of file
+    /// draw a small padlock at the upper right corner of bounds to indicate the interactable is locked
+    pub fn draw_lock(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
+        let color = Color::from_rgba(0.8, 0.8, 0.8, 0.6);
+        let corner = vct.transform_point(self.bounds.max.cast().cast_unit());
+        let (w, h) = (0.6 * vcscale, 0.4 * vcscale);
+        frame.fill_rectangle(
+            Point::from(corner - euclid::Vector2D::new(w / 2.0, 0.0)).into(), 
+            Size::new(w, h), 
+            color,
+        );
+        let mut path_builder = Builder::new();
+        path_builder.arc(Arc{
+            center: Point::from(corner).into(), 
+            radius: 0.2 * vcscale, 
+            start_angle: std::f32::consts::PI, 
+            end_angle: 2.0 * std::f32::consts::PI,
+        });
+        let stroke = Stroke {
+            width: (0.1 * vcscale).max(1.0),
+            style: stroke::Style::Solid(color),
+            ..Stroke::default()
+        };
+        frame.stroke(&path_builder.build(), stroke);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visual_state_precedence() {
+        let mut i = Interactable::new();
+        assert_eq!(i.visual_state(false), None);
+        i.tentative = true;
+        assert_eq!(i.visual_state(false), Some(VisualState::Tentative));
+        assert_eq!(i.visual_state(true), Some(VisualState::Selected));
+        i.locked = true;
+        assert_eq!(i.visual_state(true), Some(VisualState::Locked));
+    }
+}
@@ -0,0 +1,94 @@
+//! KiCad netlist export
+//! writes the `(export ...)` s-expression netlist read by the KiCad pcb editor. Only components and nets are listed.
+
+use std::{fs, io};
+
+/// a component as listed in the netlist
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Component {
+    /// reference designator, e.g. R1
+    pub reference: String,
+    /// device value as it appears in the spice netlist
+    pub value: String,
+    /// footprint attribute of the device, if any
+    pub footprint: Option<String>,
+}
+
+/// a net and every component pin connected to it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Net {
+    pub name: String,
+    /// connected pins as (reference designator, pin number). Pin numbers start at 1, in netlist order.
+    pub nodes: Vec<(String, usize)>,
+}
+
+/// quote s as an s-expression string
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// returns the netlist as s-expression text
+pub fn to_sexpr(components: &[Component], nets: &[Net]) -> String {
+    let mut s = String::from("(export (version \"E\")\n  (design (source \"Circe\"))\n  (components");
+    for c in components {
+        s.push_str(&format!("\n    (comp (ref {}) (value {})", quote(&c.reference), quote(&c.value)));
+        if let Some(fp) = &c.footprint {
+            s.push_str(&format!(" (footprint {})", quote(fp)));
+        }
+        s.push(')');
+    }
+    s.push_str(")\n  (nets");
+    for (code, n) in nets.iter().enumerate() {
+        s.push_str(&format!("\n    (net (code \"{}\") (name {})", code + 1, quote(&n.name)));
+        for (reference, pin) in &n.nodes {
+            s.push_str(&format!("\n      (node (ref {}) (pin \"{}\"))", quote(reference), pin));
+        }
+        s.push(')');
+    }
+    s.push_str("))\n");
+    s
+}
+
+/// write the netlist to path
+pub fn write(components: &[Component], nets: &[Net], path: &str) -> io::Result<()> {
+    fs::write(path, to_sexpr(components, nets).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn netlist_is_written_with_quoted_names() {
+        let components = vec![
+            Component { reference: String::from("R1"), value: String::from("1k"), footprint: Some(String::from("Resistor_SMD:R_0805")) },
+            Component { reference: String::from("V1"), value: String::from("DC 5 (\"main\")"), footprint: None },
+        ];
+        let nets = vec![
+            Net { name: String::from("gnd"), nodes: vec![(String::from("R1"), 2), (String::from("V1"), 2)] },
+            Net { name: String::from("out (a b)"), nodes: vec![(String::from("R1"), 1), (String::from("V1"), 1)] },
+        ];
+        let expected = concat!(
+            "(export (version \"E\")\n",
+            "  (design (source \"Circe\"))\n",
+            "  (components\n",
+            "    (comp (ref \"R1\") (value \"1k\") (footprint \"Resistor_SMD:R_0805\"))\n",
+            "    (comp (ref \"V1\") (value \"DC 5 (\\\"main\\\")\")))\n",
+            "  (nets\n",
+            "    (net (code \"1\") (name \"gnd\")\n",
+            "      (node (ref \"R1\") (pin \"2\"))\n",
+            "      (node (ref \"V1\") (pin \"2\")))\n",
+            "    (net (code \"2\") (name \"out (a b)\")\n",
+            "      (node (ref \"R1\") (pin \"1\"))\n",
+            "      (node (ref \"V1\") (pin \"1\")))))\n",
+        );
+        assert_eq!(to_sexpr(&components, &nets), expected);
+
+        let path = std::env::temp_dir().join(format!("circe_test_{}_kicad.net", std::process::id()));
+        let path = path.to_str().unwrap();
+        write(&components, &nets, path).unwrap();
+        let written = fs::read_to_string(path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(written, expected);
+    }
+}
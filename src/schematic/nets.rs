@@ -1,12 +1,14 @@
 //! schematic net/wires
 
-use std::collections::HashSet;
+use std::collections::{HashSet, HashMap, BTreeMap};
 use std::rc::Rc;
 
 use crate::{
-    transforms::{SSPoint, VCTransform, SSBox, SSTransform}, 
-    schematic::{BaseElement, SchematicSet, interactable::Interactive}
+    transforms::{SSPoint, VCTransform, SSBox, SSTransform, VSPoint, CSPoint, Point}, 
+    schematic::{BaseElement, SchematicSet, interactable::Interactive, file::WireRecord},
+    config::NetStyle,
 };
+use iced::{widget::canvas::{Frame, Text, Stroke}, Color, alignment::Vertical};
 use petgraph::graphmap::GraphMap;
 use petgraph::algo::tarjan_scc;
 
@@ -65,15 +67,118 @@ impl LabelManager {
     }
 }
 
+/// direction net label text runs in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LabelOrientation {
+    #[default]
+    Horizontal,
+    /// runs down along vertical wires, one character below the other. Canvas text glyphs cannot be rotated.
+    Vertical,
+}
+
+impl LabelOrientation {
+    /// returns the orientation of a label running along the wire from src to dst
+    pub fn along(src: SSPoint, dst: SSPoint) -> Self {
+        if src.x == dst.x && src.y != dst.y {
+            LabelOrientation::Vertical
+        } else {
+            LabelOrientation::Horizontal
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct SchematicNetLabel {
     label: String,
     // other stuff for drawing on schematic, being edited from schematic
 } 
+
+impl SchematicNetLabel {
+    /// draw the label text starting at vsp, running in the direction of orientation
+    pub fn draw(&self, vsp: VSPoint, orientation: LabelOrientation, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
+        let csp = vct.transform_point(vsp);
+        let text = |content: String, position: Point| Text {
+            content,
+            position: position.into(),
+            color: Color::from_rgb(0.0, 0.8, 1.0),
+            size: vcscale,
+            vertical_alignment: Vertical::Bottom,
+            ..Default::default()
+        };
+        match orientation {
+            LabelOrientation::Horizontal => frame.fill_text(text(self.label.clone(), Point::from(csp))),
+            LabelOrientation::Vertical => {
+                // beside the wire, top to bottom
+                for (i, c) in self.label.chars().enumerate() {
+                    let position = Point::from(CSPoint::new(csp.x + 0.2 * vcscale, csp.y + i as f32 * vcscale));
+                    frame.fill_text(text(c.to_string(), position));
+                }
+            },
+        }
+    }
+}
+/// a value assigned to a net by the user
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct NetAssignment<T> {
+    /// the assigned value, e.g. the name of a net class or an initial voltage
+    #[serde(alias = "class", alias = "volts")]
+    pub value: T,
+    /// point on the net, net names may change whenever nets are edited
+    pub pos: SSPoint,
+}
+
+/// values assigned to nets by the user, at most one per net, saved with the schematic. 
+/// Values follow their net as it is relabeled, and are dropped with it, see `prune`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(transparent)]
+pub struct NetAssignments<T>(Vec<NetAssignment<T>>);
+
+impl<T> Default for NetAssignments<T> {
+    fn default() -> Self {
+        NetAssignments(vec![])
+    }
+}
+
+impl<T: Clone> NetAssignments<T> {
+    /// returns the value assigned to the net passing through ssp, if any
+    pub fn get(&self, nets: &Nets, ssp: SSPoint) -> Option<&T> {
+        let net = nets.label_at(ssp)?;
+        self.0.iter().find(|a| nets.label_at(a.pos).as_ref() == Some(&net)).map(|a| &a.value)
+    }
+    /// assign value to the net passing through ssp, replacing its previous value, or remove the value of the net if none. 
+    /// Returns false if there is no net at ssp.
+    pub fn set(&mut self, nets: &Nets, ssp: SSPoint, value: Option<T>) -> bool {
+        let Some(net) = nets.label_at(ssp) else {return false};
+        self.0.retain(|a| nets.label_at(a.pos).as_ref() != Some(&net));
+        if let Some(value) = value {
+            self.0.push(NetAssignment { value, pos: ssp });
+        }
+        true
+    }
+    /// returns the assigned values keyed by net name
+    pub fn by_net(&self, nets: &Nets) -> BTreeMap<String, T> {
+        self.0.iter().filter_map(|a| Some((nets.label_at(a.pos)?.to_string(), a.value.clone()))).collect()
+    }
+    /// drop values no longer on any net. Of nets which were joined, the value assigned last is kept. 
+    /// To be called whenever nets are edited.
+    pub fn prune(&mut self, nets: &Nets) {
+        let mut seen = HashSet::new();
+        let mut kept: Vec<NetAssignment<T>> = self.0.drain(..).rev().filter(|a| {
+            nets.label_at(a.pos).is_some_and(|net| seen.insert(net))
+        }).collect();
+        kept.reverse();
+        self.0 = kept;
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Nets{
     pub graph: Box<GraphMap<NetVertex, NetEdge, petgraph::Undirected>>,
     label_manager: LabelManager,
+    /// global net names forced on the nets with a vertex at the given points, e.g. by power rails. Sorted by point.
+    rails: Vec<(SSPoint, String)>,
+    /// device ports wires were started from, the wire ends at these points follow the port when the device is moved
+    anchors: HashSet<SSPoint>,
 }
 
 impl Default for Nets {
@@ -81,28 +186,67 @@ impl Default for Nets {
         Nets{
             graph: Box::new(GraphMap::new()),
             label_manager: LabelManager::default(),
+            rails: vec![],
+            anchors: HashSet::new(),
         }
     }
 }
 
 impl Nets {
+    /// set the global net names forced on the nets with a vertex at the given points, applied whenever nets are relabeled. 
+    /// Every net of the same name is the same node in the netlist. A net with several rails is named after the leftmost rail.
+    pub fn set_rails(&mut self, mut rails: Vec<(SSPoint, String)>) {
+        rails.sort_by_key(|(ssp, _)| (ssp.x, ssp.y));
+        self.rails = rails;
+    }
     pub fn pre_netlist(&mut self) {
         self.label_manager.rst_floating_nets();
+        self.relabel();
     }
+    /// returns the name of the net at ssp. A port without wires is on the net of a rail whose port is at ssp, 
+    /// or else on a new floating net of its own.
     pub fn net_at(&mut self, ssp: SSPoint) -> String {
         for e in self.graph.all_edges() {
             if e.2.interactable.contains_ssp(ssp) {
                 return e.2.label.as_ref().unwrap().to_string();
             }
         }
+        if let Some((_, name)) = self.rails.iter().find(|(p, _)| *p == ssp) {
+            return name.clone();
+        }
         self.label_manager.new_floating_label()
     }
     pub fn tentatives_by_ssbox(&mut self, ssb: &SSBox) {
         for e in self.graph.all_edges_mut() {
-            if e.2.interactable.bounds.intersects(ssb) {
+            if !e.2.interactable.locked && e.2.interactable.intersects_ssb(ssb) {
+                e.2.interactable.tentative = true;
+            }
+        }
+    }
+    /// returns the name of the net passing through ssp, if any
+    pub fn label_at(&self, ssp: SSPoint) -> Option<Rc<String>> {
+        self.graph.all_edges().find(|e| e.2.interactable.contains_ssp(ssp)).and_then(|e| e.2.label.clone())
+    }
+    /// returns the name of the net with a vertex at ssp, or none if there is no vertex at ssp
+    pub fn label_at_vertex(&self, ssp: SSPoint) -> Option<Rc<String>> {
+        self.graph.edges(NetVertex(ssp)).find_map(|e| e.2.label.clone())
+    }
+    /// set tentative flags on every edge of the net with a vertex at ssp. Returns the net name, or none if there is no vertex at ssp.
+    pub fn tentatives_by_net(&mut self, ssp: SSPoint) -> Option<String> {
+        let label = self.label_at_vertex(ssp)?;
+        self.tentatives_by_label(&label);
+        Some(label.to_string())
+    }
+    /// set tentative flags on every edge of the net named label. Returns false if there is no such net.
+    pub fn tentatives_by_label(&mut self, label: &str) -> bool {
+        let mut found = false;
+        for e in self.graph.all_edges_mut() {
+            if e.2.label.as_deref().map(|l| l.as_str()) == Some(label) {
                 e.2.interactable.tentative = true;
+                found = true;
             }
         }
+        found
     }
     pub fn tentatives(&self) -> impl Iterator<Item = NetEdge> + '_ {
         self.graph.all_edges().filter_map(|e| {
@@ -124,33 +268,36 @@ impl Nets {
         }
         ret
     }
-    /// finds an appropriate net name and assigns it to all edge in edges. 
-    fn unify_labels(&mut self, edges: Vec<(NetVertex, NetVertex)>, taken_net_names: &[Rc<String>]) -> Rc<String> {
-        let mut label = None;
-        // get smallest untaken of existing labels, if any
-        for tup in &edges {
-            if let Some(ew) = self.graph.edge_weight(tup.0, tup.1) {
-                if let Some(label1) = &ew.label {
-                    if taken_net_names.contains(label1) {
-                        continue;
-                    }
-                    if label.is_none() || label1 < label.as_ref().unwrap() {
-                        label = Some(label1.clone());
-                    }
-                }
-            }
+    /// assigns net names deterministically: subnets are ordered by their smallest vertex and numbered in that order.
+    /// Net names therefore only depend on the geometry of the nets, not on the order in which they were drawn.
+    fn relabel(&mut self) {
+        let mut subnets = tarjan_scc(&*self.graph);  // this finds the subnets
+        for vertices in &mut subnets {
+            vertices.sort();
         }
-        // if no edge is labeled, create a new label
-        if label.is_none() {
-            label = Some(self.label_manager.new_label());
+        subnets.sort();
+        self.label_manager.labels.clear();
+        self.label_manager.wm = 0;
+        // generated names must not collide with rail names
+        for (_, name) in &self.rails {
+            self.label_manager.register(Rc::new(name.clone()));
         }
-        // assign label to all edges
-        for tup in edges {
-            if let Some(ew) = self.graph.edge_weight_mut(tup.0, tup.1) {
-                ew.label = label.clone();
+        for vertices in subnets {
+            let rail = self.rails.iter().find(|(ssp, _)| vertices.contains(&NetVertex(*ssp))).map(|(_, name)| name.clone());
+            let edges = self.nodes_to_edge_nodes(vertices);
+            if edges.is_empty() {
+                continue;
+            }
+            let label = match rail {
+                Some(name) => self.label_manager.labels.get(&name).unwrap().clone(),
+                None => self.label_manager.new_label(),
+            };
+            for tup in edges {
+                if let Some(ew) = self.graph.edge_weight_mut(tup.0, tup.1) {
+                    ew.label = Some(label.clone());
+                }
             }
         }
-        label.unwrap()
     }
     pub fn prune(&mut self, extra_vertices: Vec<SSPoint>) {  // extra vertices to add, e.g. ports
         let all_vertices: Vec<NetVertex> = self.graph.nodes().collect();
@@ -159,7 +306,7 @@ impl Nets {
             let mut colliding_edges = vec![];
             for e in self.graph.all_edges() {
                 if e.2.intersects_ssp(v.0) {
-                    colliding_edges.push((e.0, e.1, e.2.label.clone()));
+                    colliding_edges.push((e.0, e.1, e.2.label.clone(), e.2.interactable.locked));
                 }
             }
             if !colliding_edges.is_empty() {
@@ -168,12 +315,12 @@ impl Nets {
                     self.graph.add_edge(
                         e.0, 
                         *v, 
-                        NetEdge{src: e.0.0, dst: v.0, label: e.2.clone(), interactable: NetEdge::interactable(e.0.0, v.0, false), ..Default::default()}
+                        NetEdge{src: e.0.0, dst: v.0, label: e.2.clone(), interactable: NetEdge::interactable_locked(e.0.0, v.0, e.3), ..Default::default()}
                     );
                     self.graph.add_edge(
                         e.1, 
                         *v, 
-                        NetEdge{src: e.1.0, dst: v.0, label: e.2, interactable: NetEdge::interactable(e.1.0, v.0, false), ..Default::default()}
+                        NetEdge{src: e.1.0, dst: v.0, label: e.2, interactable: NetEdge::interactable_locked(e.1.0, v.0, e.3), ..Default::default()}
                     );
                 }
             }
@@ -196,11 +343,12 @@ impl Nets {
                     let first_e = self.graph.edges(v).next().unwrap();
                     let src = connected_vertices[0];
                     let dst = connected_vertices[1];
+                    let Some(locked) = self.merged_lock(src, v, dst) else {continue};
                     let ew = NetEdge{
                         src: src.0, 
                         dst: dst.0, 
                         label: first_e.2.label.clone(), 
-                        interactable: NetEdge::interactable(src.0, dst.0, false), 
+                        interactable: NetEdge::interactable_locked(src.0, dst.0, locked), 
                         ..Default::default()
                     };
                     if ew.intersects_ssp(v.0) {
@@ -216,7 +364,7 @@ impl Nets {
             let mut colliding_edges = vec![];
             for e in self.graph.all_edges() {
                 if e.2.intersects_ssp(v) {
-                    colliding_edges.push((e.0, e.1, e.2.label.clone()));
+                    colliding_edges.push((e.0, e.1, e.2.label.clone(), e.2.interactable.locked));
                 }
             }
             if !colliding_edges.is_empty() {
@@ -227,7 +375,7 @@ impl Nets {
                         dst: 
                         v, 
                         label: e.2.clone(), 
-                        interactable: NetEdge::interactable(e.0.0, v, false), 
+                        interactable: NetEdge::interactable_locked(e.0.0, v, e.3), 
                         ..Default::default()}
                     );
                     self.graph.add_edge(e.1, NetVertex(v), 
@@ -235,20 +383,61 @@ impl Nets {
                         src: e.1.0, 
                         dst: v, 
                         label: e.2, 
-                        interactable: NetEdge::interactable(e.1.0, v, false), 
+                        interactable: NetEdge::interactable_locked(e.1.0, v, e.3), 
                         ..Default::default()}
                     );
                 }
             }
         }
         // assign net names
-        // for each subnet
-        // unify labels - give vector of taken labels
-        let subgraph_vertices = tarjan_scc(&*self.graph);  // this finds the subnets
-        let mut taken_net_names = vec![];
-        for vertices in subgraph_vertices {
-            let edges = self.nodes_to_edge_nodes(vertices);
-            taken_net_names.push(self.unify_labels(edges, &taken_net_names));
+        self.relabel();
+    }
+    /// clean up the whole graph: removes zero-length edges, merges collinear runs of segments and deletes isolated vertices.
+    /// vertices in `ports` are never merged away.
+    pub fn cleanup(&mut self, ports: Vec<SSPoint>) {
+        // zero length edges
+        let zero_len: Vec<NetVertex> = self.graph.all_edges().filter(|e| e.0 == e.1).map(|e| e.0).collect();
+        for v in zero_len {
+            self.graph.remove_edge(v, v);
+        }
+        // merge collinear runs, repeat until no more vertices can be merged away
+        loop {
+            let mut merged = false;
+            let all_vertices: Vec<NetVertex> = self.graph.nodes().collect();
+            for v in all_vertices {
+                if ports.contains(&v.0) {
+                    continue;
+                }
+                let connected_vertices: Vec<NetVertex> = self.graph.neighbors(v).collect();
+                if connected_vertices.len() != 2 {
+                    continue;
+                }
+                let (src, dst) = (connected_vertices[0], connected_vertices[1]);
+                let Some(locked) = self.merged_lock(src, v, dst) else {continue};
+                let label = self.graph.edge_weight(src, v).and_then(|e| e.label.clone());
+                let ew = NetEdge{
+                    src: src.0, 
+                    dst: dst.0, 
+                    label, 
+                    interactable: NetEdge::interactable_locked(src.0, dst.0, locked), 
+                    ..Default::default()
+                };
+                // v must lie on a straight segment between its neighbors
+                let collinear = (src.0.x == v.0.x && dst.0.x == v.0.x) || (src.0.y == v.0.y && dst.0.y == v.0.y);
+                if collinear && ew.intersects_ssp(v.0) && !self.graph.contains_edge(src, dst) {
+                    self.graph.remove_node(v);
+                    self.graph.add_edge(src, dst, ew);
+                    merged = true;
+                }
+            }
+            if !merged {
+                break;
+            }
+        }
+        // isolated vertices
+        let isolated: Vec<NetVertex> = self.graph.nodes().filter(|v| self.graph.neighbors(*v).next().is_none()).collect();
+        for v in isolated {
+            self.graph.remove_node(v);
         }
     }
     pub fn edge_occupies_ssp(&self, ssp: SSPoint) -> bool {
@@ -264,7 +453,8 @@ impl Nets {
     }
     pub fn route(&mut self, src: SSPoint, dst: SSPoint) {
         // pathfinding?
-        // for now, just force edges to be vertical or horizontal
+        // for now, just force edges to be vertical or horizontal: 
+        // a diagonal is routed vertically from src to the corner level with dst, then horizontally to dst
         let delta = dst - src;
         match (delta.x, delta.y) {
             (0, 0) => {},
@@ -295,6 +485,49 @@ impl Nets {
         }
         self.prune(extra_vertices);
     }
+    /// move the vertices at each src to the paired dst, rerouting every edge connected to them with a corner where needed to stay orthogonal. 
+    /// Edges between two moved vertices are moved with them. Vertices which do not exist or have a locked edge connected are left in place.
+    pub fn drag_vertices(&mut self, moves: &[(SSPoint, SSPoint)]) {
+        let moves: Vec<(NetVertex, SSPoint)> = moves.iter()
+        .map(|(src, dst)| (NetVertex(*src), *dst))
+        .filter(|(v, dst)| v.0 != *dst && self.graph.contains_node(*v) && !self.graph.edges(*v).any(|e| e.2.interactable.locked))
+        .collect();
+        let moved_to = |n: NetVertex| moves.iter().find(|(v, _)| *v == n).map_or(n.0, |(_, dst)| *dst);
+        self.anchors = self.anchors.iter().map(|a| moved_to(NetVertex(*a))).collect();
+        let mut rerouted = Nets::default();
+        for (v, dst) in &moves {
+            for n in self.graph.neighbors(*v) {
+                rerouted.route(moved_to(n), *dst);
+            }
+        }
+        for (v, _) in &moves {
+            self.graph.remove_node(*v);
+        }
+        for (src, dst, e) in rerouted.graph.all_edges() {
+            let mut ew = e.clone();
+            ew.interactable = NetEdge::interactable(src.0, dst.0, false);
+            self.graph.add_edge(src, dst, ew);
+        }
+    }
+    /// anchor the wire end at ssp, a device port, to the port
+    pub fn anchor(&mut self, ssp: SSPoint) {
+        self.anchors.insert(ssp);
+    }
+    /// returns true if the wire end at ssp is anchored to the device port there
+    pub fn is_anchored(&self, ssp: SSPoint) -> bool {
+        self.anchors.contains(&ssp)
+    }
+    /// returns the anchored points sorted, as saved to file
+    pub fn anchors(&self) -> Vec<SSPoint> {
+        let mut anchors: Vec<SSPoint> = self.anchors.iter().copied().collect();
+        anchors.sort_by_key(|ssp| (ssp.x, ssp.y));
+        anchors
+    }
+    /// drop anchors with no wire end left or no longer at one of ports, e.g. after the wire or device was deleted
+    pub fn prune_anchors(&mut self, ports: &[SSPoint]) {
+        let graph = &self.graph;
+        self.anchors.retain(|a| graph.contains_node(NetVertex(*a)) && ports.contains(a));
+    }
     pub fn transform(&mut self, mut e: NetEdge, sst: SSTransform) {
         self.graph.remove_edge(NetVertex(e.src), NetVertex(e.dst));
         e.transform(sst);
@@ -305,9 +538,143 @@ impl Nets {
             e.2.interactable.tentative = false;
         }
     }
+    /// remove wires left dangling at the points in orphans, e.g. the ports of deleted devices. 
+    /// Each dangling run is removed back to the nearest junction, locked segment or point in keep, e.g. a remaining port.
+    pub fn prune_stubs(&mut self, orphans: Vec<SSPoint>, keep: &[SSPoint]) {
+        for ssp in orphans {
+            let mut v = NetVertex(ssp);
+            while self.graph.contains_node(v) && !keep.contains(&v.0) {
+                let mut edges = self.graph.edges(v);
+                let (Some(e), None) = (edges.next(), edges.next()) else {break};
+                if e.2.interactable.locked {
+                    break;
+                }
+                let next = if e.0 == v {e.1} else {e.0};
+                self.graph.remove_node(v);
+                v = next;
+            }
+        }
+    }
     pub fn delete_edge(&mut self, e: &NetEdge) {
         self.graph.remove_edge(NetVertex(e.src), NetVertex(e.dst));
     }
+    /// returns every wire segment as saved to file
+    pub fn records(&self) -> Vec<WireRecord> {
+        self.graph.all_edges().map(|e| WireRecord{src: e.2.src, dst: e.2.dst, locked: e.2.interactable.locked}).collect()
+    }
+    /// replace every wire segment with segments created from records, with the wire ends at anchors anchored. 
+    /// Net names are regenerated by `prune`.
+    pub fn load_records(&mut self, records: &[WireRecord], anchors: &[SSPoint]) {
+        *self = Nets::default();
+        self.anchors = anchors.iter().copied().collect();
+        for r in records {
+            self.graph.add_edge(
+                NetVertex(r.src), 
+                NetVertex(r.dst), 
+                NetEdge{src: r.src, dst: r.dst, interactable: NetEdge::interactable_locked(r.src, r.dst, r.locked), ..Default::default()}
+            );
+        }
+    }
+    /// returns true if the edge e in the graph is locked
+    pub fn is_locked(&self, e: &NetEdge) -> bool {
+        self.graph.edge_weight(NetVertex(e.src), NetVertex(e.dst)).map_or(false, |ew| ew.interactable.locked)
+    }
+    /// set the locked flag of the edge e in the graph
+    pub fn set_locked(&mut self, e: &NetEdge, locked: bool) {
+        if let Some(ew) = self.graph.edge_weight_mut(NetVertex(e.src), NetVertex(e.dst)) {
+            ew.interactable.locked = locked;
+        }
+    }
+    /// returns the locked flag of the edge from merging the edges (src, v) and (v, dst).
+    /// Returns none if only one of the edges is locked, in which case they should not be merged.
+    fn merged_lock(&self, src: NetVertex, v: NetVertex, dst: NetVertex) -> Option<bool> {
+        let lock0 = self.graph.edge_weight(src, v).map_or(false, |e| e.interactable.locked);
+        let lock1 = self.graph.edge_weight(v, dst).map_or(false, |e| e.interactable.locked);
+        if lock0 == lock1 {Some(lock0)} else {None}
+    }
+}
+
+/// radius of rounded wire corners
+const CORNER_RADIUS: f32 = 0.5;
+
+impl Nets {
+    /// returns every bend: vertices connecting exactly two perpendicular edges, mapped to their two neighbors
+    fn bends(&self) -> HashMap<NetVertex, (NetVertex, NetVertex)> {
+        self.graph.nodes().filter_map(|v| {
+            let n: Vec<NetVertex> = self.graph.neighbors(v).collect();
+            match n[..] {
+                [a, b] if (a.0 - v.0).dot(b.0 - v.0) == 0 && a != v && b != v => Some((v, (a, b))),
+                _ => None,
+            }
+        }).collect()
+    }
+    /// returns the stroke of the wires of net: its style in styles, keyed by net name, or the default
+    fn net_stroke(net: Option<&Rc<String>>, vcscale: f32, styles: &HashMap<String, NetStyle>) -> Stroke<'static> {
+        match net.and_then(|l| styles.get(l.as_str())) {
+            Some(style) => NetEdge::styled_stroke(vcscale, style),
+            None => NetEdge::persistent_stroke(vcscale),
+        }
+    }
+    /// draw the nets, nets named in styles in their net class style
+    pub fn draw_styled(&self, vct: VCTransform, vcscale: f32, frame: &mut iced::widget::canvas::Frame, styles: &HashMap<String, NetStyle>) {
+        for (_, _, edge) in self.graph.all_edges() {
+            edge.draw_stroked(vct, vcscale, frame, Nets::net_stroke(edge.label.as_ref(), vcscale, styles));
+            if edge.interactable.locked {
+                edge.interactable.draw_lock(vct, vcscale, frame);
+            }
+        }
+        for vertex in self.graph.nodes() {
+            vertex.draw_persistent(vct, vcscale, frame)
+        }
+    }
+    /// draw the nets with rounded corners at bends, nets named in styles in their net class style. 
+    /// Purely visual, the graph itself stays orthogonal.
+    pub fn draw_rounded(&self, vct: VCTransform, vcscale: f32, frame: &mut iced::widget::canvas::Frame, styles: &HashMap<String, NetStyle>) {
+        let bends = self.bends();
+        // corner radius at v, limited such that corners at both ends of an edge do not overlap
+        let radius = |v: NetVertex| {
+            bends.get(&v).map_or(0.0, |(a, b)| {
+                let la = (a.0 - v.0).cast::<f32>().length();
+                let lb = (b.0 - v.0).cast::<f32>().length();
+                CORNER_RADIUS.min(la / 2.0).min(lb / 2.0)
+            })
+        };
+        // point distance r from v towards w
+        let towards = |v: NetVertex, w: NetVertex, r: f32| -> VSPoint {
+            let dir = (w.0 - v.0).cast::<f32>().normalize();
+            VSPoint::new(v.0.x as f32 + dir.x * r, v.0.y as f32 + dir.y * r)
+        };
+        // one path per net class, keyed by the net name of styled nets
+        let class_of = |edge: &NetEdge| edge.label.as_ref().filter(|l| styles.contains_key(l.as_str())).cloned();
+        let mut builders: HashMap<Option<Rc<String>>, iced::widget::canvas::path::Builder> = HashMap::new();
+        for (src, dst, edge) in self.graph.all_edges() {
+            let p0 = vct.transform_point(towards(src, dst, radius(src)));
+            let p1 = vct.transform_point(towards(dst, src, radius(dst)));
+            let path_builder = builders.entry(class_of(edge)).or_default();
+            path_builder.move_to(Point::from(p0).into());
+            path_builder.line_to(Point::from(p1).into());
+            if edge.interactable.locked {
+                edge.interactable.draw_lock(vct, vcscale, frame);
+            }
+            edge.draw_label(vct, vcscale, frame);
+        }
+        for (v, (a, b)) in &bends {
+            let r = radius(*v);
+            let key = self.graph.edge_weight(*v, *a).and_then(class_of);
+            let path_builder = builders.entry(key).or_default();
+            path_builder.move_to(Point::from(vct.transform_point(towards(*v, *a, r))).into());
+            path_builder.quadratic_curve_to(
+                Point::from(vct.transform_point(v.0.cast().cast_unit())).into(), 
+                Point::from(vct.transform_point(towards(*v, *b, r))).into(),
+            );
+        }
+        for (net, path_builder) in builders {
+            frame.stroke(&path_builder.build(), Nets::net_stroke(net.as_ref(), vcscale, styles));
+        }
+        for vertex in self.graph.nodes().filter(|v| !bends.contains_key(v)) {
+            vertex.draw_persistent(vct, vcscale, frame)
+        }
+    }
 }
 
 impl SchematicSet for Nets {
@@ -327,12 +694,7 @@ impl SchematicSet for Nets {
 
 impl Drawable for Nets {
     fn draw_persistent(&self, vct: VCTransform, vcscale: f32, frame: &mut iced::widget::canvas::Frame) {
-        for (_, _, edge) in self.graph.all_edges() {
-            edge.draw_persistent(vct, vcscale, frame)
-        }
-        for vertex in self.graph.nodes() {
-            vertex.draw_persistent(vct, vcscale, frame)
-        }
+        self.draw_styled(vct, vcscale, frame, &HashMap::new());
     }
 
     fn draw_selected(&self, _vct: VCTransform, _vcscale: f32, _frame: &mut iced::widget::canvas::Frame) {
@@ -344,4 +706,116 @@ impl Drawable for Nets {
             edge.draw_preview(vct, vcscale, frame)
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_segment(nets: &mut Nets, src: SSPoint, dst: SSPoint) {
+        nets.graph.add_edge(NetVertex(src), NetVertex(dst), NetEdge{src, dst, interactable: NetEdge::interactable(src, dst, false), ..Default::default()});
+    }
+
+    /// a run of 3 collinear segments, a zero-length edge and an isolated vertex
+    fn messy_nets() -> Nets {
+        let mut nets = Nets::default();
+        add_segment(&mut nets, SSPoint::new(0, 0), SSPoint::new(1, 0));
+        add_segment(&mut nets, SSPoint::new(1, 0), SSPoint::new(2, 0));
+        add_segment(&mut nets, SSPoint::new(2, 0), SSPoint::new(3, 0));
+        add_segment(&mut nets, SSPoint::new(5, 5), SSPoint::new(5, 5));
+        nets.graph.add_node(NetVertex(SSPoint::new(9, 9)));
+        nets
+    }
+
+    #[test]
+    fn cleanup_merges_collinear_and_removes_strays() {
+        let mut nets = messy_nets();
+        assert_eq!(nets.graph.edge_count(), 4);
+        nets.cleanup(vec![]);
+        assert_eq!(nets.graph.edge_count(), 1);
+        assert_eq!(nets.graph.node_count(), 2);
+        assert!(nets.graph.contains_edge(NetVertex(SSPoint::new(0, 0)), NetVertex(SSPoint::new(3, 0))));
+    }
+
+    #[test]
+    fn cleanup_keeps_ports_and_corners() {
+        let mut nets = messy_nets();
+        add_segment(&mut nets, SSPoint::new(3, 0), SSPoint::new(3, 4));
+        assert_eq!(nets.graph.edge_count(), 5);
+        nets.cleanup(vec![SSPoint::new(1, 0)]);
+        assert_eq!(nets.graph.edge_count(), 3);
+        assert!(nets.graph.contains_edge(NetVertex(SSPoint::new(1, 0)), NetVertex(SSPoint::new(3, 0))));
+        assert!(nets.graph.contains_edge(NetVertex(SSPoint::new(3, 0)), NetVertex(SSPoint::new(3, 4))));
+    }
+
+    #[test]
+    fn net_names_do_not_depend_on_drawing_order() {
+        let segments = [
+            (SSPoint::new(0, 0), SSPoint::new(0, 4)),
+            (SSPoint::new(5, 0), SSPoint::new(9, 0)),
+            (SSPoint::new(-3, 2), SSPoint::new(-3, 8)),
+        ];
+        let mut forward = Nets::default();
+        for (src, dst) in segments {
+            add_segment(&mut forward, src, dst);
+        }
+        let mut backward = Nets::default();
+        for (src, dst) in segments.into_iter().rev() {
+            add_segment(&mut backward, dst, src);
+        }
+        forward.pre_netlist();
+        backward.pre_netlist();
+        for (src, _) in segments {
+            assert_eq!(forward.net_at(src), backward.net_at(src));
+        }
+        assert_eq!(forward.net_at(SSPoint::new(-3, 2)), "net_0");
+    }
+
+    #[test]
+    fn bends_are_perpendicular_degree_two_vertices() {
+        let mut nets = Nets::default();
+        add_segment(&mut nets, SSPoint::new(0, 0), SSPoint::new(4, 0));
+        add_segment(&mut nets, SSPoint::new(4, 0), SSPoint::new(4, 4));
+        add_segment(&mut nets, SSPoint::new(4, 4), SSPoint::new(8, 4));
+        add_segment(&mut nets, SSPoint::new(8, 4), SSPoint::new(12, 4));
+        let bends = nets.bends();
+        assert_eq!(bends.len(), 2);
+        assert!(bends.contains_key(&NetVertex(SSPoint::new(4, 0))));
+        assert!(bends.contains_key(&NetVertex(SSPoint::new(4, 4))));
+    }
+
+    #[test]
+    fn labels_run_along_vertical_wires() {
+        assert_eq!(LabelOrientation::along(SSPoint::new(0, 0), SSPoint::new(0, 5)), LabelOrientation::Vertical);
+        assert_eq!(LabelOrientation::along(SSPoint::new(0, 0), SSPoint::new(5, 0)), LabelOrientation::Horizontal);
+        assert_eq!(LabelOrientation::along(SSPoint::new(1, 1), SSPoint::new(1, 1)), LabelOrientation::Horizontal);
+    }
+
+    #[test]
+    fn diagonal_routes_are_orthogonal() {
+        let mut nets = Nets::default();
+        let mut prev = SSPoint::new(0, 0);
+        for cur in [(3, 5), (-4, 9), (-10, -2), (1, -7), (6, -6), (0, 0)] {
+            let cur = SSPoint::new(cur.0, cur.1);
+            let mut wire = Nets::default();
+            wire.route(prev, cur);
+            assert!(wire.graph.all_edges().all(|e| e.0.0.x == e.1.0.x || e.0.0.y == e.1.0.y));
+            nets.merge(&wire, vec![]);
+            prev = cur;
+        }
+        assert!(nets.graph.edge_count() > 0);
+        for (v0, v1, e) in nets.graph.all_edges() {
+            assert!(v0 != v1 && (v0.0.x == v1.0.x) != (v0.0.y == v1.0.y), "diagonal edge {:?}", e);
+        }
+    }
+
+    #[test]
+    fn edges_equal_in_either_direction() {
+        let (a, b) = (SSPoint::new(0, 0), SSPoint::new(3, 0));
+        let ab = NetEdge{src: a, dst: b, interactable: NetEdge::interactable(a, b, false), ..Default::default()};
+        let ba = NetEdge{src: b, dst: a, interactable: NetEdge::interactable(b, a, false), ..Default::default()};
+        assert_eq!(ab, ba);
+        let set: HashSet<NetEdge> = [ab, ba].into_iter().collect();
+        assert_eq!(set.len(), 1);
+    }
+}
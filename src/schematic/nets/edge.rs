@@ -2,12 +2,13 @@
 
 use std::rc::Rc;
 
-use super::SchematicNetLabel;
+use super::{SchematicNetLabel, LabelOrientation, NetVertex};
 use crate::{
     transforms::{
-        SSPoint, VCTransform, SSBox, SSTransform
+        SSPoint, VSPoint, VCTransform, SSBox, SSTransform
     }, 
-    schematic::{interactable::{Interactable, Interactive}, nets::Drawable}
+    schematic::{interactable::{Interactable, Interactive, VisualState}, nets::Drawable},
+    config::NetStyle,
 };
 
 use iced::{widget::canvas::{Frame, Path, Stroke, stroke, LineCap, LineDash}, Color};
@@ -28,25 +29,35 @@ pub struct NetEdge {
     pub schematic_net_label: Option<SchematicNetLabel>,
 }
 
-/// two edges are equal if their source and destination pts are equal
+/// two edges are equal if they join the same pts, in either order - wires are undirected
 impl PartialEq for NetEdge {
     fn eq(&self, other: &Self) -> bool {
-        self.src == other.src && self.dst == other.dst
+        self.ends() == other.ends()
     }
 }
 
-/// hash absed on the soruce and destination points
+impl Eq for NetEdge {}
+
+/// hash based on the end points, independent of their order
 impl std::hash::Hash for NetEdge {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.src.hash(state);
-        self.dst.hash(state);
+        self.ends().hash(state);
     }
 }
 
 impl NetEdge {
+    /// returns the end points of the edge, the lesser vertex first
+    fn ends(&self) -> (NetVertex, NetVertex) {
+        let (a, b) = (NetVertex(self.src), NetVertex(self.dst));
+        if a <= b {(a, b)} else {(b, a)}
+    }
     /// creates an interactable based on source and destination points, with settable 'tentative' flag
     pub fn interactable(src: SSPoint, dst: SSPoint, tentative: bool) -> Interactable {
-        Interactable { bounds: NetEdge::bounds_from_pts(src, dst), tentative, }
+        Interactable { bounds: NetEdge::bounds_from_pts(src, dst), tentative, locked: false }
+    }
+    /// creates an interactable based on source and destination points, with settable 'locked' flag
+    pub fn interactable_locked(src: SSPoint, dst: SSPoint, locked: bool) -> Interactable {
+        Interactable { bounds: NetEdge::bounds_from_pts(src, dst), tentative: false, locked }
     }
     /// creates a bound based on source and destination points - return value is guaranteed to have positive area
     pub fn bounds_from_pts(src: SSPoint, dst: SSPoint) -> SSBox {
@@ -85,35 +96,62 @@ const WIRE_WIDTH: f32 = 0.05;
 /// zoom level below which wire width stops becoming thinner
 const ZOOM_THRESHOLD: f32 = 5.0;
 
-impl Drawable for NetEdge {
-    fn draw_persistent(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
-        let wire_width = self::WIRE_WIDTH;
+impl NetEdge {
+    /// returns the stroke used to draw wires persistently
+    pub fn persistent_stroke(vcscale: f32) -> Stroke<'static> {
+        NetEdge::styled_stroke(vcscale, &NetStyle::default())
+    }
+    /// returns the stroke used to draw wires of a net class in style
+    pub fn styled_stroke(vcscale: f32, style: &NetStyle) -> Stroke<'static> {
+        let wire_width = self::WIRE_WIDTH * style.width;
         let zoom_thshld = self::ZOOM_THRESHOLD;
-        let wire_stroke = Stroke {
+        let [r, g, b] = style.color;
+        Stroke {
             width: (wire_width * vcscale).max(wire_width * zoom_thshld),
-            style: stroke::Style::Solid(Color::from_rgb(0.0, 0.8, 1.0)),
+            style: stroke::Style::Solid(Color::from_rgb(r, g, b)),
             line_cap: LineCap::Round,
             ..Stroke::default()
-        };
-        draw_with(self.src, self.dst, vct, frame, wire_stroke);
+        }
     }
-    fn draw_selected(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
+    /// draw the wire with stroke, and its label
+    pub fn draw_stroked(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame, stroke: Stroke) {
+        draw_with(self.src, self.dst, vct, frame, stroke);
+        self.draw_label(vct, vcscale, frame);
+    }
+    /// draw the user defined net name, if any, at the middle of the wire, running along the wire
+    pub fn draw_label(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
+        if let Some(l) = &self.schematic_net_label {
+            let mid: VSPoint = self.src.cast().cast_unit().lerp(self.dst.cast().cast_unit(), 0.5);
+            l.draw(mid, LabelOrientation::along(self.src, self.dst), vct, vcscale, frame);
+        }
+    }
+    /// draw the wire in a solid highlight color
+    pub fn draw_highlight(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame, color: Color) {
         let wire_width = self::WIRE_WIDTH;
         let zoom_thshld = self::ZOOM_THRESHOLD;
         let wire_stroke = Stroke {
             width: (wire_width * vcscale).max(wire_width * zoom_thshld),
-            style: stroke::Style::Solid(Color::from_rgb(1.0, 0.8, 0.0)),
+            style: stroke::Style::Solid(color),
             line_cap: LineCap::Round,
             ..Stroke::default()
         };
         draw_with(self.src, self.dst, vct, frame, wire_stroke);
     }
+}
+
+impl Drawable for NetEdge {
+    fn draw_persistent(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
+        self.draw_stroked(vct, vcscale, frame, NetEdge::persistent_stroke(vcscale));
+    }
+    fn draw_selected(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
+        self.draw_highlight(vct, vcscale, frame, VisualState::Selected.color());
+    }
     fn draw_preview(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame) {
         let wire_width = self::WIRE_WIDTH;
         let zoom_thshld = self::ZOOM_THRESHOLD;
         let wire_stroke = Stroke {
             width: (wire_width * vcscale).max(wire_width * zoom_thshld),
-            style: stroke::Style::Solid(Color::from_rgb(1.0, 1.0, 0.5)),
+            style: stroke::Style::Solid(VisualState::Tentative.color()),
             line_cap: LineCap::Butt,
             line_dash: LineDash{segments: &[3. * (wire_width * vcscale).max(wire_width * 2.0)], offset: 0},
             ..Stroke::default()
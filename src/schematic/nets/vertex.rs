@@ -4,7 +4,7 @@ use std::cmp::Ordering;
 
 use crate::{
     transforms::{SSPoint, VCTransform}, 
-    schematic::nets::Drawable
+    schematic::{nets::Drawable, interactable::VisualState}
 };
 use iced::{widget::canvas::{Frame, Path, Stroke, stroke, LineCap}, Color};
 
@@ -58,7 +58,7 @@ impl Drawable for NetVertex {
         let zoom_thshld = self::ZOOM_THRESHOLD;
         let wire_stroke = Stroke {
             width: (solder_dia * vcscale).max(solder_dia * zoom_thshld),
-            style: stroke::Style::Solid(Color::from_rgb(1.0, 0.8, 0.0)),
+            style: stroke::Style::Solid(VisualState::Selected.color()),
             line_cap: LineCap::Round,
             ..Stroke::default()
         };
@@ -69,7 +69,7 @@ impl Drawable for NetVertex {
         let zoom_thshld = self::ZOOM_THRESHOLD;
         let wire_stroke = Stroke {
             width: (solder_dia * vcscale).max(solder_dia * zoom_thshld),
-            style: stroke::Style::Solid(Color::from_rgb(1.0, 1.0, 0.5)),
+            style: stroke::Style::Solid(VisualState::Tentative.color()),
             line_cap: LineCap::Round,
             ..Stroke::default()
         };
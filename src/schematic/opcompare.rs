@@ -0,0 +1,56 @@
+//! comparison of operating point results against a saved baseline
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// net voltages of an operating point, keyed by net name
+pub type OpVoltages = BTreeMap<String, f32>;
+
+/// a named set of operating point results to compare later runs against
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpBaseline {
+    pub name: String,
+    pub voltages: OpVoltages,
+}
+
+/// the voltage of a single net in the baseline and in the current results
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpDelta {
+    pub net: String,
+    /// voltage in the baseline, none if the net did not exist
+    pub old: Option<f32>,
+    /// voltage in the current results, none if the net no longer exists
+    pub new: Option<f32>,
+}
+
+impl OpDelta {
+    /// returns new - old, if the net exists in both
+    pub fn delta(&self) -> Option<f32> {
+        Some(self.new? - self.old?)
+    }
+}
+
+/// returns the voltage of every net appearing in either baseline or current, ordered by net name
+pub fn compare(baseline: &OpVoltages, current: &OpVoltages) -> Vec<OpDelta> {
+    let nets: BTreeSet<&String> = baseline.keys().chain(current.keys()).collect();
+    nets.into_iter().map(|net| OpDelta {
+        net: net.clone(),
+        old: baseline.get(net).copied(),
+        new: current.get(net).copied(),
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_lists_every_net_with_delta() {
+        let baseline = OpVoltages::from([(String::from("net_0"), 1.0), (String::from("net_1"), 2.0)]);
+        let current = OpVoltages::from([(String::from("net_1"), 2.5), (String::from("net_2"), 3.0)]);
+        let deltas = compare(&baseline, &current);
+        assert_eq!(deltas.iter().map(|d| d.net.as_str()).collect::<Vec<_>>(), vec!["net_0", "net_1", "net_2"]);
+        assert_eq!(deltas.iter().map(|d| d.delta()).collect::<Vec<_>>(), vec![None, Some(0.5), None]);
+        assert_eq!(deltas[0].new, None);
+        assert_eq!(deltas[2].old, None);
+    }
+}
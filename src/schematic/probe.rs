@@ -0,0 +1,73 @@
+//! voltage probes
+//! a probe is placed on a net and shows the voltage of the net at the last operating point
+
+use crate::{transforms::{SSPoint, VCTransform, VSPoint, Point}, config::NumberFormat};
+use iced::{widget::canvas::{Frame, path::Builder, Stroke, Text, self}, Color};
+
+use super::{devices::format_significant, opcompare::OpVoltages};
+
+/// a voltage probe on a net
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Probe {
+    /// name of the probed net, refreshed from pos whenever nets are renamed
+    pub net: String,
+    /// point on the net the probe is placed at
+    pub pos: SSPoint,
+    /// voltage of the net at the last operating point, if known
+    #[serde(skip)]
+    pub value: Option<f32>,
+}
+
+impl Probe {
+    /// color of the probe marker and readout
+    const COLOR: Color = Color::from_rgb(0.4, 1.0, 0.4);
+
+    pub fn new(net: String, pos: SSPoint) -> Self {
+        Probe { net, pos, value: None }
+    }
+    /// take the voltage of the probed net from the operating point results
+    pub fn update(&mut self, voltages: &OpVoltages) {
+        self.value = voltages.get(&self.net).copied();
+    }
+    /// draw a diamond marker at the probe position with the net name and its voltage next to it, 
+    /// with precision significant figures formatted by number_format
+    pub fn draw(&self, vct: VCTransform, vcscale: f32, frame: &mut Frame, precision: usize, number_format: &NumberFormat) {
+        let p: VSPoint = self.pos.cast().cast_unit();
+        let mut path_builder = Builder::new();
+        path_builder.move_to(Point::from(vct.transform_point(VSPoint::new(p.x, p.y + 0.5))).into());
+        path_builder.line_to(Point::from(vct.transform_point(VSPoint::new(p.x + 0.5, p.y))).into());
+        path_builder.line_to(Point::from(vct.transform_point(VSPoint::new(p.x, p.y - 0.5))).into());
+        path_builder.line_to(Point::from(vct.transform_point(VSPoint::new(p.x - 0.5, p.y))).into());
+        path_builder.close();
+        let stroke = Stroke {
+            width: (0.1 * vcscale).max(0.1 * 2.0),
+            style: canvas::stroke::Style::Solid(Self::COLOR),
+            ..Stroke::default()
+        };
+        frame.stroke(&path_builder.build(), stroke);
+
+        let value = self.value.map_or(String::from("?"), |v| number_format.localize(&format_significant(v, precision)));
+        frame.fill_text(Text {
+            content: format!("{}: {} V", self.net, value),
+            position: Point::from(vct.transform_point(VSPoint::new(p.x + 0.75, p.y + 1.5))).into(),
+            color: Self::COLOR,
+            size: vcscale,
+            ..Default::default()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probe_reads_its_net_voltage() {
+        let mut probe = Probe::new(String::from("net_1"), SSPoint::new(2, 3));
+        probe.update(&OpVoltages::from([(String::from("net_1"), 4.5), (String::from("net_2"), 1.0)]));
+        assert_eq!(probe.value, Some(4.5));
+        probe.net = String::from("net_3");
+        probe.update(&OpVoltages::from([(String::from("net_1"), 4.5)]));
+        assert_eq!(probe.value, None);
+    }
+}
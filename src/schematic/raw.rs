@@ -0,0 +1,227 @@
+//! ngspice raw output files
+//! results of a simulation run outside of circe, e.g. `ngspice -b -r out.raw netlist.cir`, in ascii or binary format
+
+use std::fs;
+
+/// a vector of a plot, e.g. a node voltage over time
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawVector {
+    /// name as used by the live results: the net name for node voltages, `<device>#branch` for branch currents
+    pub name: String,
+    /// quantity of the vector, e.g. voltage, current or time
+    pub kind: String,
+    /// value at each point as (real, imaginary), imaginary is 0 for real plots
+    pub values: Vec<(f64, f64)>,
+}
+
+/// a plot of a rawfile, e.g. the results of one analysis
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RawPlot {
+    pub title: String,
+    /// name of the analysis, e.g. Operating Point or Transient Analysis
+    pub name: String,
+    pub complex: bool,
+    pub vectors: Vec<RawVector>,
+}
+
+impl RawPlot {
+    /// returns the number of points of the plot
+    pub fn points(&self) -> usize {
+        self.vectors.first().map_or(0, |v| v.values.len())
+    }
+    /// returns the values of every vector at point i in the form of the live results, none if there is no such point
+    pub fn point(&self, i: usize) -> Option<paprika::PkVecvaluesall> {
+        let vecsa: Vec<paprika::PkVecvalues> = self.vectors.iter().enumerate().map(|(j, v)| {
+            v.values.get(i).map(|&(creal, cimag)| paprika::PkVecvalues {
+                name: v.name.clone(),
+                creal,
+                cimag,
+                is_scale: j == 0,
+                is_complex: self.complex,
+            })
+        }).collect::<Option<_>>()?;
+        if vecsa.is_empty() {
+            return None;
+        }
+        Some(paprika::PkVecvaluesall { veccount: vecsa.len() as i32, vecindex: i as i32, vecsa })
+    }
+}
+
+/// results read from a rawfile, one plot per analysis
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimResults {
+    pub plots: Vec<RawPlot>,
+}
+
+impl SimResults {
+    /// returns the last point of the last plot in the form of the live results, e.g. the operating point or the end of a transient
+    pub fn last_point(&self) -> Option<paprika::PkVecvaluesall> {
+        let plot = self.plots.last()?;
+        plot.point(plot.points().checked_sub(1)?)
+    }
+}
+
+/// rename a rawfile vector after the live results: `v(net)` is `net`, `i(v1)` is `v1#branch`
+fn live_name(name: &str) -> String {
+    let lower = name.to_ascii_lowercase();
+    if let Some(net) = lower.strip_prefix("v(").and_then(|s| s.strip_suffix(')')) {
+        net.to_string()
+    } else if let Some(dev) = lower.strip_prefix("i(").and_then(|s| s.strip_suffix(')')) {
+        format!("{}#branch", dev)
+    } else {
+        lower
+    }
+}
+
+/// parse the ngspice rawfile at path
+pub fn parse(path: &str) -> Result<SimResults, String> {
+    let bytes = fs::read(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+    parse_bytes(&bytes)
+}
+
+/// parse the contents of an ngspice rawfile, ascii or binary. Several plots may follow each other.
+pub fn parse_bytes(bytes: &[u8]) -> Result<SimResults, String> {
+    let mut results = SimResults::default();
+    let mut pos = 0;
+    // returns the next line, without the line ending, and moves past it
+    let next_line = |pos: &mut usize| -> Option<String> {
+        if *pos >= bytes.len() {
+            return None;
+        }
+        let end = bytes[*pos..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |i| *pos + i);
+        let line = String::from_utf8_lossy(&bytes[*pos..end]).trim_end_matches('\r').to_string();
+        *pos = end + 1;
+        Some(line)
+    };
+    while let Some(line) = next_line(&mut pos) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut plot = RawPlot::default();
+        let (mut nvars, mut npoints): (usize, usize) = (0, 0);
+        let mut line = Some(line);
+        // header
+        let binary = loop {
+            let Some(l) = line.take() else {return Err(String::from("rawfile ends before its values"))};
+            let (key, value) = l.split_once(':').map_or((l.as_str(), ""), |(k, v)| (k, v.trim()));
+            match key.trim().to_ascii_lowercase().as_str() {
+                "title" => plot.title = value.to_string(),
+                "plotname" => plot.name = value.to_string(),
+                "flags" => plot.complex = value.to_ascii_lowercase().contains("complex"),
+                "no. variables" => nvars = value.parse().map_err(|_| format!("invalid variable count {}", value))?,
+                "no. points" => npoints = value.parse().map_err(|_| format!("invalid point count {}", value))?,
+                "variables" => {
+                    for _ in 0..nvars {
+                        let l = next_line(&mut pos).ok_or("rawfile ends in its variables")?;
+                        let mut words = l.split_whitespace().skip(1);
+                        let (Some(name), kind) = (words.next(), words.next()) else {return Err(format!("invalid variable {}", l))};
+                        plot.vectors.push(RawVector { name: live_name(name), kind: kind.unwrap_or("").to_string(), values: vec![] });
+                    }
+                },
+                "values" => break false,
+                "binary" => break true,
+                _ => {},
+            }
+            line = next_line(&mut pos);
+        };
+        if plot.vectors.len() != nvars {
+            return Err(String::from("rawfile values before its variables"));
+        }
+        // a rawfile always has the scale vector, e.g. time or frequency
+        if nvars == 0 {
+            return Err(String::from("rawfile has no variables"));
+        }
+        let per_value = if plot.complex {2} else {1};
+        let overflow = || String::from("rawfile header sizes overflow");
+        if binary {
+            let len = npoints.checked_mul(nvars)
+                .and_then(|n| n.checked_mul(per_value * 8))
+                .ok_or_else(overflow)?;
+            let end = pos.checked_add(len).ok_or_else(overflow)?;
+            let data = bytes.get(pos..end).ok_or("rawfile ends in its binary values")?;
+            let mut floats = data.chunks_exact(8).map(|c| f64::from_le_bytes(c.try_into().unwrap()));
+            for _ in 0..npoints {
+                for v in &mut plot.vectors {
+                    let re = floats.next().expect("data holds a value per variable and point");
+                    let im = if plot.complex {floats.next().expect("data holds a value per variable and point")} else {0.0};
+                    v.values.push((re, im));
+                }
+            }
+            pos += len;
+        } else {
+            // each point is its index followed by the value of every variable, complex values as re,im
+            let ntokens = npoints.checked_mul(nvars + 1).ok_or_else(overflow)?;
+            let mut tokens = vec![];
+            while tokens.len() < ntokens {
+                let l = next_line(&mut pos).ok_or("rawfile ends in its values")?;
+                tokens.extend(l.split_whitespace().map(String::from));
+            }
+            for point in tokens.chunks(nvars + 1) {
+                for (v, t) in plot.vectors.iter_mut().zip(&point[1..]) {
+                    let (re, im) = t.split_once(',').unwrap_or((t, "0"));
+                    let parse = |s: &str| s.parse::<f64>().map_err(|_| format!("invalid value {}", t));
+                    v.values.push((parse(re)?, parse(im)?));
+                }
+            }
+        }
+        results.plots.push(plot);
+    }
+    if results.plots.is_empty() {
+        return Err(String::from("rawfile has no plots"));
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "Title: Netlist Created by Circe\nDate: Thu Jan  1 00:00:00  2026\nPlotname: Transient Analysis\nFlags: real\nNo. Variables: 3\nNo. Points: 2\nVariables:\n\t0\ttime\ttime\n\t1\tv(net_1)\tvoltage\n\t2\ti(v1)\tcurrent\n";
+
+    #[test]
+    fn ascii_and_binary_rawfiles_read_the_same() {
+        let ascii = format!("{}Values:\n 0\t0.0\n\t1.0\n\t-1e-3\n\n 1\t1e-6\n\t2.5\n\t-2e-3\n", HEADER);
+        let mut binary = format!("{}Binary:\n", HEADER).into_bytes();
+        for v in [0.0f64, 1.0, -1e-3, 1e-6, 2.5, -2e-3] {
+            binary.extend(v.to_le_bytes());
+        }
+        let results = parse_bytes(ascii.as_bytes()).unwrap();
+        assert_eq!(results, parse_bytes(&binary).unwrap());
+
+        let plot = &results.plots[0];
+        assert_eq!(plot.name, "Transient Analysis");
+        assert_eq!(plot.points(), 2);
+        let names: Vec<&str> = plot.vectors.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, ["time", "net_1", "v1#branch"]);
+        let last = results.last_point().unwrap();
+        assert_eq!(last.vecsa[1].name, "net_1");
+        assert_eq!(last.vecsa[1].creal, 2.5);
+        assert!(parse_bytes(format!("{}Values:\n 0\t0.0\n", HEADER).as_bytes()).is_err());
+    }
+
+    #[test]
+    fn huge_point_count_is_an_error() {
+        let header = HEADER.replace("No. Points: 2", &format!("No. Points: {}", usize::MAX));
+        let binary = format!("{}Binary:\n", header);
+        assert_eq!(parse_bytes(binary.as_bytes()), Err(String::from("rawfile header sizes overflow")));
+        let ascii = format!("{}Values:\n 0\t0.0\n", header);
+        assert_eq!(parse_bytes(ascii.as_bytes()), Err(String::from("rawfile header sizes overflow")));
+    }
+
+    #[test]
+    fn zero_variables_is_an_error() {
+        let header = "Plotname: Transient Analysis\nFlags: real\nNo. Variables: 0\nNo. Points: 2\nVariables:\n";
+        let binary = format!("{}Binary:\n", header);
+        assert_eq!(parse_bytes(binary.as_bytes()), Err(String::from("rawfile has no variables")));
+        let ascii = format!("{}Values:\n 0\n 1\n", header);
+        assert_eq!(parse_bytes(ascii.as_bytes()), Err(String::from("rawfile has no variables")));
+    }
+
+    #[test]
+    fn complex_values_are_split() {
+        let raw = "Plotname: AC Analysis\nFlags: complex\nNo. Variables: 2\nNo. Points: 1\nVariables:\n\t0\tfrequency\tfrequency\n\t1\tv(out)\tvoltage\nValues:\n 0\t1e3,0\n\t0.5,-0.25\n";
+        let results = parse_bytes(raw.as_bytes()).unwrap();
+        assert!(results.plots[0].complex);
+        assert_eq!(results.plots[0].vectors[1].values, vec![(0.5, -0.25)]);
+    }
+}
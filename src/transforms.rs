@@ -11,37 +11,42 @@ pub struct CanvasSpace;
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize, serde::Serialize)]
 pub struct ViewportSpace;
 
-/// PhantomData tag used to denote the i16 space in which the schematic exists
+/// PhantomData tag used to denote the integer space in which the schematic exists, see `SSCoord`
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize, serde::Serialize)]
 pub struct SchematicSpace;
 
+/// SchematicSpace coordinate
+pub type SSCoord = i32;
+/// largest magnitude of a SchematicSpace coordinate, exact as f32
+pub const SS_COORD_LIMIT: SSCoord = 1 << 24;
+
 /// CanvasSpace Point
 pub type CSPoint = euclid::Point2D<f32, CanvasSpace>;
 /// ViewportSpace Point
 pub type VSPoint = euclid::Point2D<f32, ViewportSpace>;
 /// SchematicSpace Point
-pub type SSPoint = euclid::Point2D<i16, SchematicSpace>;
+pub type SSPoint = euclid::Point2D<SSCoord, SchematicSpace>;
 
 /// CanvasSpace Box
 pub type CSBox = euclid::Box2D<f32, CanvasSpace>;
 /// ViewportSpace Box
 pub type VSBox = euclid::Box2D<f32, ViewportSpace>;
 /// SchematicSpace Box
-pub type SSBox = euclid::Box2D<i16, SchematicSpace>;
+pub type SSBox = euclid::Box2D<SSCoord, SchematicSpace>;
 
 /// CanvasSpace Vector
 pub type CSVec = euclid::Vector2D<f32, CanvasSpace>;
 /// ViewportSpace Vector
 pub type VSVec = euclid::Vector2D<f32, ViewportSpace>;
 /// SchematicSpace Vector
-pub type SSVec = euclid::Vector2D<i16, SchematicSpace>;
+pub type SSVec = euclid::Vector2D<SSCoord, SchematicSpace>;
 
 /// viewport to canvas space transform
 pub type VCTransform = euclid::Transform2D<f32, ViewportSpace, CanvasSpace>;
 /// canvas to viewport space transform
 pub type CVTransform = euclid::Transform2D<f32, CanvasSpace, ViewportSpace>;
 /// schematic space transform
-pub type SSTransform = euclid::Transform2D<i16, SchematicSpace, SchematicSpace>;
+pub type SSTransform = euclid::Transform2D<SSCoord, SchematicSpace, SchematicSpace>;
 
 /// 90 deg clockwise rotation transform
 pub const SST_CWR: SSTransform = SSTransform::new(
@@ -53,6 +58,16 @@ pub const SST_CCWR: SSTransform = SSTransform::new(
     0, 1, -1, 0, 0, 0
 );
 
+/// mirror along the y axis transform - flips the x coordinate
+pub const SST_XMIR: SSTransform = SSTransform::new(
+    -1, 0, 0, 1, 0, 0
+);
+
+/// mirror along the x axis transform - flips the y coordinate
+pub const SST_YMIR: SSTransform = SSTransform::new(
+    1, 0, 0, -1, 0, 0
+);
+
 /// converts SSTransform to VVTransform so that it can be composited with VCTransform
 pub fn sst_to_xxt<T>(sst: SSTransform) -> Transform2D<f32, T, T> {
     sst.cast().with_destination().with_source()
@@ -87,4 +102,17 @@ impl From<CSPoint> for Point {
     fn from(src: CSPoint) -> Self {
         Self(src)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotations_compose_to_identity() {
+        assert_eq!(SST_CCWR.then(&SST_CWR), SSTransform::identity());
+        assert_eq!(SST_CWR.then(&SST_CCWR), SSTransform::identity());
+        let full_turn = SST_CCWR.then(&SST_CCWR).then(&SST_CCWR).then(&SST_CCWR);
+        assert_eq!(full_turn, SSTransform::identity());
+    }
+}
@@ -2,18 +2,24 @@
 //! CanvasSpace <-> ViewportSpace <-> SchematicSpace 
 //! CanvasSpace is the UI canvas coordinate
 //! ViewportSpace is the schematic coordinate in f32
-//! SchematicSpace is the schematic coordinate in i16
+//! SchematicSpace is the schematic coordinate in integers, see `SSCoord`
 
-use crate::transforms::{Point, CSPoint, VSPoint, SSPoint, VCTransform, CVTransform, VSBox, CSBox, VSVec, CSVec};
+use crate::transforms::{Point, CSPoint, VSPoint, SSPoint, SS_COORD_LIMIT, VCTransform, CVTransform, VSBox, CSBox, VSVec, CSVec};
 use iced::widget::canvas::path::Builder;
 use iced::widget::canvas::{
     stroke, LineCap, Path, Stroke, LineDash, Frame, Text, Event,
 };
 use iced::Color;
 
+use crate::appearance::Palette;
+use circe::config::{AutoPan, CursorShape, CursorStyle, GridStyle};
+use circe::schematic::ViewRecord;
+
 #[derive(Clone, Debug)]
 pub enum ViewportState {
     Panning(CSPoint),
+    /// panning by dragging in the minimap
+    Minimap,
     NewView(VSPoint, VSPoint),
     None,
 }
@@ -30,6 +36,18 @@ pub struct Viewport {
     scale: f32,
 
     curpos: (CSPoint, VSPoint, SSPoint),
+    /// true if the cursor is outside of the schematic coordinate range and its schematic position was clamped
+    curpos_clamped: bool,
+    /// false while the snap-off modifier (alt) is held, allowing off-grid placement where permitted
+    snap: bool,
+    /// true while ctrl is held, pixel scrolling then zooms instead of panning
+    ctrl: bool,
+    /// fingers on a touch surface and their last position in canvas space, a two finger gesture pans and pinch-zooms
+    fingers: Vec<(iced::touch::Finger, CSPoint)>,
+    /// if true, the scale after fitting bounds is snapped down to a power of two, so the grid aligns to whole pixels
+    pub snap_fit_zoom: bool,
+    /// size of the canvas as of the last event, none before the first
+    canvas_size: Option<CSVec>,
 }
 
 impl Default for Viewport {
@@ -40,6 +58,12 @@ impl Default for Viewport {
             scale: 10.0,  // scale from canvas to viewport, sqrt of transform determinant. Save value to save computing power
 
             curpos: (CSPoint::origin(), VSPoint::origin(), SSPoint::origin()),
+            curpos_clamped: false,
+            snap: true,
+            ctrl: false,
+            fingers: vec![],
+            snap_fit_zoom: false,
+            canvas_size: None,
         }
     }
 }
@@ -49,6 +73,10 @@ impl Viewport {
     const MAX_SCALING: f32 = 100.0;  
     /// most zoomed out - every 1.0 unit is 1.0 pixels
     const MIN_SCALING: f32 = 1.;  
+    /// length of the scale bar in pixels
+    const SCALE_BAR_LEN: f32 = 100.0;
+    /// number of divisions in the scale bar
+    const SCALE_BAR_TICKS: u8 = 4;
 
     /// mutate viewport based on event
     pub fn events_handler(
@@ -58,24 +86,65 @@ impl Viewport {
         bounds: iced::Rectangle
     ) -> (Option<crate::Msg>, bool, bool) {
         self.curpos_update(curpos_csp);
+        if let Event::Keyboard(iced::keyboard::Event::ModifiersChanged(modifiers)) = event {
+            self.snap = !modifiers.alt();
+            self.ctrl = modifiers.control();
+        }
 
         let mut msg = None;
         let mut clear_passive = false;
         let mut processed = true;
         let mut state = self.state.clone();
         match (&mut state, event) {
+            // trackpads scroll by pixels: two finger scrolling pans, unless ctrl is held
+            (
+                _, 
+                Event::Mouse(iced::mouse::Event::WheelScrolled{delta: iced::mouse::ScrollDelta::Pixels { x, y }})
+            ) if !self.ctrl => {
+                self.pan(self.cv_transform().transform_vector(CSVec::new(x, y)));
+                clear_passive = true;
+            },
             // zooming
             (
                 _, 
                 Event::Mouse(iced::mouse::Event::WheelScrolled{delta})
             ) => { match delta {
-                iced::mouse::ScrollDelta::Lines { y, .. } | iced::mouse::ScrollDelta::Pixels { y, .. } => { 
+                iced::mouse::ScrollDelta::Lines { y, .. } => { 
                     let scale = 1.0 + y.clamp(-5.0, 5.0) / 5.;
                     self.zoom(scale);
-                }}
-                msg = Some(crate::Msg::NewZoom(self.vc_scale()));
+                },
+                iced::mouse::ScrollDelta::Pixels { y, .. } => { 
+                    let scale = 1.0 + y.clamp(-50.0, 50.0) / 50.;
+                    self.zoom(scale);
+                },
+                }
+                msg = Some(crate::Msg::NewZoom);
                 clear_passive = true;
             },
+            // touch gestures
+            (
+                _, 
+                Event::Touch(iced::touch::Event::FingerPressed { id, position })
+            ) => {
+                self.fingers.retain(|f| f.0 != id);
+                self.fingers.push((id, CSPoint::new(position.x - bounds.x, position.y - bounds.y)));
+            },
+            (
+                _, 
+                Event::Touch(iced::touch::Event::FingerMoved { id, position })
+            ) => {
+                let csp = CSPoint::new(position.x - bounds.x, position.y - bounds.y);
+                if self.touch_moved(id, csp) {
+                    msg = Some(crate::Msg::NewZoom);
+                    clear_passive = true;
+                }
+            },
+            (
+                _, 
+                Event::Touch(iced::touch::Event::FingerLifted { id, .. } | iced::touch::Event::FingerLost { id, .. })
+            ) => {
+                self.fingers.retain(|f| f.0 != id);
+            },
             // panning
             (
                 ViewportState::None, 
@@ -134,7 +203,7 @@ impl Viewport {
                         VSBox::from_points([vsp0, vsp1])
                     );
                 }
-                msg = Some(crate::Msg::NewZoom(self.vc_scale()));
+                msg = Some(crate::Msg::NewZoom);
                 state = ViewportState::None;
                 clear_passive = true;
             },
@@ -165,6 +234,15 @@ impl Viewport {
         self.curpos.2
     }
 
+    /// cursor position for placement, off grid only with the snap-off modifier and if snapping is not required
+    pub fn curpos_placement(&self, snap_required: bool) -> VSPoint {
+        if self.snap || snap_required {
+            self.curpos.2.cast().cast_unit()
+        } else {
+            self.curpos.1
+        }
+    }
+
     /// returns transform and scale such that VSBox (viewport/schematic bounds) fit inside CSBox (canvas bounds)
     fn bounds_transform(csb: CSBox, vsb: VSBox) -> (VCTransform, f32) {
         let mut vct = VCTransform::identity();
@@ -181,15 +259,90 @@ impl Viewport {
     /// change transform such that VSBox (viewport/schematic bounds) fit inside CSBox (canvas bounds)
     pub fn display_bounds(&mut self, csb: CSBox, vsb: VSBox) {
         (self.transform, self.scale) = Viewport::bounds_transform(csb, vsb);
+        if self.snap_fit_zoom {
+            self.zoom_at(Viewport::nice_scale(self.scale) / self.scale, csb.center());
+        }
         // recalculate cursor in viewport, or it will be wrong until cursor is moved
         self.curpos_update(self.curpos.0);
     }
 
+    /// returns the largest power of two not above scale within the scaling limits, 
+    /// rounding down so that fitted bounds remain fully visible
+    fn nice_scale(scale: f32) -> f32 {
+        2f32.powf(scale.log2().floor()).clamp(Viewport::MIN_SCALING, Viewport::MAX_SCALING)
+    }
+
     /// pan by vector v
     pub fn pan(&mut self, v: VSVec) {
         self.transform = self.transform.pre_translate(v);
     }
 
+    /// returns the area of the schematic visible on the canvas of bounds csb
+    pub fn visible_vsb(&self, csb: CSBox) -> VSBox {
+        self.cv_transform().outer_transformed_box(&csb)
+    }
+
+    /// returns the view of the canvas of bounds csb, the point at its center and the zoom, to be saved
+    pub fn view(&self, csb: CSBox) -> ViewRecord {
+        ViewRecord { center: self.cv_transform().transform_point(csb.center()), scale: self.scale }
+    }
+
+    /// restore a saved view on the canvas of bounds csb, with the zoom clamped to the scaling limits. 
+    /// Returns false and leaves the view unchanged if the view is not finite or centered outside of the coordinate range.
+    pub fn restore_view(&mut self, view: ViewRecord, csb: CSBox) -> bool {
+        let limit = SS_COORD_LIMIT as f32;
+        if !(view.scale.is_finite() && view.scale > 0.0 && view.center.x.abs() <= limit && view.center.y.abs() <= limit) {
+            return false;
+        }
+        let s = view.scale.clamp(Viewport::MIN_SCALING, Viewport::MAX_SCALING);
+        let vct = VCTransform::identity().then_scale(s, -s);
+        self.transform = vct.then_translate(csb.center() - vct.transform_point(view.center));
+        self.scale = s;
+        self.curpos_update(self.curpos.0);
+        true
+    }
+
+    /// pan the view such that vsp is at the center of the canvas of bounds csb
+    pub fn center_on(&mut self, vsp: VSPoint, csb: CSBox) {
+        let center = self.cv_transform().transform_point(csb.center());
+        self.pan(center - vsp);
+        self.curpos_update(self.curpos.0);
+    }
+
+    /// keep the point at the center of the canvas in place when the canvas is resized to csb. 
+    /// Returns true if the canvas size changed since the last call.
+    pub fn resize(&mut self, csb: CSBox) -> bool {
+        let size = csb.max - csb.min;
+        let prev = self.canvas_size.replace(size);
+        match prev {
+            Some(prev) if prev != size => {
+                self.transform = self.transform.then_translate((size - prev) / 2.0);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// pan the view toward the canvas edges within settings.margin of curpos_csp, by settings.speed pixels. 
+    /// csb is the canvas bounds. Returns true if the view was panned.
+    pub fn auto_pan(&mut self, curpos_csp: CSPoint, csb: CSBox, settings: &AutoPan) -> bool {
+        let towards = |p: f32, min: f32, max: f32| {
+            if p < min + settings.margin {
+                settings.speed
+            } else if p > max - settings.margin {
+                -settings.speed
+            } else {
+                0.0
+            }
+        };
+        let v = CSVec::new(towards(curpos_csp.x, csb.min.x, csb.max.x), towards(curpos_csp.y, csb.min.y, csb.max.y));
+        if settings.margin <= 0.0 || v == CSVec::zero() {
+            return false;
+        }
+        self.pan(self.cv_transform().transform_vector(v));
+        true
+    }
+
     /// return the canvas to viewport space transform
     pub fn cv_transform(&self) -> CVTransform {
         self.transform.inverse().unwrap()
@@ -212,16 +365,75 @@ impl Viewport {
         1. / self.scale
     }
 
-    /// update the cursor position
+    /// converts vsp to the nearest point in schematic space. 
+    /// Points outside of the schematic coordinate range are clamped to the range, in which case the returned flag is true.
+    fn vsp_to_ssp(vsp: VSPoint) -> (SSPoint, bool) {
+        let (min, max) = (-SS_COORD_LIMIT as f32, SS_COORD_LIMIT as f32);
+        let rounded = vsp.round();
+        let clamped = VSPoint::new(rounded.x.clamp(min, max), rounded.y.clamp(min, max));
+        (clamped.cast().cast_unit(), clamped != rounded)
+    }
+
+    /// update the cursor position. The position is clamped to the schematic coordinate range.
     pub fn curpos_update(&mut self, csp1: CSPoint) {
         let vsp1 = self.cv_transform().transform_point(csp1);
-        let ssp1: SSPoint = vsp1.round().cast().cast_unit();
+        let (ssp1, clamped) = Viewport::vsp_to_ssp(vsp1);
+        let vsp1 = if clamped {ssp1.cast().cast_unit()} else {vsp1};
         self.curpos = (csp1, vsp1, ssp1);
+        self.curpos_clamped = clamped;
+    }
+
+    /// warn in the top left corner if the cursor is out of schematic range
+    pub fn draw_range_warning(&self, frame: &mut Frame) {
+        if self.curpos_clamped {
+            frame.fill_text(Text {
+                content: format!("cursor outside of schematic range (±{}), placement is clamped", SS_COORD_LIMIT),
+                position: iced::Point::new(10.0, 10.0),
+                color: Color::from_rgb(1.0, 0.3, 0.3),
+                size: 16.0,
+                ..Default::default()
+            });
+        }
+    }
+
+    /// move finger id to csp. While exactly two fingers are down, pans by the movement of their centroid and
+    /// zooms by the change in their distance, anchored at the centroid. Returns true if the view changed.
+    fn touch_moved(&mut self, id: iced::touch::Finger, csp: CSPoint) -> bool {
+        let Some(i) = self.fingers.iter().position(|f| f.0 == id) else {
+            return false;
+        };
+        if self.fingers.len() != 2 {
+            self.fingers[i].1 = csp;
+            return false;
+        }
+        let (a0, b0) = (self.fingers[0].1, self.fingers[1].1);
+        self.fingers[i].1 = csp;
+        let (a1, b1) = (self.fingers[0].1, self.fingers[1].1);
+
+        let (c0, c1) = (a0.lerp(b0, 0.5), a1.lerp(b1, 0.5));
+        self.pan(self.cv_transform().transform_vector(c1 - c0));
+        let (d0, d1) = ((b0 - a0).length(), (b1 - a1).length());
+        if d0 > 0.0 && d1 > 0.0 {
+            self.zoom_at(d1 / d0, c1);
+        }
+        self.curpos_update(self.curpos.0);
+        true
     }
 
-    /// change the viewport zoom by scale
+    /// change the viewport zoom by scale, anchored at the cursor
     pub fn zoom(&mut self, scale: f32) {
         let (csp, vsp, _) = self.curpos;
+        self.zoom_anchored(scale, csp, vsp);
+    }
+
+    /// change the viewport zoom by scale, anchored at csp
+    fn zoom_at(&mut self, scale: f32, csp: CSPoint) {
+        let vsp = self.cv_transform().transform_point(csp);
+        self.zoom_anchored(scale, csp, vsp);
+    }
+
+    /// change the viewport zoom by scale such that vsp remains at csp
+    fn zoom_anchored(&mut self, scale: f32, csp: CSPoint, vsp: VSPoint) {
         let scaled_transform = self.transform.then_scale(scale, scale);
 
         let mut new_transform;  // transform with applied scale and translated to maintain p_viewport position
@@ -243,38 +455,161 @@ impl Viewport {
         self.scale = self.transform.determinant().abs().sqrt();
     }
 
-    /// draw the cursor onto canvas
-    pub fn draw_cursor(&self, frame: &mut Frame) {
+    /// draw the cursor onto canvas in the given style. If simple, a box cursor is filled rather than outlined, which is cheaper to draw.
+    pub fn draw_cursor(&self, frame: &mut Frame, snap_required: bool, simple: bool, style: &CursorStyle) {
+        let [r, g, b] = style.color;
+        let color = Color::from_rgb(r, g, b);
         let cursor_stroke = || -> Stroke {
             Stroke {
                 width: 1.0,
-                style: stroke::Style::Solid(Color::from_rgb(1.0, 0.9, 0.0)),
+                style: stroke::Style::Solid(color),
                 line_cap: LineCap::Round,
                 ..Stroke::default()
             }
         };
-        let curdim = 5.0;
-        let csp = self.vc_transform().transform_point(self.curpos.2.cast().cast_unit());
+        let curdim = style.size;
+        let csp = self.vc_transform().transform_point(self.curpos_placement(snap_required));
         let csp_topleft = csp - CSVec::from([curdim/2.; 2]);
         let s = iced::Size::from([curdim, curdim]);
-        let c = Path::rectangle(iced::Point::from([csp_topleft.x, csp_topleft.y]), s);
-        frame.stroke(&c, cursor_stroke());
+        match style.shape {
+            CursorShape::Box if simple => {
+                frame.fill_rectangle(iced::Point::from([csp_topleft.x, csp_topleft.y]), s, color);
+            },
+            CursorShape::Box => {
+                let c = Path::rectangle(iced::Point::from([csp_topleft.x, csp_topleft.y]), s);
+                frame.stroke(&c, cursor_stroke());
+            },
+            CursorShape::Dot => {
+                frame.fill(&Path::circle(Point::from(csp).into(), curdim / 2.0), color);
+            },
+            CursorShape::Crosshair | CursorShape::FullCrosshair => {
+                let (x0, x1, y0, y1) = if style.shape == CursorShape::FullCrosshair {
+                    (0.0, frame.width(), 0.0, frame.height())
+                } else {
+                    (csp.x - curdim, csp.x + curdim, csp.y - curdim, csp.y + curdim)
+                };
+                let mut path_builder = Builder::new();
+                path_builder.move_to(iced::Point::new(x0, csp.y));
+                path_builder.line_to(iced::Point::new(x1, csp.y));
+                path_builder.move_to(iced::Point::new(csp.x, y0));
+                path_builder.line_to(iced::Point::new(csp.x, y1));
+                frame.stroke(&path_builder.build(), cursor_stroke());
+            },
+        }
+    }
+
+    /// returns the unit canvas directions of the schematic +x and +y axes with their gizmo labels
+    fn gizmo_axes(&self) -> [(CSVec, &'static str); 2] {
+        [(VSVec::new(1.0, 0.0), "x"), (VSVec::new(0.0, 1.0), "N")].map(|(v, label)| {
+            (self.vc_transform().transform_vector(v).normalize(), label)
+        })
+    }
+
+    /// draw the orientation gizmo in the bottom left corner of the canvas in the palette foreground color. 
+    /// Arrows point in the schematic +x and +y directions, the bar underneath spans one grid unit at the current zoom.
+    pub fn draw_gizmo(&self, frame: &mut Frame, bb_canvas: CSBox, palette: &Palette) {
+        let color = palette.foreground_alpha(0.8);
+        let stroke = Stroke {
+            width: 1.5,
+            style: stroke::Style::Solid(color),
+            line_cap: LineCap::Round,
+            ..Stroke::default()
+        };
+        let arrow_len = 20.0;
+        let center = CSPoint::new(bb_canvas.min.x + 30.0, bb_canvas.max.y - 40.0);
+
+        let mut path_builder = Builder::new();
+        for (dir, label) in self.gizmo_axes() {
+            let tip = center + dir * arrow_len;
+            let side = CSVec::new(-dir.y, dir.x) * 3.0;
+            path_builder.move_to(Point::from(center).into());
+            path_builder.line_to(Point::from(tip).into());
+            path_builder.move_to(Point::from(tip - dir * 5.0 + side).into());
+            path_builder.line_to(Point::from(tip).into());
+            path_builder.line_to(Point::from(tip - dir * 5.0 - side).into());
+            frame.fill_text(Text {
+                content: String::from(label),
+                position: Point::from(tip + dir * 4.0 - CSVec::new(3.0, 6.0)).into(),
+                color,
+                size: 12.0,
+                ..Default::default()
+            });
+        }
+        // one grid unit
+        let bar_start = center + CSVec::new(-10.0, 20.0);
+        let bar_end = bar_start + CSVec::new(self.vc_scale(), 0.0);
+        path_builder.move_to(Point::from(bar_start - CSVec::new(0.0, 3.0)).into());
+        path_builder.line_to(Point::from(bar_start).into());
+        path_builder.line_to(Point::from(bar_end).into());
+        path_builder.line_to(Point::from(bar_end - CSVec::new(0.0, 3.0)).into());
+        frame.stroke(&path_builder.build(), stroke);
+        frame.fill_text(Text {
+            content: String::from("1"),
+            position: Point::from(bar_end + CSVec::new(4.0, -7.0)).into(),
+            color,
+            size: 12.0,
+            ..Default::default()
+        });
     }
 
-    /// draw the schematic grid onto canvas
-    pub fn draw_grid(&self, frame: &mut Frame, bb_canvas: CSBox) {
+    /// returns the scale bar ticks as (offset along the bar in pixels, label). Every other tick is labeled with the schematic units it spans.
+    fn scale_bar_ticks(&self) -> Vec<(f32, Option<String>)> {
+        let units = Viewport::SCALE_BAR_LEN * self.cv_scale();
+        (0..=Viewport::SCALE_BAR_TICKS).map(|i| {
+            let frac = i as f32 / Viewport::SCALE_BAR_TICKS as f32;
+            (Viewport::SCALE_BAR_LEN * frac, (i % 2 == 0).then(|| format!("{:.1}", units * frac)))
+        }).collect()
+    }
+
+    /// draw a scale bar in the bottom right corner of the canvas in the palette foreground color.
+    /// The bar has a fixed length in pixels, tick labels show the schematic units it spans at the current zoom.
+    pub fn draw_scale_bar(&self, frame: &mut Frame, bb_canvas: CSBox, palette: &Palette) {
+        let color = palette.foreground_alpha(0.8);
+        let stroke = Stroke {
+            width: 1.5,
+            style: stroke::Style::Solid(color),
+            line_cap: LineCap::Square,
+            ..Stroke::default()
+        };
+        let start = CSPoint::new(bb_canvas.max.x - Viewport::SCALE_BAR_LEN - 30.0, bb_canvas.max.y - 20.0);
+
+        let mut path_builder = Builder::new();
+        path_builder.move_to(Point::from(start).into());
+        path_builder.line_to(Point::from(start + CSVec::new(Viewport::SCALE_BAR_LEN, 0.0)).into());
+        for (offset, label) in self.scale_bar_ticks() {
+            let tick = start + CSVec::new(offset, 0.0);
+            let tick_len = if label.is_some() {6.0} else {3.0};
+            path_builder.move_to(Point::from(tick).into());
+            path_builder.line_to(Point::from(tick - CSVec::new(0.0, tick_len)).into());
+            if let Some(content) = label {
+                frame.fill_text(Text {
+                    content,
+                    position: Point::from(tick - CSVec::new(6.0, 20.0)).into(),
+                    color,
+                    size: 12.0,
+                    ..Default::default()
+                });
+            }
+        }
+        frame.stroke(&path_builder.build(), stroke);
+    }
+
+    /// draw the schematic grid anchored at origin onto canvas in the palette foreground color, and mark the coordinate origin.
+    /// The fine grid is only drawn if fine is true. Snapping rounds to whole schematic units, which an origin in schematic space keeps on the grid, so only the drawn grid moves with origin.
+    pub fn draw_grid(&self, frame: &mut Frame, bb_canvas: CSBox, fine: bool, style: GridStyle, origin: SSPoint, palette: &Palette) {
         let a = Text {
             content: String::from("origin"),
             position: Point::from(self.vc_transform().transform_point(VSPoint::origin())).into(),
-            color: Color::from_rgba(1.0, 1.0, 1.0, 1.0),
+            color: palette.foreground,
             size: self.vc_scale(),
             ..Default::default()
         };
         frame.fill_text(a);
 
-        fn draw_grid_w_spacing(spacing: f32, bb_canvas: CSBox, vct: VCTransform, cvt: CVTransform, frame: &mut Frame, stroke: Stroke) {
-            let bb_viewport = cvt.outer_transformed_box(&bb_canvas);
-            let v = ((bb_viewport.min / spacing).round() * spacing) - bb_viewport.min;
+        /// draws columns at spacing, dashed into dots by stroke. If rows, also draws rows at spacing, for full grid lines.
+        fn draw_grid_w_spacing(spacing: f32, bb_canvas: CSBox, vct: VCTransform, frame: &mut Frame, stroke: Stroke, rows: bool, origin: VSPoint) {
+            let bb_viewport = vct.inverse().unwrap().outer_transformed_box(&bb_canvas);
+            let v = origin + ((bb_viewport.min - origin) / spacing).round() * spacing - bb_viewport.min;
             let bb_viewport = bb_viewport.translate(v);
 
             let v = bb_viewport.max - bb_viewport.min;
@@ -287,7 +622,21 @@ impl Viewport {
                 );
                 frame.stroke(&c, stroke.clone());
             }
+            if !rows {
+                return;
+            }
+            for row in 0..=(v.y.ceil() / spacing) as u32 {
+                let csp0 = bb_viewport.min + VSVec::from([0.0, row as f32 * spacing]);
+                let csp1 = bb_viewport.min + VSVec::from([v.x.ceil(), row as f32 * spacing]);
+                let c = Path::line(
+                    Point::from(vct.transform_point(csp0)).into(), 
+                    Point::from(vct.transform_point(csp1)).into()
+                );
+                frame.stroke(&c, stroke.clone());
+            }
         }
+        let lines = style == GridStyle::Lines;
+        let origin: VSPoint = origin.cast().cast_unit();
         let coarse_grid_threshold: f32 = 2.0;
         let fine_grid_threshold: f32 = 6.0;
 
@@ -295,47 +644,65 @@ impl Viewport {
             // draw coarse grid
             let spacing = 16.;
 
-            let grid_stroke = Stroke {
-                width: (0.5 * self.vc_scale()).clamp(0.5, 3.0),
-                style: stroke::Style::Solid(Color::from_rgba(1.0, 1.0, 1.0, 0.5)),
-                line_cap: LineCap::Round,
-                line_dash: LineDash{segments: &[0.0, spacing * self.vc_scale()], offset: 0},
-                ..Stroke::default()
+            let grid_stroke = if lines {
+                Stroke {
+                    width: 1.0,
+                    style: stroke::Style::Solid(palette.foreground_alpha(0.25)),
+                    ..Stroke::default()
+                }
+            } else {
+                Stroke {
+                    width: (0.5 * self.vc_scale()).clamp(0.5, 3.0),
+                    style: stroke::Style::Solid(palette.foreground_alpha(0.5)),
+                    line_cap: LineCap::Round,
+                    line_dash: LineDash{segments: &[0.0, spacing * self.vc_scale()], offset: 0},
+                    ..Stroke::default()
+                }
             };
 
             draw_grid_w_spacing(
                 spacing, 
                 bb_canvas, 
                 self.vc_transform(), 
-                self.cv_transform(),
                 frame, 
                 grid_stroke,
+                lines,
+                origin,
             );
 
-            if self.vc_scale() > fine_grid_threshold {  // draw fine grid if sufficiently zoomed in
+            if fine && self.vc_scale() > fine_grid_threshold {  // draw fine grid if sufficiently zoomed in
                 let spacing = 2.;
         
-                let grid_stroke = Stroke {
-                    width: 1.0,
-                    style: stroke::Style::Solid(Color::from_rgba(1.0, 1.0, 1.0, 0.5)),
-                    line_cap: LineCap::Round,
-                    line_dash: LineDash{segments: &[0.0, spacing * self.vc_scale()], offset: 0},
-                    ..Stroke::default()
+                let grid_stroke = if lines {
+                    Stroke {
+                        width: 0.5,
+                        style: stroke::Style::Solid(palette.foreground_alpha(0.15)),
+                        ..Stroke::default()
+                    }
+                } else {
+                    Stroke {
+                        width: 1.0,
+                        style: stroke::Style::Solid(palette.foreground_alpha(0.5)),
+                        line_cap: LineCap::Round,
+                        line_dash: LineDash{segments: &[0.0, spacing * self.vc_scale()], offset: 0},
+                        ..Stroke::default()
+                    }
                 };
         
                 draw_grid_w_spacing(
                     spacing, 
                     bb_canvas, 
                     self.vc_transform(), 
-                    self.cv_transform(),
                     frame, 
                     grid_stroke,
+                    lines,
+                    origin,
                 );
             } 
         }
         let ref_stroke = Stroke {
             width: (0.1 * self.vc_scale()).clamp(0.1, 3.0),
-            style: stroke::Style::Solid(Color::from_rgba(1.0, 1.0, 1.0, 0.5)),
+            style: stroke::Style::Solid(palette.foreground_alpha(0.5)),
             line_cap: LineCap::Round,
             ..Stroke::default()
         };
@@ -348,6 +715,188 @@ impl Viewport {
         let p = self.vc_transform().transform_point(VSPoint::origin());
         let r = self.vc_scale() * 0.5;
         path_builder.circle(Point::from(p).into(), r);
-        frame.stroke(&path_builder.build(), ref_stroke);
+        frame.stroke(&path_builder.build(), ref_stroke.clone());
+
+        // a moved grid origin is marked by a cross without circle, to tell it from the coordinate origin
+        if origin != VSPoint::origin() {
+            let mut path_builder = Builder::new();
+            for (a, b) in [(VSVec::new(-1.0, -1.0), VSVec::new(1.0, 1.0)), (VSVec::new(-1.0, 1.0), VSVec::new(1.0, -1.0))] {
+                path_builder.move_to(Point::from(self.vc_transform().transform_point(origin + a * 0.5)).into());
+                path_builder.line_to(Point::from(self.vc_transform().transform_point(origin + b * 0.5)).into());
+            }
+            frame.stroke(&path_builder.build(), ref_stroke);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// asserts that points a and b are within rounding error of each other
+    fn assert_close<U>(a: euclid::Point2D<f32, U>, b: euclid::Point2D<f32, U>) {
+        assert!((a - b).length() < 1e-3, "{:?} is not close to {:?}", a, b);
+    }
+
+    /// canvas of width by height pixels at the origin
+    fn canvas(width: f32, height: f32) -> CSBox {
+        CSBox::new(CSPoint::origin(), CSPoint::new(width, height))
+    }
+
+    /// bounds of a 100 by 100 pixel canvas widget
+    fn bounds() -> iced::Rectangle {
+        iced::Rectangle::new(iced::Point::ORIGIN, iced::Size::new(100.0, 100.0))
+    }
+
+    #[test]
+    fn vsp_to_ssp_clamps_at_coordinate_extremes() {
+        assert_eq!(Viewport::vsp_to_ssp(VSPoint::new(1.4, -2.6)), (SSPoint::new(1, -3), false));
+        assert_eq!(Viewport::vsp_to_ssp(VSPoint::new(40000.0, -40000.0)), (SSPoint::new(40000, -40000), false));
+        let limit = SS_COORD_LIMIT as f32;
+        assert_eq!(Viewport::vsp_to_ssp(VSPoint::new(limit, -limit)), (SSPoint::new(SS_COORD_LIMIT, -SS_COORD_LIMIT), false));
+        assert_eq!(Viewport::vsp_to_ssp(VSPoint::new(2.0 * limit, 0.0)), (SSPoint::new(SS_COORD_LIMIT, 0), true));
+        assert_eq!(Viewport::vsp_to_ssp(VSPoint::new(0.0, -1e12)), (SSPoint::new(0, -SS_COORD_LIMIT), true));
+    }
+
+    #[test]
+    fn cursor_far_outside_range_is_clamped() {
+        let mut viewport = Viewport::default();
+        viewport.curpos_update(CSPoint::new(1e10, -1e10));
+        assert_eq!(viewport.curpos_ssp(), SSPoint::new(SS_COORD_LIMIT, SS_COORD_LIMIT));
+        assert!(viewport.curpos_clamped);
+        viewport.curpos_update(CSPoint::new(10.0, -10.0));
+        assert_eq!(viewport.curpos_ssp(), SSPoint::new(1, 1));
+        assert!(!viewport.curpos_clamped);
+    }
+
+    #[test]
+    fn alt_places_off_grid_unless_snap_is_required() {
+        let mut viewport = Viewport::default();
+        let modifiers = |m| Event::Keyboard(iced::keyboard::Event::ModifiersChanged(m));
+        let csp = CSPoint::new(14.0, -6.0);
+        assert_eq!(viewport.curpos_placement(false), VSPoint::origin());
+        viewport.events_handler(modifiers(iced::keyboard::Modifiers::ALT), csp, bounds());
+        assert_close(viewport.curpos_placement(false), VSPoint::new(1.4, 0.6));
+        assert_eq!(viewport.curpos_placement(true), VSPoint::new(1.0, 1.0));
+        viewport.events_handler(modifiers(iced::keyboard::Modifiers::empty()), csp, bounds());
+        assert_eq!(viewport.curpos_placement(false), VSPoint::new(1.0, 1.0));
+    }
+
+    #[test]
+    fn gizmo_points_north_up_at_any_zoom() {
+        let mut viewport = Viewport::default();
+        let axes = viewport.gizmo_axes();
+        assert_eq!(axes, [(CSVec::new(1.0, 0.0), "x"), (CSVec::new(0.0, -1.0), "N")]);
+        viewport.zoom_at(3.0, CSPoint::new(40.0, 20.0));
+        assert_eq!(viewport.gizmo_axes(), axes);
+    }
+
+    #[test]
+    fn scale_bar_labels_follow_zoom() {
+        let mut viewport = Viewport::default();
+        let labels = |viewport: &Viewport| viewport.scale_bar_ticks().into_iter().filter_map(|(_, l)| l).collect::<Vec<_>>();
+        assert_eq!(viewport.scale_bar_ticks().iter().map(|(offset, _)| *offset).collect::<Vec<_>>(), [0.0, 25.0, 50.0, 75.0, 100.0]);
+        assert_eq!(labels(&viewport), ["0.0", "5.0", "10.0"]);
+        viewport.zoom_at(2.0, CSPoint::new(40.0, 20.0));
+        assert_eq!(labels(&viewport), ["0.0", "2.5", "5.0"]);
+    }
+
+    #[test]
+    fn zooming_reports_the_new_zoom() {
+        let mut viewport = Viewport::default();
+        let scroll = |delta| Event::Mouse(iced::mouse::Event::WheelScrolled { delta });
+        let (msg, ..) = viewport.events_handler(scroll(iced::mouse::ScrollDelta::Lines { x: 0.0, y: 1.0 }), CSPoint::new(50.0, 50.0), bounds());
+        assert!(matches!(msg, Some(crate::Msg::NewZoom)));
+        assert!((viewport.vc_scale() - 12.0).abs() < 1e-3);
+        let (msg, ..) = viewport.events_handler(scroll(iced::mouse::ScrollDelta::Pixels { x: 5.0, y: 5.0 }), CSPoint::new(50.0, 50.0), bounds());
+        assert!(msg.is_none(), "panning keeps the zoom");
+    }
+
+    #[test]
+    fn pixel_scroll_pans_without_zooming() {
+        let mut viewport = Viewport::default();
+        let scroll = Event::Mouse(iced::mouse::Event::WheelScrolled { delta: iced::mouse::ScrollDelta::Pixels { x: 20.0, y: -10.0 } });
+        viewport.events_handler(scroll, CSPoint::origin(), bounds());
+        assert_eq!(viewport.vc_scale(), 10.0);
+        assert_close(viewport.vc_transform().transform_point(VSPoint::origin()), CSPoint::new(20.0, -10.0));
+    }
+
+    #[test]
+    fn pinch_zooms_at_finger_centroid() {
+        let mut viewport = Viewport::default();
+        let bounds = iced::Rectangle::new(iced::Point::new(10.0, 10.0), iced::Size::new(100.0, 100.0));
+        let finger = |e| Event::Touch(e);
+        let (f0, f1) = (iced::touch::Finger(0), iced::touch::Finger(1));
+        viewport.events_handler(finger(iced::touch::Event::FingerPressed { id: f0, position: iced::Point::new(40.0, 60.0) }), CSPoint::origin(), bounds);
+        viewport.events_handler(finger(iced::touch::Event::FingerPressed { id: f1, position: iced::Point::new(60.0, 60.0) }), CSPoint::origin(), bounds);
+        let anchor = viewport.cv_transform().transform_point(CSPoint::new(40.0, 50.0));
+        viewport.events_handler(finger(iced::touch::Event::FingerMoved { id: f1, position: iced::Point::new(80.0, 60.0) }), CSPoint::origin(), bounds);
+        // spreading one finger doubles the distance and moves the centroid by 10 pixels
+        assert!((viewport.vc_scale() - 20.0).abs() < 1e-3);
+        let moved = viewport.vc_transform().transform_point(anchor);
+        assert_close(moved, CSPoint::new(50.0, 50.0));
+
+        viewport.events_handler(finger(iced::touch::Event::FingerLifted { id: f1, position: iced::Point::new(80.0, 60.0) }), CSPoint::origin(), bounds);
+        viewport.events_handler(finger(iced::touch::Event::FingerMoved { id: f0, position: iced::Point::new(0.0, 0.0) }), CSPoint::origin(), bounds);
+        assert!((viewport.vc_scale() - 20.0).abs() < 1e-3);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn fitted_zoom_snaps_to_power_of_two() {
+        let mut viewport = Viewport::default();
+        let csb = canvas(300.0, 200.0);
+        let vsb = VSBox::new(VSPoint::new(-5.0, -5.0), VSPoint::new(5.0, 5.0));
+        viewport.display_bounds(csb, vsb);
+        assert_eq!(viewport.vc_scale(), 20.0);
+        viewport.snap_fit_zoom = true;
+        viewport.display_bounds(csb, vsb);
+        assert!((viewport.vc_scale() - 16.0).abs() < 1e-3);
+        // the fitted bounds stay centered
+        assert_close(viewport.vc_transform().transform_point(VSPoint::origin()), CSPoint::new(150.0, 100.0));
+    }
+
+    #[test]
+    fn resizing_keeps_center_in_place() {
+        let mut viewport = Viewport::default();
+        assert!(!viewport.resize(canvas(100.0, 80.0)));
+        let center = viewport.cv_transform().transform_point(CSPoint::new(50.0, 40.0));
+        assert!(viewport.resize(canvas(300.0, 60.0)));
+        assert_close(viewport.cv_transform().transform_point(CSPoint::new(150.0, 30.0)), center);
+        assert_eq!(viewport.vc_scale(), 10.0);
+        assert!(!viewport.resize(canvas(300.0, 60.0)));
+    }
+
+    #[test]
+    fn saved_view_is_restored_on_another_canvas() {
+        let mut viewport = Viewport::default();
+        let csb = canvas(200.0, 100.0);
+        viewport.pan(VSVec::new(-7.0, 3.0));
+        viewport.zoom_at(2.0, csb.center());
+        let view = viewport.view(csb);
+
+        let mut restored = Viewport::default();
+        let larger = canvas(400.0, 300.0);
+        assert!(restored.restore_view(view, larger));
+        assert_close(restored.view(larger).center, view.center);
+        assert_eq!(restored.vc_scale(), view.scale);
+
+        assert!(restored.restore_view(ViewRecord { scale: 1000.0, ..view }, larger));
+        assert_eq!(restored.vc_scale(), Viewport::MAX_SCALING);
+        assert!(!restored.restore_view(ViewRecord { scale: f32::NAN, ..view }, larger));
+        assert!(!restored.restore_view(ViewRecord { center: VSPoint::new(1e9, 0.0), ..view }, larger));
+        assert_eq!(restored.vc_scale(), Viewport::MAX_SCALING);
+    }
+
+    #[test]
+    fn auto_pan_near_edges_only() {
+        let mut viewport = Viewport::default();
+        let csb = canvas(200.0, 100.0);
+        let settings = AutoPan { margin: 20.0, speed: 10.0 };
+        assert!(!viewport.auto_pan(CSPoint::new(100.0, 50.0), csb, &settings));
+        let vsp = viewport.cv_transform().transform_point(CSPoint::new(100.0, 50.0));
+        // near the right edge: the view reveals more to the right, so content moves left
+        assert!(viewport.auto_pan(CSPoint::new(195.0, 50.0), csb, &settings));
+        assert_close(viewport.vc_transform().transform_point(vsp), CSPoint::new(90.0, 50.0));
+        assert!(!viewport.auto_pan(CSPoint::new(195.0, 50.0), csb, &AutoPan { margin: 0.0, ..settings }));
+    }
+}